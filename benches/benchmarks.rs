@@ -3,7 +3,7 @@
 use anyhow::{anyhow, Result};
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use guitar_tab_generator::{
-    arrangement::{self, create_arrangements, BeatVec, Line},
+    arrangement::{self, create_arrangements, BeatVec, Line, StringCosts},
     guitar::{create_string_tuning, Guitar, STD_6_STRING_TUNING_OPEN_PITCHES},
     parser::parse_lines,
     pitch::Pitch,
@@ -277,11 +277,18 @@ fn bench_create_single_composition_scaling(c: &mut Criterion) {
             tuning_name: "standard".to_owned(),
             guitar_num_frets: 18,
             guitar_capo: 0,
+            guitar_scale_length: None,
             num_arrangements: 1,
             width: 40,
             padding: 2,
             playback_index: Some(12),
             open_string_cost: 0,
+            string_costs: StringCosts::default(),
+            pick_style: None,
+            input_mode: guitar_tab_generator::parser::InputMode::Absolute,
+            show_chord_names: false,
+            show_picking_fingers: false,
+            show_hand_positions: false,
         };
 
         // group
@@ -308,11 +315,18 @@ fn bench_create_single_composition_large_scaling(c: &mut Criterion) {
             tuning_name: "standard".to_owned(),
             guitar_num_frets: 18,
             guitar_capo: 0,
+            guitar_scale_length: None,
             num_arrangements: 1,
             width: 40,
             padding: 2,
             playback_index: Some(12),
             open_string_cost: 0,
+            string_costs: StringCosts::default(),
+            pick_style: None,
+            input_mode: guitar_tab_generator::parser::InputMode::Absolute,
+            show_chord_names: false,
+            show_picking_fingers: false,
+            show_hand_positions: false,
         };
 
         // group
@@ -30,10 +30,7 @@ fn main() -> Result<()> {
         A3"
     .to_string();
 
-    let lines: Vec<Line<Vec<Pitch>>> = match parse_lines(input) {
-        Ok(input_lines) => input_lines,
-        Err(e) => return Err(std::sync::Arc::try_unwrap(e).unwrap()),
-    };
+    let lines: Vec<Line<Vec<Pitch>>> = parse_lines(input)?;
 
     let tuning = create_string_tuning(&[
         Pitch::E4,
@@ -50,10 +47,7 @@ fn main() -> Result<()> {
     // dbg!(&guitar);
 
     let num_arrangements = 1;
-    let arrangements = match create_arrangements(guitar.clone(), lines, num_arrangements, 0) {
-        Ok(arrangements) => arrangements,
-        Err(e) => return Err(std::sync::Arc::try_unwrap(e).unwrap()),
-    };
+    let arrangements = create_arrangements(guitar.clone(), lines, num_arrangements, 0)?;
 
     // dbg!(&arrangements);
 
@@ -24,11 +24,18 @@ fn main() {
         tuning_name: "standard".to_owned(),
         guitar_num_frets: 18,
         guitar_capo: 0,
+        guitar_scale_length: None,
         num_arrangements: 1,
         width: 55,
         padding: 2,
         playback_index: Some(12),
         open_string_cost: 0,
+        string_costs: guitar_tab_generator::arrangement::StringCosts::default(),
+        pick_style: None,
+        input_mode: guitar_tab_generator::parser::InputMode::Absolute,
+        show_chord_names: false,
+        show_picking_fingers: false,
+        show_hand_positions: false,
     };
 
     let compositions = guitar_tab_generator::wrapper_create_arrangements(input).unwrap();
@@ -0,0 +1,19 @@
+use anyhow::Result;
+use guitar_tab_generator::schema::json_schemas;
+use std::{fs, path::Path};
+
+extern crate guitar_tab_generator;
+
+/// Writes the JSON schemas of the serializable library types to the `schema` directory.
+fn main() -> Result<()> {
+    let schema_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+    fs::create_dir_all(&schema_dir)?;
+
+    for (file_name, schema) in json_schemas() {
+        let schema_path = schema_dir.join(file_name);
+        fs::write(&schema_path, serde_json::to_string_pretty(&schema)? + "\n")?;
+        println!("Wrote {}", schema_path.display());
+    }
+
+    Ok(())
+}
@@ -1,27 +1,82 @@
 use crate::{
-    guitar::{generate_pitch_fingerings, Guitar, PitchFingering},
+    error::{Error, Result},
+    guitar::{
+        generate_harmonic_fingerings, generate_pitch_fingerings, Guitar, Harmonic, PitchFingering,
+    },
+    picking::{alternate_pick_directions, is_outside_picking, PickDirection, PickStyle},
     pitch::Pitch,
+    string_number::StringNumber,
+    technique::Technique,
 };
-use anyhow::{anyhow, Result};
 use average::Mean;
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use pathfinding::prelude::yen;
-use std::{collections::HashSet, sync::Arc};
-
-#[derive(Debug)]
-pub struct InvalidInput {
-    value: String,
-    line_number: u16,
-}
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashSet};
+use strum_macros::EnumString;
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(
+    Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, JsonSchema,
+)]
 pub enum Line<T> {
     MeasureBreak,
     Rest,
     Playable(T),
+    /// Start of a repeated passage (`|:`).
+    RepeatStart,
+    /// End of a repeated passage (`:|`), which is played again from the previous repeat start.
+    RepeatEnd,
+    /// Start of a numbered ending of a repeated passage (e.g. `1.`).
+    Ending(u8),
+    /// Start of a named section of the song (e.g. `[Verse]`).
+    Section(String),
+}
+use Line::{Ending, MeasureBreak, Playable, RepeatEnd, RepeatStart, Rest, Section};
+impl<T> Line<T> {
+    /// Whether the line is a beat (a playable line or a rest) rather than a measure break or a
+    /// marker of the song structure.
+    pub fn is_beat(&self) -> bool {
+        matches!(self, Playable(_) | Rest)
+    }
+
+    /// Converts the contents of a playable line, keeping every other line as it is.
+    pub fn map_playable<U>(&self, f: impl FnOnce(&T) -> U) -> Line<U> {
+        match self {
+            MeasureBreak => MeasureBreak,
+            Rest => Rest,
+            Playable(contents) => Playable(f(contents)),
+            RepeatStart => RepeatStart,
+            RepeatEnd => RepeatEnd,
+            Ending(ending_number) => Ending(*ending_number),
+            Section(name) => Section(name.clone()),
+        }
+    }
+}
+#[cfg(test)]
+mod test_line {
+    use super::*;
+
+    #[test]
+    fn is_beat() {
+        assert!(Line::Playable(vec![Pitch::E2]).is_beat());
+        assert!(Line::<BeatVec<Pitch>>::Rest.is_beat());
+        assert!(!Line::<BeatVec<Pitch>>::MeasureBreak.is_beat());
+        assert!(!Line::<BeatVec<Pitch>>::Section("Verse".to_owned()).is_beat());
+    }
+    #[test]
+    fn map_playable() {
+        assert_eq!(
+            Line::Playable(vec![Pitch::E2]).map_playable(|pitches| pitches.len()),
+            Line::Playable(1)
+        );
+        assert_eq!(
+            Line::<BeatVec<Pitch>>::Ending(2).map_playable(|pitches| pitches.len()),
+            Line::Ending(2)
+        );
+    }
 }
-use Line::{MeasureBreak, Playable, Rest};
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 enum Node {
@@ -35,6 +90,50 @@ enum Node {
     },
 }
 
+#[cfg(test)]
+mod test_helpers {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    /// Creates a note from `(string number, fret)` fingerings on the default guitar.
+    pub(super) fn note(line_index: u16, fingerings: &[(u8, u8)]) -> Node {
+        voiced_note(line_index, fingerings, vec![])
+    }
+
+    /// Creates a note from `(string number, fret)` fingerings on the default guitar, assigned to
+    /// the given voices.
+    pub(super) fn voiced_note(
+        line_index: u16,
+        fingerings: &[(u8, u8)],
+        voices: BeatVec<u8>,
+    ) -> Node {
+        let guitar = Guitar::default();
+        let pitch_fingerings = fingerings
+            .iter()
+            .map(|&(string_number, fret)| {
+                let string_number = StringNumber::new(string_number).unwrap();
+                PitchFingering {
+                    pitch: guitar.tuning[&string_number]
+                        .plus_offset(fret as i16)
+                        .unwrap(),
+                    string_number,
+                    fret,
+                    harmonic: None,
+                }
+            })
+            .collect_vec();
+
+        Node::Note {
+            line_index,
+            beat_fingering_combo: BeatFingeringCombo::new(
+                pitch_fingerings.iter().collect_vec(),
+                &guitar,
+            )
+            .with_voices(voices),
+        }
+    }
+}
+
 pub type PitchVec<T> = Vec<T>;
 pub type BeatVec<T> = Vec<T>;
 
@@ -45,9 +144,13 @@ pub struct BeatFingeringCombo {
     avg_non_zero_fret: Option<OrderedFloat<f32>>,
     uses_open_string: bool,
     non_zero_fret_span: u8,
+    fret_stretch_mm: Option<OrderedFloat<f32>>,
+    /// The voice of every fingering, from 0 for the lowest voice, or empty if the beat is not
+    /// divided into voices.
+    voices: BeatVec<u8>,
 }
 impl BeatFingeringCombo {
-    pub fn new(beat_fingering_candidate: BeatVec<&PitchFingering>) -> Self {
+    pub fn new(beat_fingering_candidate: BeatVec<&PitchFingering>, guitar: &Guitar) -> Self {
         BeatFingeringCombo {
             fingering_combo: beat_fingering_candidate
                 .clone()
@@ -56,9 +159,39 @@ impl BeatFingeringCombo {
                 .collect(),
             avg_non_zero_fret: calc_avg_non_zero_fret(&beat_fingering_candidate),
             uses_open_string: has_open_string(&beat_fingering_candidate),
+            fret_stretch_mm: calc_fret_stretch_mm(&beat_fingering_candidate, guitar)
+                .map(OrderedFloat),
             non_zero_fret_span: calc_fret_span(beat_fingering_candidate).unwrap_or(0),
+            voices: vec![],
         }
     }
+
+    /// Assigns the fingerings to voices, in the order of the fingerings.
+    pub fn with_voices(mut self, voices: BeatVec<u8>) -> Self {
+        self.voices = voices;
+        self
+    }
+
+    /// Finds the average non-zero fret of the fingerings of every voice, from the lowest voice.
+    /// Returns nothing if the beat is not divided into voices.
+    fn voice_avg_non_zero_frets(&self) -> Vec<Option<OrderedFloat<f32>>> {
+        let Some(&max_voice) = self.voices.iter().max() else {
+            return vec![];
+        };
+        (0..=max_voice)
+            .map(|voice| {
+                calc_avg_non_zero_fret(
+                    &self
+                        .fingering_combo
+                        .iter()
+                        .zip(&self.voices)
+                        .filter(|(_, &fingering_voice)| fingering_voice == voice)
+                        .map(|(fingering, _)| fingering)
+                        .collect_vec(),
+                )
+            })
+            .collect_vec()
+    }
 }
 
 fn has_open_string(beat_fingering_candidate: &[&PitchFingering]) -> bool {
@@ -76,6 +209,7 @@ mod test_create_beat_fingering_combo {
             pitch: Pitch::A0,
             string_number: StringNumber::new(1).unwrap(),
             fret: 2,
+            harmonic: None,
         };
 
         let BeatFingeringCombo {
@@ -83,11 +217,14 @@ mod test_create_beat_fingering_combo {
             avg_non_zero_fret,
             non_zero_fret_span,
             uses_open_string: _,
-        } = BeatFingeringCombo::new(vec![&pitch_fingering_1]);
+            fret_stretch_mm,
+            voices: _,
+        } = BeatFingeringCombo::new(vec![&pitch_fingering_1], &Guitar::default());
 
         assert_eq!(fingering_combo, vec![pitch_fingering_1]);
         assert_eq!(avg_non_zero_fret, Some(OrderedFloat(2.0)));
         assert_eq!(non_zero_fret_span, 0);
+        assert_eq!(fret_stretch_mm, None);
     }
     #[test]
     fn complex() {
@@ -95,21 +232,25 @@ mod test_create_beat_fingering_combo {
             pitch: Pitch::A0,
             string_number: StringNumber::new(1).unwrap(),
             fret: 2,
+            harmonic: None,
         };
         let pitch_fingering_2 = PitchFingering {
             pitch: Pitch::B1,
             string_number: StringNumber::new(2).unwrap(),
             fret: 5,
+            harmonic: None,
         };
         let pitch_fingering_3 = PitchFingering {
             pitch: Pitch::C2,
             string_number: StringNumber::new(3).unwrap(),
             fret: 0,
+            harmonic: None,
         };
         let pitch_fingering_4 = PitchFingering {
             pitch: Pitch::D3,
             string_number: StringNumber::new(4).unwrap(),
             fret: 1,
+            harmonic: None,
         };
 
         let BeatFingeringCombo {
@@ -117,12 +258,17 @@ mod test_create_beat_fingering_combo {
             avg_non_zero_fret,
             non_zero_fret_span,
             uses_open_string: _,
-        } = BeatFingeringCombo::new(vec![
-            &pitch_fingering_1,
-            &pitch_fingering_2,
-            &pitch_fingering_3,
-            &pitch_fingering_4,
-        ]);
+            fret_stretch_mm,
+            voices: _,
+        } = BeatFingeringCombo::new(
+            vec![
+                &pitch_fingering_1,
+                &pitch_fingering_2,
+                &pitch_fingering_3,
+                &pitch_fingering_4,
+            ],
+            &Guitar::default().with_scale_length(648).unwrap(),
+        );
 
         assert_eq!(
             fingering_combo,
@@ -135,6 +281,7 @@ mod test_create_beat_fingering_combo {
         );
         assert_eq!(avg_non_zero_fret, Some(OrderedFloat(8.0 / 3.0)));
         assert_eq!(non_zero_fret_span, 4);
+        assert!((fret_stretch_mm.unwrap().into_inner() - 126.2).abs() < 0.1);
     }
 }
 
@@ -163,6 +310,7 @@ mod test_calc_avg_non_zero_fret {
             pitch: Pitch::A0,
             string_number: StringNumber::new(1).unwrap(),
             fret: 2,
+            harmonic: None,
         };
 
         assert_eq!(
@@ -176,6 +324,7 @@ mod test_calc_avg_non_zero_fret {
             pitch: Pitch::A0,
             string_number: StringNumber::new(1).unwrap(),
             fret: 0,
+            harmonic: None,
         };
 
         assert_eq!(calc_avg_non_zero_fret(&[&pitch_fingering_1]), None);
@@ -186,11 +335,13 @@ mod test_calc_avg_non_zero_fret {
             pitch: Pitch::A0,
             string_number: StringNumber::new(1).unwrap(),
             fret: 0,
+            harmonic: None,
         };
         let pitch_fingering_2 = PitchFingering {
             pitch: Pitch::B2,
             string_number: StringNumber::new(2).unwrap(),
             fret: 0,
+            harmonic: None,
         };
 
         assert_eq!(
@@ -204,21 +355,25 @@ mod test_calc_avg_non_zero_fret {
             pitch: Pitch::A0,
             string_number: StringNumber::new(1).unwrap(),
             fret: 2,
+            harmonic: None,
         };
         let pitch_fingering_2 = PitchFingering {
             pitch: Pitch::B1,
             string_number: StringNumber::new(2).unwrap(),
             fret: 5,
+            harmonic: None,
         };
         let pitch_fingering_3 = PitchFingering {
             pitch: Pitch::C2,
             string_number: StringNumber::new(3).unwrap(),
             fret: 0,
+            harmonic: None,
         };
         let pitch_fingering_4 = PitchFingering {
             pitch: Pitch::D3,
             string_number: StringNumber::new(4).unwrap(),
             fret: 1,
+            harmonic: None,
         };
 
         assert_eq!(
@@ -233,16 +388,41 @@ mod test_calc_avg_non_zero_fret {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Arrangement {
     pub lines: Vec<Line<BeatVec<PitchFingering>>>,
     difficulty: i32,
     max_fret_span: u8,
+    max_fret_stretch_mm: Option<OrderedFloat<f32>>,
 }
 impl Arrangement {
+    pub fn difficulty(&self) -> i32 {
+        self.difficulty
+    }
     pub fn max_fret_span(&self) -> u8 {
         self.max_fret_span
     }
+    /// Returns the widest physical stretch in millimetres if the guitar has a scale length.
+    pub fn max_fret_stretch_mm(&self) -> Option<f32> {
+        self.max_fret_stretch_mm.map(OrderedFloat::into_inner)
+    }
+    /// Lists the position of the fretting hand (the fret of the index finger) in every line, which
+    /// persists through open strings and rests until the hand shifts (see `next_hand_position`).
+    /// Lines before the first fretted note and lines that are not beats have no position.
+    pub fn hand_positions(&self) -> Vec<Option<u8>> {
+        let mut hand_position: Option<u8> = None;
+        self.lines
+            .iter()
+            .map(|line| match line {
+                Playable(fingerings) => {
+                    hand_position = next_hand_position(hand_position, fingerings);
+                    hand_position
+                }
+                Rest => hand_position,
+                _ => None,
+            })
+            .collect_vec()
+    }
 }
 #[cfg(test)]
 mod test_max_fret_span {
@@ -254,9 +434,103 @@ mod test_max_fret_span {
             lines: vec![],
             difficulty: 4,
             max_fret_span: 5,
+            max_fret_stretch_mm: None,
         };
         assert_eq!(arrangement.max_fret_span(), 5);
     }
+    #[test]
+    fn test_difficulty() {
+        let arrangement = Arrangement {
+            lines: vec![],
+            difficulty: 4,
+            max_fret_span: 5,
+            max_fret_stretch_mm: None,
+        };
+        assert_eq!(arrangement.difficulty(), 4);
+    }
+    #[test]
+    fn test_max_fret_stretch_mm() {
+        let arrangement = Arrangement {
+            lines: vec![],
+            difficulty: 4,
+            max_fret_span: 5,
+            max_fret_stretch_mm: Some(OrderedFloat(92.5)),
+        };
+        assert_eq!(arrangement.max_fret_stretch_mm(), Some(92.5));
+    }
+    #[test]
+    fn test_hand_positions() {
+        let fingering = |fret| PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(1).unwrap(),
+            fret,
+            harmonic: None,
+        };
+        let arrangement = Arrangement {
+            lines: vec![
+                Playable(vec![fingering(0)]),
+                Playable(vec![fingering(5)]),
+                MeasureBreak,
+                Playable(vec![fingering(0)]),
+                Rest,
+                Playable(vec![fingering(7)]),
+                Playable(vec![fingering(10)]),
+            ],
+            difficulty: 4,
+            max_fret_span: 0,
+            max_fret_stretch_mm: None,
+        };
+        assert_eq!(
+            arrangement.hand_positions(),
+            vec![None, Some(5), None, Some(5), Some(5), Some(5), Some(7)]
+        );
+    }
+}
+#[cfg(test)]
+mod test_arrangement_serde {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input_lines = vec![
+            Line::Playable(vec![Pitch::E4, Pitch::A2]),
+            Line::Rest,
+            Line::MeasureBreak,
+            Line::Playable(vec![Pitch::G3]),
+        ];
+        let arrangements = create_arrangements(Guitar::default(), input_lines, 2, 0).unwrap();
+
+        let serialized = serde_json::to_string(&arrangements).unwrap();
+        let deserialized: Vec<Arrangement> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(deserialized, arrangements);
+    }
+    #[test]
+    fn field_names() {
+        let arrangement = Arrangement {
+            lines: vec![
+                Line::Playable(vec![PitchFingering {
+                    pitch: Pitch::C4,
+                    string_number: crate::string_number::StringNumber::new(2).unwrap(),
+                    fret: 1,
+                    harmonic: None,
+                }]),
+                Line::Rest,
+                Line::MeasureBreak,
+            ],
+            difficulty: 4,
+            max_fret_span: 0,
+            max_fret_stretch_mm: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&arrangement).unwrap(),
+            concat!(
+                r#"{"lines":[{"Playable":[{"string_number":2,"fret":1,"pitch":"C4"}]},"Rest","MeasureBreak"],"#,
+                r#""difficulty":4,"max_fret_span":0,"max_fret_stretch_mm":null}"#
+            )
+        );
+    }
 }
 
 use memoize::memoize;
@@ -266,18 +540,208 @@ pub fn create_arrangements(
     input_lines: Vec<Line<BeatVec<Pitch>>>,
     num_arrangements: u8,
     open_string_cost: u16,
-) -> Result<Vec<Arrangement>, Arc<anyhow::Error>> {
+) -> Result<Vec<Arrangement>> {
+    arrange(
+        guitar,
+        input_lines,
+        Articulation::default(),
+        num_arrangements,
+        open_string_cost,
+        StringCosts::default(),
+    )
+}
+
+/// The weights of the costs of moving between strings from one beat to the next, which are
+/// configured alongside the open string cost.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct StringCosts {
+    /// Cost of every string that the notes move across, which is doubled when the strings move
+    /// against the pitches (e.g. to a lower string for a higher pitch), since the fretting hand
+    /// then has to shift along the neck as well.
+    pub crossing: u16,
+    /// Additional cost of every string skipped over between two single notes, unless both are on
+    /// open strings.
+    pub skip: u16,
+}
+impl Default for StringCosts {
+    fn default() -> Self {
+        StringCosts {
+            crossing: 2,
+            skip: 50,
+        }
+    }
+}
+
+/// The objective that the fingerings of an arrangement are chosen by.
+#[derive(
+    Debug, Clone, Copy, Default, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema, EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum ArrangementStyle {
+    /// Keeps the hand in one position, moving it as little as possible.
+    #[default]
+    Position,
+    /// Plays consecutive notes on different strings so that they ring over each other, favoring
+    /// open strings (e.g. for harp-like Baroque arrangements).
+    Campanella,
+}
+
+/// How the pitches of every input line are played, aligned with the input lines and their
+/// pitches. Lines and pitches that are missing are played without a technique for a single beat.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Articulation {
+    pub style: ArrangementStyle,
+    pub techniques: Vec<BeatVec<Option<Technique>>>,
+    /// The voice of every pitch, from 0 for the lowest voice (e.g. the bass line under a melody).
+    pub voices: Vec<BeatVec<u8>>,
+    /// The number of beats that every pitch rings for, unless a later note on its string cuts it
+    /// off.
+    pub durations: Vec<BeatVec<u8>>,
+    /// How a plectrum player picks the single notes, if the arrangement should avoid string
+    /// crossings that are awkward to pick.
+    pub pick_style: Option<PickStyle>,
+    /// The fingerings pinned by the user for pitches of every line, which every arrangement uses.
+    pub pinned_fingerings: Vec<Vec<PitchFingering>>,
+}
+impl Articulation {
+    /// Keeps the articulation of the beats from the first arranged line onwards, so that it is
+    /// aligned with the line indices of the pathfinding nodes.
+    fn beats<T>(self, input_lines: &[Line<T>]) -> Articulation {
+        fn beat_values<T, V>(input_lines: &[Line<T>], values: Vec<BeatVec<V>>) -> Vec<BeatVec<V>> {
+            let first_index = first_arranged_index(input_lines);
+            input_lines
+                .iter()
+                .enumerate()
+                .zip(values.into_iter().chain(std::iter::repeat_with(Vec::new)))
+                .filter(|((line_index, line), _)| *line_index >= first_index && line.is_beat())
+                .map(|(_, values)| values)
+                .collect_vec()
+        }
+
+        Articulation {
+            style: self.style,
+            pick_style: self.pick_style,
+            techniques: beat_values(input_lines, self.techniques),
+            durations: beat_values(input_lines, self.durations),
+            voices: beat_values(input_lines, self.voices),
+            pinned_fingerings: beat_values(input_lines, self.pinned_fingerings),
+        }
+    }
+}
+#[cfg(test)]
+mod test_articulation_beats {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let input_lines: Vec<Line<BeatVec<Pitch>>> = vec![
+            Rest,
+            Playable(vec![Pitch::A2, Pitch::E4]),
+            MeasureBreak,
+            Rest,
+            Playable(vec![Pitch::B3]),
+        ];
+        let articulation = Articulation {
+            style: ArrangementStyle::Campanella,
+            techniques: vec![vec![], vec![None, Some(Technique::Vibrato)]],
+            durations: vec![vec![], vec![4, 1], vec![], vec![], vec![2]],
+            voices: vec![vec![], vec![0, 1]],
+            pick_style: Some(PickStyle::Alternate),
+            pinned_fingerings: vec![],
+        };
+
+        assert_eq!(
+            articulation.beats(&input_lines),
+            Articulation {
+                style: ArrangementStyle::Campanella,
+                techniques: vec![vec![None, Some(Technique::Vibrato)], vec![], vec![]],
+                durations: vec![vec![4, 1], vec![], vec![2]],
+                voices: vec![vec![0, 1], vec![], vec![]],
+                pick_style: Some(PickStyle::Alternate),
+                pinned_fingerings: vec![vec![], vec![], vec![]],
+            }
+        );
+    }
+}
+
+/// Checks that the number of requested arrangements is between one and the maximum.
+pub(crate) fn validate_num_arrangements(num_arrangements: u8) -> Result<()> {
     const MAX_NUM_ARRANGEMENTS: u8 = 20;
     match num_arrangements {
-        1..=MAX_NUM_ARRANGEMENTS => (),
-        0 => return Err(Arc::new(anyhow!("No arrangements were requested."))),
-        _ => {
-            return Err(Arc::new(anyhow!(
-                "Too many arrangements to calculate. The maximum is {}.",
-                MAX_NUM_ARRANGEMENTS
-            )))
-        }
-    };
+        1..=MAX_NUM_ARRANGEMENTS => Ok(()),
+        0 => Err(Error::NoArrangementsRequested),
+        _ => Err(Error::TooManyArrangementsRequested {
+            max: MAX_NUM_ARRANGEMENTS,
+        }),
+    }
+}
+#[cfg(test)]
+mod test_validate_num_arrangements {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        assert!(validate_num_arrangements(1).is_ok());
+        assert!(validate_num_arrangements(20).is_ok());
+    }
+    #[test]
+    fn none_requested() {
+        assert_eq!(
+            validate_num_arrangements(0).unwrap_err(),
+            Error::NoArrangementsRequested
+        );
+    }
+    #[test]
+    fn too_many_requested() {
+        assert_eq!(
+            validate_num_arrangements(21).unwrap_err(),
+            Error::TooManyArrangementsRequested { max: 20 }
+        );
+    }
+}
+
+/// Creates arrangements like `create_arrangements`, where the articulation of the pitches of every
+/// input line affects their fingerings:
+/// - legato techniques (hammer-ons, pull-offs and slides) must reach a pitch of the next beat on
+///   the same string and bends cannot be played on open strings or as harmonics;
+/// - playing a note on a string that is still ringing with an earlier note that has not reached
+///   the end of its duration cuts that note off, which is costed;
+/// - with strict alternate picking, string crossings that need outside picking are costed. Economy
+///   picking sweeps across the strings, so it never needs outside picking;
+/// - every voice is played on higher strings than the voices below it, and lines where that is
+///   impossible are reported with `Error::UnorderedVoices`;
+/// - pinned fingerings are used by every arrangement.
+#[memoize(Capacity: 10)]
+pub fn create_arrangements_with_articulation(
+    guitar: Guitar,
+    input_lines: Vec<Line<BeatVec<Pitch>>>,
+    articulation: Articulation,
+    num_arrangements: u8,
+    open_string_cost: u16,
+    string_costs: StringCosts,
+) -> Result<Vec<Arrangement>> {
+    arrange(
+        guitar,
+        input_lines,
+        articulation,
+        num_arrangements,
+        open_string_cost,
+        string_costs,
+    )
+}
+
+/// Calculates the arrangements of `create_arrangements_with_articulation` without memoization,
+/// so that both memoized entry points share it.
+fn arrange(
+    guitar: Guitar,
+    input_lines: Vec<Line<BeatVec<Pitch>>>,
+    articulation: Articulation,
+    num_arrangements: u8,
+    open_string_cost: u16,
+    string_costs: StringCosts,
+) -> Result<Vec<Arrangement>> {
+    validate_num_arrangements(num_arrangements)?;
 
     let input_playable_lines = input_lines
         .iter()
@@ -289,64 +753,108 @@ pub fn create_arrangements(
                 lines: vec![],
                 difficulty: 0,
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
             };
             num_arrangements as usize
         ];
         return Ok(empty_compositions);
     }
 
-    let first_playable_index = input_lines
-        .iter()
-        .position(|line| matches!(line, Line::Playable(_)))
-        .unwrap_or(0);
-
-    let lines = input_lines
-        .iter()
-        .skip(first_playable_index)
-        .cloned()
-        .collect_vec();
-
+    // Validate all lines so that the line numbers of impossible pitches match the input
     let pitch_fingering_candidates: Vec<Line<BeatVec<PitchVec<PitchFingering>>>> =
-        validate_fingerings(&guitar, &lines)?;
+        validate_fingerings(&guitar, &input_lines)?
+            .into_iter()
+            .skip(first_arranged_index(&input_lines))
+            .collect_vec();
 
-    let measure_break_indices: Vec<usize> = pitch_fingering_candidates
-        .iter()
-        .enumerate()
-        .filter(|(.., line_candidate)| matches!(line_candidate, MeasureBreak))
-        .map(|(line_index, ..)| line_index)
-        .collect_vec();
+    let structure_lines = structure_lines(&pitch_fingering_candidates);
+    let repeats = find_repeats(&pitch_fingering_candidates);
+    let beat_articulation = articulation.beats(&input_lines);
+    let beat_pick_directions = match beat_articulation.pick_style {
+        Some(PickStyle::Alternate) => alternate_pick_directions(&pitch_fingering_candidates)
+            .into_iter()
+            .zip(&pitch_fingering_candidates)
+            .filter(|(_, line_candidate)| line_candidate.is_beat())
+            .map(|(pick_direction, _)| pick_direction)
+            .collect_vec(),
+        Some(PickStyle::Economy) | None => vec![],
+    };
 
+    let mut unordered_voices: Vec<Error> = vec![];
     let path_node_groups: Vec<BeatVec<Node>> = pitch_fingering_candidates
         .iter()
-        .filter(|line_candidate| !matches!(line_candidate, MeasureBreak))
         .enumerate()
-        .map(|(line_index, line_candidate)| match line_candidate {
-            MeasureBreak => unreachable!("Measure breaks should have been filtered out."),
-            Rest => vec![Node::Rest {
-                line_index: line_index as u16,
-            }],
-            Playable(beat_fingerings_per_pitch) => {
-                generate_fingering_combos(beat_fingerings_per_pitch)
-                    .iter()
-                    .map(|pitch_fingering_group| Node::Note {
-                        line_index: line_index as u16,
-                        beat_fingering_combo: BeatFingeringCombo::new(
-                            pitch_fingering_group.to_vec(),
-                        ),
-                    })
-                    .collect()
-            }
-        })
+        .filter(|(_, line_candidate)| line_candidate.is_beat())
+        .enumerate()
+        .map(
+            |(line_index, (candidate_index, line_candidate))| match line_candidate {
+                Rest => vec![Node::Rest {
+                    line_index: line_index as u16,
+                }],
+                Playable(beat_fingerings_per_pitch) => {
+                    let voices = &beat_articulation.voices[line_index];
+                    let all_fingering_combos = generate_fingering_combos(beat_fingerings_per_pitch);
+                    let fingering_combos = all_fingering_combos
+                        .iter()
+                        .filter(|pitch_fingering_group| {
+                            voices_ordered(pitch_fingering_group, voices)
+                        })
+                        .collect_vec();
+                    if fingering_combos.is_empty() && !all_fingering_combos.is_empty() {
+                        unordered_voices.push(Error::UnorderedVoices {
+                            line_number: first_arranged_index(&input_lines) + candidate_index + 1,
+                            voices: voice_pitches(beat_fingerings_per_pitch, voices),
+                        });
+                    }
+
+                    fingering_combos
+                        .iter()
+                        .filter(|pitch_fingering_group| {
+                            techniques_playable(
+                                pitch_fingering_group,
+                                &beat_articulation.techniques[line_index],
+                            ) && pinned_fingerings_used(
+                                pitch_fingering_group,
+                                &beat_articulation.pinned_fingerings[line_index],
+                            )
+                        })
+                        .map(|pitch_fingering_group| Node::Note {
+                            line_index: line_index as u16,
+                            beat_fingering_combo: BeatFingeringCombo::new(
+                                pitch_fingering_group.to_vec(),
+                                &guitar,
+                            )
+                            .with_voices(beat_articulation.voices[line_index].clone()),
+                        })
+                        .collect()
+                }
+                _ => unreachable!("Lines that are not beats should have been filtered out."),
+            },
+        )
         .collect();
 
+    if let Some(error) = Error::from_errors(unordered_voices) {
+        return Err(error);
+    }
+
     let num_path_node_groups = path_node_groups.len();
 
     let path_nodes: Vec<Node> = path_node_groups.into_iter().flatten().collect_vec();
 
-    let path_results: Vec<(Vec<Node>, i32)> = yen(
-        &Node::Start,
-        |current_node| calc_next_nodes(current_node, path_nodes.clone(), open_string_cost),
-        |current_node| match current_node {
+    let path_results: Vec<(Vec<SearchNode>, i32)> = yen(
+        &SearchNode::start(),
+        |search_node| {
+            calc_next_search_nodes(
+                search_node,
+                path_nodes.clone(),
+                open_string_cost,
+                string_costs,
+                &repeats,
+                &beat_articulation,
+                &beat_pick_directions,
+            )
+        },
+        |search_node| match &search_node.node {
             Node::Start => false,
             Node::Rest { line_index } | Node::Note { line_index, .. } => {
                 // Pathfinding goal is reached when the node is in the last node group
@@ -358,13 +866,17 @@ pub fn create_arrangements(
     // dbg!(&path_results);
 
     if path_results.is_empty() {
-        return Err(Arc::new(anyhow!("No arrangements could be calculated.")));
+        return Err(Error::NoArrangementsCalculated);
     }
 
     let arrangements = path_results
         .into_iter()
-        .map(|path_result| {
-            process_path(path_result.0, path_result.1, measure_break_indices.clone())
+        .map(|(search_path, path_difficulty)| {
+            let path_nodes = search_path
+                .into_iter()
+                .map(|search_node| search_node.node)
+                .collect_vec();
+            process_path(path_nodes, path_difficulty, structure_lines.clone())
         })
         .collect_vec();
 
@@ -377,6 +889,28 @@ mod test_create_arrangements {
     use super::*;
     use crate::string_number::StringNumber;
 
+    const NO_STRING_COSTS: StringCosts = StringCosts {
+        crossing: 0,
+        skip: 0,
+    };
+
+    /// Returns the string numbers of the fingerings of every playable line of an arrangement.
+    fn string_numbers(arrangement: &Arrangement) -> Vec<Vec<u8>> {
+        arrangement
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Playable(fingerings) => Some(
+                    fingerings
+                        .iter()
+                        .map(|fingering| fingering.string_number.get())
+                        .collect_vec(),
+                ),
+                _ => None,
+            })
+            .collect_vec()
+    }
+
     #[test]
     fn single_line_single_pitch() {
         let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![Line::Playable(vec![Pitch::E4])];
@@ -385,9 +919,11 @@ mod test_create_arrangements {
                 pitch: Pitch::E4,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 0,
+                harmonic: None,
             }])],
             difficulty: 0,
             max_fret_span: 0,
+            max_fret_stretch_mm: None,
         }];
 
         let arrangements = create_arrangements(Guitar::default(), input_pitches, 1, 0).unwrap();
@@ -403,36 +939,44 @@ mod test_create_arrangements {
                     pitch: Pitch::E4,
                     string_number: StringNumber::new(1).unwrap(),
                     fret: 0,
+                    harmonic: None,
                 }])],
                 difficulty: 0,
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
             },
             Arrangement {
                 lines: vec![Line::Playable(vec![PitchFingering {
                     pitch: Pitch::E4,
                     string_number: StringNumber::new(2).unwrap(),
                     fret: 5,
+                    harmonic: None,
                 }])],
                 difficulty: 5,
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
             },
             Arrangement {
                 lines: vec![Line::Playable(vec![PitchFingering {
                     pitch: Pitch::E4,
                     string_number: StringNumber::new(3).unwrap(),
                     fret: 9,
+                    harmonic: None,
                 }])],
                 difficulty: 9,
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
             },
             Arrangement {
                 lines: vec![Line::Playable(vec![PitchFingering {
                     pitch: Pitch::E4,
                     string_number: StringNumber::new(4).unwrap(),
                     fret: 14,
+                    harmonic: None,
                 }])],
                 difficulty: 14,
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
             },
         ];
 
@@ -453,12 +997,14 @@ mod test_create_arrangements {
                     pitch: Pitch::E4,
                     string_number: StringNumber::new(1).unwrap(),
                     fret: 0,
+                    harmonic: None,
                 }]),
                 Line::Rest,
                 Line::MeasureBreak,
             ],
             difficulty: 0,
             max_fret_span: 0,
+            max_fret_stretch_mm: None,
         }];
 
         let arrangements = create_arrangements(Guitar::default(), input_pitches, 1, 0).unwrap();
@@ -476,6 +1022,7 @@ mod test_create_arrangements {
                 lines: vec![],
                 difficulty: 0,
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
             };
             2
         ];
@@ -500,512 +1047,1711 @@ mod test_create_arrangements {
                     pitch: Pitch::E4,
                     string_number: StringNumber::new(1).unwrap(),
                     fret: 0,
+                    harmonic: None,
                 }]),
                 Line::Rest,
             ],
             difficulty: 0,
             max_fret_span: 0,
+            max_fret_stretch_mm: None,
         }];
 
         assert_eq!(arrangements, expected_arrangements);
     }
     #[test]
-    fn zero_arrangements_requested() {
-        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![Line::Playable(vec![Pitch::E4])];
+    fn repeat_back_transition() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Section("Intro".to_owned()),
+            Line::RepeatStart,
+            Line::Playable(vec![Pitch::G4]),
+            Line::Playable(vec![Pitch::C5]),
+            Line::RepeatEnd,
+        ];
 
-        let error = create_arrangements(Guitar::default(), input_pitches, 0, 0).unwrap_err();
-        let error_msg = format!("{error}");
-        assert_eq!(error_msg, "No arrangements were requested.");
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            Articulation::default(),
+            1,
+            0,
+            NO_STRING_COSTS,
+        )
+        .unwrap()[0]
+            .clone();
+
+        assert_eq!(
+            arrangement.lines,
+            vec![
+                Line::Section("Intro".to_owned()),
+                Line::RepeatStart,
+                Line::Playable(vec![PitchFingering {
+                    pitch: Pitch::G4,
+                    string_number: StringNumber::new(2).unwrap(),
+                    fret: 8,
+                    harmonic: None,
+                }]),
+                Line::Playable(vec![PitchFingering {
+                    pitch: Pitch::C5,
+                    string_number: StringNumber::new(1).unwrap(),
+                    fret: 8,
+                    harmonic: None,
+                }]),
+                Line::RepeatEnd,
+            ]
+        );
+        // 8 for each of the two beats and 8 to play the first beat again after the repeat
+        assert_eq!(arrangement.difficulty(), 24);
     }
     #[test]
-    fn too_many_arrangements_requested() {
-        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![Line::Playable(vec![Pitch::E4])];
+    fn second_ending_transition() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::RepeatStart,
+            Line::Playable(vec![Pitch::A5]),
+            Line::Ending(1),
+            Line::Playable(vec![Pitch::F2]),
+            Line::RepeatEnd,
+            Line::Ending(2),
+            Line::Playable(vec![Pitch::C5]),
+        ];
 
-        let error = create_arrangements(Guitar::default(), input_pitches, 22, 0).unwrap_err();
-        let error_msg = format!("{error}");
+        let arrangement = create_arrangements(Guitar::default(), input_pitches, 1, 0)
+            .unwrap()
+            .remove(0);
+        // The C5 of the second ending is played after the A5 at the 17th fret rather than after
+        // the F2 of the first ending, so it stays high on the neck
         assert_eq!(
-            error_msg,
-            "Too many arrangements to calculate. The maximum is 20."
+            arrangement.lines[6],
+            Line::Playable(vec![PitchFingering {
+                pitch: Pitch::C5,
+                string_number: StringNumber::new(3).unwrap(),
+                fret: 17,
+                harmonic: None,
+            }])
         );
     }
-}
+    #[test]
+    fn repeat_back_transition_string_crossing() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::RepeatStart,
+            Line::Playable(vec![Pitch::G4]),
+            Line::Playable(vec![Pitch::C5]),
+            Line::RepeatEnd,
+        ];
 
-/// Generates fingerings for each pitch, and returns a result containing the fingerings or
-/// an error message if any impossible pitches (with no fingerings) are found.
-///
-/// Arguments:
-///
-/// * `guitar`: A reference to a `Guitar` object, which contains information about the guitar's
-/// string ranges.
-/// * `input_pitches`: A slice of vectors, where each vector represents a beat and contains a
-/// vector of pitches.
-///
-/// Returns a `Result` containing either a
-/// `Vec<Vec<Vec<Fingering>>>` if the input pitches are valid, or an `Err` containing an error
-/// message if there are invalid pitches.
-fn validate_fingerings(
-    guitar: &Guitar,
-    input_pitches: &[Line<BeatVec<Pitch>>],
-) -> Result<Vec<Line<BeatVec<PitchVec<PitchFingering>>>>> {
-    let mut impossible_pitches: Vec<InvalidInput> = vec![];
-    let fingerings: Vec<Line<BeatVec<PitchVec<PitchFingering>>>> = input_pitches
-        .iter()
-        .enumerate()
-        .map(|(beat_index, beat_input)| match beat_input {
-            MeasureBreak => MeasureBreak,
-            Rest => Rest,
-            Playable(beat_pitches) => Playable(
-                beat_pitches
-                    .iter()
-                    .map(|beat_pitch| {
-                        let pitch_fingerings: PitchVec<PitchFingering> =
-                            generate_pitch_fingerings(&guitar.string_ranges, beat_pitch);
-                        if pitch_fingerings.is_empty() {
-                            impossible_pitches.push(InvalidInput {
-                                value: format!("{:?}", beat_pitch),
-                                line_number: (beat_index as u16) + 1,
-                            })
-                        }
-                        pitch_fingerings
-                    })
-                    .collect(),
-            ),
-        })
-        .collect();
-
-    if !impossible_pitches.is_empty() {
-        let error_msg = impossible_pitches
-            .iter()
-            .map(|invalid_input| {
-                format!(
-                    "Pitch {} on line {} cannot be played on any strings of the configured guitar.",
-                    invalid_input.value, invalid_input.line_number
-                )
-            })
-            .collect::<Vec<String>>()
-            .join("\n");
+        let arrangement =
+            create_arrangements(Guitar::default(), input_pitches, 1, 0).unwrap()[0].clone();
 
-        return Err(anyhow!(error_msg));
+        // 8 for each of the two beats, 2 to cross to the first string and 8 to play the first beat
+        // again after the repeat
+        assert_eq!(arrangement.difficulty(), 26);
     }
+    #[test]
+    fn pull_off_on_same_string() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Playable(vec![Pitch::B3]),
+            Line::Playable(vec![Pitch::A3]),
+        ];
+        let techniques = vec![vec![Some(Technique::PullOff)], vec![None]];
 
-    Ok(fingerings)
-}
-#[cfg(test)]
-mod test_validate_fingerings {
-    use super::*;
+        let articulation = Articulation {
+            techniques,
+            ..Default::default()
+        };
 
-    #[test]
-    fn valid_simple() {
-        let guitar = Guitar::default();
-        let input_pitches = vec![Playable(vec![Pitch::G3])];
-        let expected_fingerings = vec![Playable(vec![generate_pitch_fingerings(
-            &guitar.string_ranges,
-            &Pitch::G3,
-        )])];
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            articulation,
+            1,
+            0,
+            StringCosts::default(),
+        )
+        .unwrap()[0]
+            .clone();
 
         assert_eq!(
-            validate_fingerings(&guitar, &input_pitches).unwrap(),
-            expected_fingerings
+            arrangement.lines,
+            vec![
+                Line::Playable(vec![PitchFingering {
+                    pitch: Pitch::B3,
+                    string_number: StringNumber::new(3).unwrap(),
+                    fret: 4,
+                    harmonic: None,
+                }]),
+                Line::Playable(vec![PitchFingering {
+                    pitch: Pitch::A3,
+                    string_number: StringNumber::new(3).unwrap(),
+                    fret: 2,
+                    harmonic: None,
+                }]),
+            ]
         );
     }
     #[test]
-    fn valid_complex() {
-        let guitar = Guitar::default();
-        let input_pitches = vec![
-            Playable(vec![Pitch::G3]),
-            MeasureBreak,
-            Playable(vec![Pitch::B3]),
-            Rest,
-            Playable(vec![Pitch::D4, Pitch::G4]),
-        ];
-        let expected_fingerings = vec![
-            Playable(vec![generate_pitch_fingerings(
-                &guitar.string_ranges,
-                &Pitch::G3,
-            )]),
-            MeasureBreak,
-            Playable(vec![generate_pitch_fingerings(
-                &guitar.string_ranges,
-                &Pitch::B3,
-            )]),
-            Rest,
-            Playable(vec![
-                generate_pitch_fingerings(&guitar.string_ranges, &Pitch::D4),
-                generate_pitch_fingerings(&guitar.string_ranges, &Pitch::G4),
-            ]),
+    fn pinned_fingering() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Playable(vec![Pitch::E4]),
+            Line::Playable(vec![Pitch::G4]),
         ];
+        let pinned_fingering = PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(3).unwrap(),
+            fret: 9,
+            harmonic: None,
+        };
+
+        let arrangement = |articulation: Articulation| {
+            create_arrangements_with_articulation(
+                Guitar::default(),
+                input_pitches.clone(),
+                articulation,
+                1,
+                0,
+                StringCosts::default(),
+            )
+            .unwrap()[0]
+                .clone()
+        };
 
+        assert_ne!(
+            arrangement(Articulation::default()).lines[0],
+            Line::Playable(vec![pinned_fingering])
+        );
         assert_eq!(
-            validate_fingerings(&guitar, &input_pitches).unwrap(),
-            expected_fingerings
+            arrangement(Articulation {
+                pinned_fingerings: vec![vec![pinned_fingering]],
+                ..Default::default()
+            })
+            .lines[0],
+            Line::Playable(vec![pinned_fingering])
         );
     }
     #[test]
-    fn invalid_simple() {
-        let guitar = Guitar::default();
-        let input_pitches = vec![Playable(vec![Pitch::B9])];
-
-        let error = validate_fingerings(&guitar, &input_pitches).unwrap_err();
-        let error_msg = format!("{error}");
-        let expected_error_msg =
-            "Pitch B9 on line 1 cannot be played on any strings of the configured guitar.";
-        assert_eq!(error_msg, expected_error_msg);
-    }
-    #[test]
-    fn invalid_complex() {
-        let guitar = Guitar::default();
-        let input_pitches = vec![
-            Playable(vec![Pitch::A1]),
-            Playable(vec![Pitch::G3]),
-            Playable(vec![Pitch::B3]),
-            Playable(vec![Pitch::A1, Pitch::B1]),
-            Playable(vec![Pitch::G3, Pitch::D2]),
-            Playable(vec![Pitch::D4, Pitch::G4]),
+    fn sustained_note_keeps_ringing() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Playable(vec![Pitch::A2]),
+            Line::Playable(vec![Pitch::C3]),
+            Line::Playable(vec![Pitch::D3]),
         ];
 
-        let error = validate_fingerings(&guitar, &input_pitches).unwrap_err();
-        let error_msg = format!("{error}");
-        let expected_error_msg =
-            "Pitch A1 on line 1 cannot be played on any strings of the configured guitar.\n\
-            Pitch A1 on line 4 cannot be played on any strings of the configured guitar.\n\
-            Pitch B1 on line 4 cannot be played on any strings of the configured guitar.\n\
-            Pitch D2 on line 5 cannot be played on any strings of the configured guitar.";
-        assert_eq!(error_msg, expected_error_msg);
-    }
-}
+        let arrangement = create_arrangements(Guitar::default(), input_pitches.clone(), 1, 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![vec![5], vec![5], vec![5]]
+        );
 
-/// Generates all playable combinations of fingerings for all the pitches in a beat.
-fn generate_fingering_combos(
-    beat_fingerings_per_pitch: &[Vec<PitchFingering>],
-) -> Vec<BeatVec<&PitchFingering>> {
-    if beat_fingerings_per_pitch.is_empty() {
-        unreachable!("Beat pitch fingerings should not be empty.")
+        let articulation = Articulation {
+            durations: vec![vec![3]],
+            ..Default::default()
+        };
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            articulation,
+            1,
+            0,
+            StringCosts::default(),
+        )
+        .unwrap()
+        .remove(0);
+        // C3 and D3 move off the A string so that they do not cut off the ringing A2
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![vec![5], vec![6], vec![6]]
+        );
     }
+    #[test]
+    fn campanella_scale() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = [
+            Pitch::A3,
+            Pitch::B3,
+            Pitch::C4,
+            Pitch::D4,
+            Pitch::E4,
+            Pitch::FSharpGFlat4,
+            Pitch::G4,
+        ]
+        .into_iter()
+        .map(|pitch| Line::Playable(vec![pitch]))
+        .collect_vec();
 
-    beat_fingerings_per_pitch
-        .iter()
-        .multi_cartesian_product()
-        .filter(no_duplicate_strings)
-        .collect_vec()
-}
-#[cfg(test)]
-mod test_generate_fingering_combos {
-    use super::*;
-    use crate::string_number::StringNumber;
+        let arrangement = create_arrangements(Guitar::default(), input_pitches.clone(), 1, 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![
+                vec![3],
+                vec![3],
+                vec![3],
+                vec![2],
+                vec![2],
+                vec![1],
+                vec![1]
+            ]
+        );
 
-    #[test]
-    fn simple() {
-        let pitch_fingering = PitchFingering {
-            pitch: Pitch::B6,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 3,
+        let articulation = Articulation {
+            style: ArrangementStyle::Campanella,
+            ..Default::default()
         };
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches.clone(),
+            articulation.clone(),
+            1,
+            0,
+            StringCosts {
+                skip: 0,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .remove(0);
+        // No two consecutive notes share a string, so that every note rings into the next
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![
+                vec![3],
+                vec![2],
+                vec![3],
+                vec![2],
+                vec![1],
+                vec![3],
+                vec![2]
+            ]
+        );
 
-        let beat_fingerings_per_pitch = vec![vec![pitch_fingering]];
-        let expected_fingering_combos = vec![vec![&pitch_fingering]];
-
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            articulation,
+            1,
+            0,
+            StringCosts::default(),
+        )
+        .unwrap()
+        .remove(0);
+        // The last two notes stay on the highest strings instead of skipping from the first string
+        // to the third string
         assert_eq!(
-            generate_fingering_combos(&beat_fingerings_per_pitch),
-            expected_fingering_combos
+            string_numbers(&arrangement),
+            vec![
+                vec![3],
+                vec![2],
+                vec![3],
+                vec![2],
+                vec![1],
+                vec![2],
+                vec![1]
+            ]
         );
     }
     #[test]
-    fn complex() {
-        let pitch_fingering_a_string_2 = PitchFingering {
-            pitch: Pitch::B6,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 3,
-        };
-        let pitch_fingering_a_string_3 = PitchFingering {
-            pitch: Pitch::B6,
-            string_number: StringNumber::new(3).unwrap(),
-            fret: 8,
-        };
-        let pitch_fingering_b_string_2 = PitchFingering {
-            pitch: Pitch::C7,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 4,
-        };
-        let pitch_fingering_b_string_3 = PitchFingering {
-            pitch: Pitch::C7,
-            string_number: StringNumber::new(3).unwrap(),
-            fret: 9,
-        };
-        let pitch_fingering_b_string_4 = PitchFingering {
-            pitch: Pitch::C7,
-            string_number: StringNumber::new(4).unwrap(),
-            fret: 14,
+    fn string_skips() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = [Pitch::F3, Pitch::C4, Pitch::F3, Pitch::C4]
+            .into_iter()
+            .map(|pitch| Line::Playable(vec![pitch]))
+            .collect_vec();
+        let arrangement = |string_costs: StringCosts| {
+            create_arrangements_with_articulation(
+                Guitar::default(),
+                input_pitches.clone(),
+                Articulation::default(),
+                1,
+                0,
+                string_costs,
+            )
+            .unwrap()
+            .remove(0)
         };
 
-        let beat_fingerings_per_pitch = vec![
-            vec![pitch_fingering_a_string_2, pitch_fingering_a_string_3],
-            vec![
-                pitch_fingering_b_string_2,
-                pitch_fingering_b_string_3,
-                pitch_fingering_b_string_4,
-            ],
-        ];
-        let expected_fingering_combos = vec![
-            vec![&pitch_fingering_a_string_2, &pitch_fingering_b_string_3],
-            vec![&pitch_fingering_a_string_2, &pitch_fingering_b_string_4],
-            vec![&pitch_fingering_a_string_3, &pitch_fingering_b_string_2],
-            vec![&pitch_fingering_a_string_3, &pitch_fingering_b_string_4],
-        ];
-
+        let free_string_costs = StringCosts {
+            crossing: 0,
+            skip: 0,
+        };
+        // Playing the C4 notes at the first fret would shift the hand down from the third position,
+        // so the C4 notes stay on the third string even without string costs
         assert_eq!(
-            generate_fingering_combos(&beat_fingerings_per_pitch),
-            expected_fingering_combos
+            string_numbers(&arrangement(free_string_costs)),
+            vec![vec![4], vec![3], vec![4], vec![3]]
+        );
+        // The C4 notes move to the neighbouring string instead of skipping the third string
+        assert_eq!(
+            string_numbers(&arrangement(StringCosts::default())),
+            vec![vec![4], vec![3], vec![4], vec![3]]
         );
     }
-
     #[test]
-    #[should_panic]
-    fn empty_input() {
-        generate_fingering_combos(&[]);
-    }
-}
-
-#[allow(clippy::ptr_arg)]
-/// Checks if there are any duplicate strings in a vector of `Fingering`
-/// objects to ensure that all pitches can be played.
-fn no_duplicate_strings(beat_fingering_option: &Vec<&PitchFingering>) -> bool {
-    let num_pitches = beat_fingering_option.len();
-    let num_strings = beat_fingering_option
-        .iter()
-        .map(|fingering| fingering.string_number)
-        .collect::<HashSet<_>>()
-        .len();
+    fn alternate_picking_avoids_outside_picking() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> =
+            [Pitch::G3, Pitch::C4, Pitch::E4, Pitch::C4, Pitch::G3]
+                .into_iter()
+                .map(|pitch| Line::Playable(vec![pitch]))
+                .collect_vec();
 
-    num_pitches == num_strings
-}
-#[cfg(test)]
-mod test_no_duplicate_strings {
-    use super::*;
-    use crate::string_number::StringNumber;
+        let arrangement = create_arrangements(Guitar::default(), input_pitches.clone(), 1, 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![vec![3], vec![2], vec![1], vec![2], vec![3]]
+        );
 
-    #[test]
-    fn valid_simple() {
-        let fingering_1 = PitchFingering {
-            pitch: Pitch::B6,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 3,
+        let articulation = Articulation {
+            pick_style: Some(PickStyle::Alternate),
+            ..Default::default()
         };
-        let beat_fingering_option: &Vec<&PitchFingering> = &vec![&fingering_1];
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches.clone(),
+            articulation.clone(),
+            1,
+            0,
+            NO_STRING_COSTS,
+        )
+        .unwrap()
+        .remove(0);
+        // Picked down-up-down-up-down, the C4 notes stay on the third string rather than being
+        // picked upwards on the second string right after a downstroke on the third string
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![vec![3], vec![3], vec![1], vec![3], vec![3]]
+        );
 
-        assert!(no_duplicate_strings(beat_fingering_option));
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            articulation,
+            1,
+            0,
+            StringCosts::default(),
+        )
+        .unwrap()
+        .remove(0);
+        // With string costs, the E4 moves next to the C4 notes instead of skipping the second string
+        assert_eq!(
+            string_numbers(&arrangement),
+            vec![vec![3], vec![3], vec![2], vec![3], vec![3]]
+        );
     }
     #[test]
-    fn valid_complex() {
-        let fingering_1 = PitchFingering {
-            pitch: Pitch::CSharpDFlat2,
-            string_number: StringNumber::new(1).unwrap(),
-            fret: 1,
-        };
-        let fingering_2 = PitchFingering {
-            pitch: Pitch::F4,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 3,
-        };
-        let fingering_3 = PitchFingering {
-            pitch: Pitch::A5,
-            string_number: StringNumber::new(4).unwrap(),
-            fret: 4,
+    fn melody_above_bass() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> =
+            vec![Line::Playable(vec![Pitch::C3, Pitch::B2])];
+
+        let arrangement = create_arrangements(Guitar::default(), input_pitches.clone(), 1, 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(string_numbers(&arrangement), vec![vec![5, 6]]);
+
+        // The melody B2 crosses below the bass C3, so it is kept on a higher string
+        let articulation = Articulation {
+            voices: vec![vec![0, 1]],
+            ..Default::default()
         };
-        let fingering_4 = PitchFingering {
-            pitch: Pitch::DSharpEFlat6,
-            string_number: StringNumber::new(11).unwrap(),
-            fret: 0,
+        let arrangement = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            articulation,
+            1,
+            0,
+            StringCosts::default(),
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(string_numbers(&arrangement), vec![vec![6, 5]]);
+    }
+    #[test]
+    fn unordered_voices() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Playable(vec![Pitch::E2]),
+            Line::MeasureBreak,
+            Line::Playable(vec![Pitch::A4, Pitch::A2]),
+        ];
+        let articulation = Articulation {
+            voices: vec![vec![], vec![], vec![0, 1]],
+            ..Default::default()
         };
-        let beat_fingering_option: &Vec<&PitchFingering> =
-            &vec![&fingering_1, &fingering_2, &fingering_3, &fingering_4];
 
-        assert!(no_duplicate_strings(beat_fingering_option));
+        let error = create_arrangements_with_articulation(
+            Guitar::default(),
+            input_pitches,
+            articulation,
+            1,
+            0,
+            StringCosts::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            Error::UnorderedVoices {
+                line_number: 3,
+                voices: vec![vec![Pitch::A4], vec![Pitch::A2]]
+            }
+        );
+        assert_eq!(
+            format!("{error}"),
+            "The voices on line 3 (A4 | A2) cannot each be played on higher strings than the voices below them."
+        );
     }
     #[test]
-    fn invalid_simple() {
-        let fingering_1 = PitchFingering {
-            pitch: Pitch::CSharpDFlat2,
-            string_number: StringNumber::new(4).unwrap(),
-            fret: 1,
-        };
-        let fingering_2 = PitchFingering {
-            pitch: Pitch::F4,
-            string_number: StringNumber::new(4).unwrap(),
-            fret: 3,
+    fn melody_above_sustained_bass() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Playable(vec![Pitch::A2, Pitch::D3]),
+            Line::Playable(vec![Pitch::B2]),
+        ];
+        let arrangement = |voices| {
+            let articulation = Articulation {
+                durations: vec![vec![2, 1], vec![1]],
+                voices,
+                ..Default::default()
+            };
+            create_arrangements_with_articulation(
+                Guitar::default(),
+                input_pitches.clone(),
+                articulation,
+                1,
+                0,
+                StringCosts::default(),
+            )
+            .unwrap()
+            .remove(0)
         };
-        let beat_fingering_option: &Vec<&PitchFingering> = &vec![&fingering_1, &fingering_2];
 
-        assert!(!no_duplicate_strings(beat_fingering_option));
+        assert_eq!(
+            string_numbers(&arrangement(vec![])),
+            vec![vec![5, 4], vec![6]]
+        );
+        // The melody B2 stays above the bass A2 that is still ringing under it
+        assert_eq!(
+            string_numbers(&arrangement(vec![vec![0, 1], vec![1]])),
+            vec![vec![6, 4], vec![5]]
+        );
     }
     #[test]
-    fn invalid_complex() {
-        let fingering_1 = PitchFingering {
-            pitch: Pitch::CSharpDFlat2,
-            string_number: StringNumber::new(1).unwrap(),
-            fret: 1,
-        };
-        let fingering_2 = PitchFingering {
-            pitch: Pitch::F4,
-            string_number: StringNumber::new(3).unwrap(),
-            fret: 3,
-        };
-        let fingering_3 = PitchFingering {
-            pitch: Pitch::A5,
-            string_number: StringNumber::new(6).unwrap(),
-            fret: 4,
-        };
-        let fingering_4 = PitchFingering {
-            pitch: Pitch::DSharpEFlat6,
-            string_number: StringNumber::new(3).unwrap(),
-            fret: 0,
-        };
-        let beat_fingering_option: &Vec<&PitchFingering> =
-            &vec![&fingering_1, &fingering_2, &fingering_3, &fingering_4];
+    fn impossible_pitch_line_number() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![
+            Line::Rest,
+            Line::Playable(vec![Pitch::E4]),
+            Line::Playable(vec![Pitch::C1]),
+        ];
 
-        assert!(!no_duplicate_strings(beat_fingering_option));
+        let error = create_arrangements(Guitar::default(), input_pitches, 1, 0).unwrap_err();
+        assert_eq!(
+            error,
+            Error::ImpossiblePitch {
+                pitch: Pitch::C1,
+                line_number: 3,
+                span: None,
+            }
+        );
     }
     #[test]
-    fn empty_input() {
-        assert!(no_duplicate_strings(&vec![]));
+    fn zero_arrangements_requested() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![Line::Playable(vec![Pitch::E4])];
+
+        let error = create_arrangements(Guitar::default(), input_pitches, 0, 0).unwrap_err();
+        let error_msg = format!("{error}");
+        assert_eq!(error_msg, "No arrangements were requested.");
+    }
+    #[test]
+    fn too_many_arrangements_requested() {
+        let input_pitches: Vec<Line<BeatVec<Pitch>>> = vec![Line::Playable(vec![Pitch::E4])];
+
+        let error = create_arrangements(Guitar::default(), input_pitches, 22, 0).unwrap_err();
+        let error_msg = format!("{error}");
+        assert_eq!(
+            error_msg,
+            "Too many arrangements to calculate. The maximum is 20."
+        );
     }
 }
 
-/// Calculates the difference between the maximum and minimum non-zero
-/// fret numbers in a given vector of fingerings.
-fn calc_fret_span(beat_fingering_candidate: Vec<&PitchFingering>) -> Option<u8> {
-    let beat_fingering_option_fret_numbers = beat_fingering_candidate
+/// Finds the index of the first line that is not a rest or a measure break, from which
+/// arrangements start.
+pub fn first_arranged_index<T>(input_lines: &[Line<T>]) -> usize {
+    input_lines
         .iter()
-        .filter(|fingering| fingering.fret != 0)
-        .map(|fingering| fingering.fret);
+        .position(|line| !matches!(line, Rest | MeasureBreak))
+        .unwrap_or(0)
+}
+#[cfg(test)]
+mod test_first_arranged_index {
+    use super::*;
 
-    let min_non_zero_fret = match beat_fingering_option_fret_numbers.clone().min() {
-        None => return None,
-        Some(fret_num) => fret_num,
-    };
-    let max_non_zero_fret = match beat_fingering_option_fret_numbers.clone().max() {
-        None => unreachable!("A maximum should exist if a minimum exists."),
-        Some(fret_num) => fret_num,
-    };
+    #[test]
+    fn simple() {
+        let input_lines = vec![
+            Rest,
+            MeasureBreak,
+            Section("Intro".to_owned()),
+            Playable(vec![Pitch::E2]),
+        ];
+        assert_eq!(first_arranged_index(&input_lines), 2);
+        assert_eq!(first_arranged_index::<BeatVec<Pitch>>(&[Rest, Rest]), 0);
+    }
+}
 
-    Some(max_non_zero_fret - min_non_zero_fret)
+/// Finds the lines that are not beats (measure breaks and structure markers) with their indices.
+/// These lines are not part of the pathfinding and are inserted back into the arranged lines.
+fn structure_lines<T>(lines: &[Line<T>]) -> Vec<(usize, Line<BeatVec<PitchFingering>>)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.is_beat())
+        .map(|(line_index, line)| {
+            let structure_line =
+                line.map_playable(|_| unreachable!("Beats should have been filtered out."));
+            (line_index, structure_line)
+        })
+        .collect_vec()
 }
 #[cfg(test)]
-mod test_calc_fret_span {
+mod test_structure_lines {
     use super::*;
-    use crate::string_number::StringNumber;
 
     #[test]
     fn simple() {
-        let fingering_1 = PitchFingering {
-            pitch: Pitch::B6,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 3,
-        };
+        let lines = vec![
+            Section("Verse".to_owned()),
+            RepeatStart,
+            Playable(vec![Pitch::E2]),
+            Rest,
+            MeasureBreak,
+            RepeatEnd,
+        ];
+        assert_eq!(
+            structure_lines(&lines),
+            vec![
+                (0, Section("Verse".to_owned())),
+                (1, RepeatStart),
+                (4, MeasureBreak),
+                (5, RepeatEnd),
+            ]
+        );
+    }
+}
 
-        assert_eq!(calc_fret_span(vec![&fingering_1]).unwrap(), 0);
+/// A repeated passage, as the indices of its first and last beats among the beats of the lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Repeat {
+    first_beat_index: u16,
+    last_beat_index: u16,
+    /// The first beat of the first numbered ending, which is inside the passage.
+    first_ending_beat_index: Option<u16>,
+    /// The first beat of the numbered ending right after the passage, which is played instead of
+    /// the first ending after the passage is repeated.
+    second_ending_beat_index: Option<u16>,
+}
+impl Repeat {
+    /// Finds the last beat before the first ending, which the second ending is played after.
+    /// Returns nothing if the passage has no second ending or no beats before its first ending.
+    fn second_ending_entry_beat_index(&self) -> Option<u16> {
+        self.second_ending_beat_index?;
+        self.first_ending_beat_index
+            .filter(|&first_ending_beat_index| first_ending_beat_index > self.first_beat_index)
+            .map(|first_ending_beat_index| first_ending_beat_index - 1)
     }
-    #[test]
-    fn complex() {
-        let fingering_1 = PitchFingering {
-            pitch: Pitch::CSharpDFlat2,
-            string_number: StringNumber::new(1).unwrap(),
-            fret: 1,
-        };
-        let fingering_2 = PitchFingering {
-            pitch: Pitch::F4,
-            string_number: StringNumber::new(2).unwrap(),
-            fret: 3,
-        };
-        let fingering_3 = PitchFingering {
-            pitch: Pitch::A5,
-            string_number: StringNumber::new(4).unwrap(),
-            fret: 4,
-        };
-        let fingering_4 = PitchFingering {
-            pitch: Pitch::DSharpEFlat6,
-            string_number: StringNumber::new(11).unwrap(),
-            fret: 0,
-        };
-        let beat_fingering_option: Vec<&PitchFingering> =
-            vec![&fingering_1, &fingering_2, &fingering_3, &fingering_4];
+}
+#[cfg(test)]
+mod test_second_ending_entry_beat_index {
+    use super::*;
 
-        assert_eq!(calc_fret_span(beat_fingering_option).unwrap(), 3);
+    fn repeat(
+        first_ending_beat_index: Option<u16>,
+        second_ending_beat_index: Option<u16>,
+    ) -> Repeat {
+        Repeat {
+            first_beat_index: 1,
+            last_beat_index: 4,
+            first_ending_beat_index,
+            second_ending_beat_index,
+        }
     }
+
     #[test]
-    fn empty_input() {
-        assert!(calc_fret_span(vec![]).is_none());
+    fn simple() {
+        assert_eq!(
+            repeat(Some(3), Some(5)).second_ending_entry_beat_index(),
+            Some(2)
+        );
+    }
+    #[test]
+    fn without_second_ending() {
+        assert_eq!(repeat(Some(3), None).second_ending_entry_beat_index(), None);
+        assert_eq!(repeat(None, None).second_ending_entry_beat_index(), None);
+    }
+    #[test]
+    fn first_ending_at_repeat_start() {
+        assert_eq!(
+            repeat(Some(1), Some(5)).second_ending_entry_beat_index(),
+            None
+        );
     }
 }
 
-/// Calculates the next nodes and their costs based on the current node and a
-/// list of all path nodes.
-///
-/// Returns a vector of tuples, where each tuple contains a `Node` the `i32`
-/// cost of moving to that node.
-fn calc_next_nodes(
-    current_node: &Node,
-    path_nodes: Vec<Node>,
-    open_string_cost: u16,
-) -> Vec<(Node, i32)> {
-    let next_node_index = match current_node {
-        Node::Start => 0,
-        Node::Rest { line_index } | Node::Note { line_index, .. } => line_index + 1,
-    };
+/// Finds the repeated passages of the lines. A repeat end without a repeat start since the
+/// previous repeat end repeats from the beat after the previous repeat end, or from the first beat.
+fn find_repeats<T>(lines: &[Line<T>]) -> Vec<Repeat> {
+    let mut repeats: Vec<Repeat> = vec![];
+    let mut num_beats: u16 = 0;
+    let mut repeat_start_beat_index: u16 = 0;
+    let mut first_ending_beat_index: Option<u16> = None;
+    let mut after_repeat_end = false;
 
-    let next_nodes: Vec<(Node, i32)> = path_nodes
-        .iter()
-        .filter(|&node| {
-            next_node_index
-                == match node {
-                    Node::Start => unreachable!("Start should never be a future node."),
-                    Node::Rest { line_index } | Node::Note { line_index, .. } => *line_index,
+    for line in lines {
+        match line {
+            Playable(_) | Rest => {
+                num_beats += 1;
+                after_repeat_end = false;
+            }
+            RepeatStart => {
+                repeat_start_beat_index = num_beats;
+                first_ending_beat_index = None;
+            }
+            RepeatEnd => {
+                if num_beats > repeat_start_beat_index {
+                    repeats.push(Repeat {
+                        first_beat_index: repeat_start_beat_index,
+                        last_beat_index: num_beats - 1,
+                        first_ending_beat_index,
+                        second_ending_beat_index: None,
+                    });
+                    after_repeat_end = true;
                 }
-        })
-        .map(|next_node| {
-            (
-                next_node.clone(),
-                calculate_node_difficulty(current_node, next_node, open_string_cost),
-            )
-        })
-        .collect_vec();
+                repeat_start_beat_index = num_beats;
+                first_ending_beat_index = None;
+            }
+            Ending(_) if after_repeat_end => {
+                if let Some(repeat) = repeats.last_mut() {
+                    if repeat.first_ending_beat_index.is_some() {
+                        repeat.second_ending_beat_index = Some(num_beats);
+                    }
+                }
+                after_repeat_end = false;
+            }
+            Ending(_) => {
+                first_ending_beat_index.get_or_insert(num_beats);
+            }
+            MeasureBreak | Section(_) => {}
+        }
+    }
 
-    next_nodes
+    repeats
 }
 #[cfg(test)]
-mod test_calc_next_nodes {
+mod test_find_repeats {
     use super::*;
 
-    fn create_test_path_nodes() -> Vec<Node> {
-        vec![
-            Node::Note {
-                line_index: 0,
-                beat_fingering_combo: BeatFingeringCombo {
-                    fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(0.1)),
-                    non_zero_fret_span: 0,
-                    uses_open_string: false,
+    #[test]
+    fn with_endings() {
+        let lines = vec![
+            Playable(vec![Pitch::E2]),
+            RepeatStart,
+            Playable(vec![Pitch::A2]),
+            Rest,
+            Ending(1),
+            Playable(vec![Pitch::D3]),
+            RepeatEnd,
+            Ending(2),
+            Playable(vec![Pitch::G3]),
+        ];
+        assert_eq!(
+            find_repeats(&lines),
+            vec![Repeat {
+                first_beat_index: 1,
+                last_beat_index: 3,
+                first_ending_beat_index: Some(3),
+                second_ending_beat_index: Some(4),
+            }]
+        );
+    }
+    #[test]
+    fn first_ending_only() {
+        let lines = vec![
+            RepeatStart,
+            Playable(vec![Pitch::A2]),
+            Ending(1),
+            Playable(vec![Pitch::D3]),
+            RepeatEnd,
+            Playable(vec![Pitch::G3]),
+            Ending(2),
+            Playable(vec![Pitch::B3]),
+        ];
+        assert_eq!(
+            find_repeats(&lines),
+            vec![Repeat {
+                first_beat_index: 0,
+                last_beat_index: 1,
+                first_ending_beat_index: Some(1),
+                second_ending_beat_index: None,
+            }]
+        );
+    }
+    #[test]
+    fn without_repeat_start() {
+        let lines = vec![
+            Playable(vec![Pitch::E2]),
+            RepeatEnd,
+            Playable(vec![Pitch::A2]),
+            MeasureBreak,
+            RepeatEnd,
+            RepeatStart,
+            RepeatEnd,
+        ];
+        assert_eq!(
+            find_repeats(&lines),
+            vec![
+                Repeat {
+                    first_beat_index: 0,
+                    last_beat_index: 0,
+                    first_ending_beat_index: None,
+                    second_ending_beat_index: None,
                 },
-            },
-            Node::Note {
-                line_index: 0,
-                beat_fingering_combo: BeatFingeringCombo {
-                    fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(0.2)),
-                    non_zero_fret_span: 0,
-                    uses_open_string: false,
+                Repeat {
+                    first_beat_index: 1,
+                    last_beat_index: 1,
+                    first_ending_beat_index: None,
+                    second_ending_beat_index: None,
+                },
+            ]
+        );
+    }
+}
+
+/// Generates the fingering candidates of a pitch on the guitar: the fretted notes and the
+/// harmonics that the guitar considers.
+pub(crate) fn pitch_fingering_candidates(
+    guitar: &Guitar,
+    pitch: &Pitch,
+) -> PitchVec<PitchFingering> {
+    generate_pitch_fingerings(&guitar.string_ranges, pitch)
+        .into_iter()
+        .chain(generate_harmonic_fingerings(
+            &guitar.string_ranges,
+            pitch,
+            guitar.harmonics,
+        ))
+        .collect()
+}
+#[cfg(test)]
+mod test_pitch_fingering_candidates {
+    use super::*;
+    use crate::guitar::{Harmonic, HarmonicCandidates};
+
+    #[test]
+    fn includes_harmonics() {
+        let guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        let candidates = pitch_fingering_candidates(&guitar, &Pitch::E5);
+        assert!(candidates
+            .iter()
+            .any(|fingering| fingering.harmonic == Some(Harmonic::Natural)));
+
+        let candidates = pitch_fingering_candidates(&Guitar::default(), &Pitch::E5);
+        assert!(candidates
+            .iter()
+            .all(|fingering| fingering.harmonic.is_none()));
+    }
+}
+
+/// Generates fingerings for each pitch, and returns a result containing the fingerings or
+/// an error message if any impossible pitches (with no fingerings) are found.
+///
+/// Arguments:
+///
+/// * `guitar`: A reference to a `Guitar` object, which contains information about the guitar's
+///   string ranges.
+/// * `input_pitches`: A slice of vectors, where each vector represents a beat and contains a
+///   vector of pitches.
+///
+/// Returns a `Result` containing either a
+/// `Vec<Vec<Vec<Fingering>>>` if the input pitches are valid, or an `Err` containing an error
+/// message if there are invalid pitches.
+fn validate_fingerings(
+    guitar: &Guitar,
+    input_pitches: &[Line<BeatVec<Pitch>>],
+) -> Result<Vec<Line<BeatVec<PitchVec<PitchFingering>>>>> {
+    let mut impossible_pitches: Vec<Error> = vec![];
+    let fingerings: Vec<Line<BeatVec<PitchVec<PitchFingering>>>> = input_pitches
+        .iter()
+        .enumerate()
+        .map(|(beat_index, beat_input)| {
+            beat_input.map_playable(|beat_pitches| {
+                beat_pitches
+                    .iter()
+                    .map(|beat_pitch| {
+                        let pitch_fingerings = pitch_fingering_candidates(guitar, beat_pitch);
+                        if pitch_fingerings.is_empty() {
+                            impossible_pitches.push(Error::ImpossiblePitch {
+                                pitch: *beat_pitch,
+                                line_number: beat_index + 1,
+                                span: None,
+                            })
+                        }
+                        pitch_fingerings
+                    })
+                    .collect()
+            })
+        })
+        .collect();
+
+    if let Some(error) = Error::from_errors(impossible_pitches) {
+        return Err(error);
+    }
+
+    Ok(fingerings)
+}
+#[cfg(test)]
+mod test_validate_fingerings {
+    use super::*;
+    use crate::{guitar::HarmonicCandidates, string_number::StringNumber};
+
+    #[test]
+    fn harmonics() {
+        let guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        let input_pitches = vec![Playable(vec![Pitch::E6])];
+        let expected_fingerings = vec![Playable(vec![vec![PitchFingering {
+            pitch: Pitch::E6,
+            string_number: StringNumber::new(1).unwrap(),
+            fret: 5,
+            harmonic: Some(Harmonic::Natural),
+        }]])];
+
+        assert_eq!(
+            validate_fingerings(&guitar, &input_pitches).unwrap(),
+            expected_fingerings
+        );
+    }
+    #[test]
+    fn valid_simple() {
+        let guitar = Guitar::default();
+        let input_pitches = vec![Playable(vec![Pitch::G3])];
+        let expected_fingerings = vec![Playable(vec![generate_pitch_fingerings(
+            &guitar.string_ranges,
+            &Pitch::G3,
+        )])];
+
+        assert_eq!(
+            validate_fingerings(&guitar, &input_pitches).unwrap(),
+            expected_fingerings
+        );
+    }
+    #[test]
+    fn valid_complex() {
+        let guitar = Guitar::default();
+        let input_pitches = vec![
+            Playable(vec![Pitch::G3]),
+            MeasureBreak,
+            Playable(vec![Pitch::B3]),
+            Rest,
+            Playable(vec![Pitch::D4, Pitch::G4]),
+        ];
+        let expected_fingerings = vec![
+            Playable(vec![generate_pitch_fingerings(
+                &guitar.string_ranges,
+                &Pitch::G3,
+            )]),
+            MeasureBreak,
+            Playable(vec![generate_pitch_fingerings(
+                &guitar.string_ranges,
+                &Pitch::B3,
+            )]),
+            Rest,
+            Playable(vec![
+                generate_pitch_fingerings(&guitar.string_ranges, &Pitch::D4),
+                generate_pitch_fingerings(&guitar.string_ranges, &Pitch::G4),
+            ]),
+        ];
+
+        assert_eq!(
+            validate_fingerings(&guitar, &input_pitches).unwrap(),
+            expected_fingerings
+        );
+    }
+    #[test]
+    fn invalid_simple() {
+        let guitar = Guitar::default();
+        let input_pitches = vec![Playable(vec![Pitch::B9])];
+
+        let error = validate_fingerings(&guitar, &input_pitches).unwrap_err();
+        let error_msg = format!("{error}");
+        let expected_error_msg =
+            "Pitch B9 on line 1 cannot be played on any strings of the configured guitar.";
+        assert_eq!(error_msg, expected_error_msg);
+    }
+    #[test]
+    fn invalid_complex() {
+        let guitar = Guitar::default();
+        let input_pitches = vec![
+            Playable(vec![Pitch::A1]),
+            Playable(vec![Pitch::G3]),
+            Playable(vec![Pitch::B3]),
+            Playable(vec![Pitch::A1, Pitch::B1]),
+            Playable(vec![Pitch::G3, Pitch::D2]),
+            Playable(vec![Pitch::D4, Pitch::G4]),
+        ];
+
+        let error = validate_fingerings(&guitar, &input_pitches).unwrap_err();
+        let error_msg = format!("{error}");
+        let expected_error_msg =
+            "Pitch A1 on line 1 cannot be played on any strings of the configured guitar.\n\
+            Pitch A1 on line 4 cannot be played on any strings of the configured guitar.\n\
+            Pitch B1 on line 4 cannot be played on any strings of the configured guitar.\n\
+            Pitch D2 on line 5 cannot be played on any strings of the configured guitar.";
+        assert_eq!(error_msg, expected_error_msg);
+    }
+}
+
+/// Generates all playable combinations of fingerings for all the pitches in a beat.
+fn generate_fingering_combos(
+    beat_fingerings_per_pitch: &[Vec<PitchFingering>],
+) -> Vec<BeatVec<&PitchFingering>> {
+    if beat_fingerings_per_pitch.is_empty() {
+        unreachable!("Beat pitch fingerings should not be empty.")
+    }
+
+    beat_fingerings_per_pitch
+        .iter()
+        .multi_cartesian_product()
+        .filter(no_duplicate_strings)
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_generate_fingering_combos {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    #[test]
+    fn simple() {
+        let pitch_fingering = PitchFingering {
+            pitch: Pitch::B6,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+
+        let beat_fingerings_per_pitch = vec![vec![pitch_fingering]];
+        let expected_fingering_combos = vec![vec![&pitch_fingering]];
+
+        assert_eq!(
+            generate_fingering_combos(&beat_fingerings_per_pitch),
+            expected_fingering_combos
+        );
+    }
+    #[test]
+    fn complex() {
+        let pitch_fingering_a_string_2 = PitchFingering {
+            pitch: Pitch::B6,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+        let pitch_fingering_a_string_3 = PitchFingering {
+            pitch: Pitch::B6,
+            string_number: StringNumber::new(3).unwrap(),
+            fret: 8,
+            harmonic: None,
+        };
+        let pitch_fingering_b_string_2 = PitchFingering {
+            pitch: Pitch::C7,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 4,
+            harmonic: None,
+        };
+        let pitch_fingering_b_string_3 = PitchFingering {
+            pitch: Pitch::C7,
+            string_number: StringNumber::new(3).unwrap(),
+            fret: 9,
+            harmonic: None,
+        };
+        let pitch_fingering_b_string_4 = PitchFingering {
+            pitch: Pitch::C7,
+            string_number: StringNumber::new(4).unwrap(),
+            fret: 14,
+            harmonic: None,
+        };
+
+        let beat_fingerings_per_pitch = vec![
+            vec![pitch_fingering_a_string_2, pitch_fingering_a_string_3],
+            vec![
+                pitch_fingering_b_string_2,
+                pitch_fingering_b_string_3,
+                pitch_fingering_b_string_4,
+            ],
+        ];
+        let expected_fingering_combos = vec![
+            vec![&pitch_fingering_a_string_2, &pitch_fingering_b_string_3],
+            vec![&pitch_fingering_a_string_2, &pitch_fingering_b_string_4],
+            vec![&pitch_fingering_a_string_3, &pitch_fingering_b_string_2],
+            vec![&pitch_fingering_a_string_3, &pitch_fingering_b_string_4],
+        ];
+
+        assert_eq!(
+            generate_fingering_combos(&beat_fingerings_per_pitch),
+            expected_fingering_combos
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_input() {
+        generate_fingering_combos(&[]);
+    }
+}
+
+#[allow(clippy::ptr_arg)]
+/// Checks if there are any duplicate strings in a vector of `Fingering`
+/// objects to ensure that all pitches can be played.
+fn no_duplicate_strings(beat_fingering_option: &Vec<&PitchFingering>) -> bool {
+    let num_pitches = beat_fingering_option.len();
+    let num_strings = beat_fingering_option
+        .iter()
+        .map(|fingering| fingering.string_number)
+        .collect::<HashSet<_>>()
+        .len();
+
+    num_pitches == num_strings
+}
+#[cfg(test)]
+mod test_no_duplicate_strings {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    #[test]
+    fn valid_simple() {
+        let fingering_1 = PitchFingering {
+            pitch: Pitch::B6,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+        let beat_fingering_option: &Vec<&PitchFingering> = &vec![&fingering_1];
+
+        assert!(no_duplicate_strings(beat_fingering_option));
+    }
+    #[test]
+    fn valid_complex() {
+        let fingering_1 = PitchFingering {
+            pitch: Pitch::CSharpDFlat2,
+            string_number: StringNumber::new(1).unwrap(),
+            fret: 1,
+            harmonic: None,
+        };
+        let fingering_2 = PitchFingering {
+            pitch: Pitch::F4,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+        let fingering_3 = PitchFingering {
+            pitch: Pitch::A5,
+            string_number: StringNumber::new(4).unwrap(),
+            fret: 4,
+            harmonic: None,
+        };
+        let fingering_4 = PitchFingering {
+            pitch: Pitch::DSharpEFlat6,
+            string_number: StringNumber::new(11).unwrap(),
+            fret: 0,
+            harmonic: None,
+        };
+        let beat_fingering_option: &Vec<&PitchFingering> =
+            &vec![&fingering_1, &fingering_2, &fingering_3, &fingering_4];
+
+        assert!(no_duplicate_strings(beat_fingering_option));
+    }
+    #[test]
+    fn invalid_simple() {
+        let fingering_1 = PitchFingering {
+            pitch: Pitch::CSharpDFlat2,
+            string_number: StringNumber::new(4).unwrap(),
+            fret: 1,
+            harmonic: None,
+        };
+        let fingering_2 = PitchFingering {
+            pitch: Pitch::F4,
+            string_number: StringNumber::new(4).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+        let beat_fingering_option: &Vec<&PitchFingering> = &vec![&fingering_1, &fingering_2];
+
+        assert!(!no_duplicate_strings(beat_fingering_option));
+    }
+    #[test]
+    fn invalid_complex() {
+        let fingering_1 = PitchFingering {
+            pitch: Pitch::CSharpDFlat2,
+            string_number: StringNumber::new(1).unwrap(),
+            fret: 1,
+            harmonic: None,
+        };
+        let fingering_2 = PitchFingering {
+            pitch: Pitch::F4,
+            string_number: StringNumber::new(3).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+        let fingering_3 = PitchFingering {
+            pitch: Pitch::A5,
+            string_number: StringNumber::new(6).unwrap(),
+            fret: 4,
+            harmonic: None,
+        };
+        let fingering_4 = PitchFingering {
+            pitch: Pitch::DSharpEFlat6,
+            string_number: StringNumber::new(3).unwrap(),
+            fret: 0,
+            harmonic: None,
+        };
+        let beat_fingering_option: &Vec<&PitchFingering> =
+            &vec![&fingering_1, &fingering_2, &fingering_3, &fingering_4];
+
+        assert!(!no_duplicate_strings(beat_fingering_option));
+    }
+    #[test]
+    fn empty_input() {
+        assert!(no_duplicate_strings(&vec![]));
+    }
+}
+
+/// Checks that a beat fingering combination uses every fingering pinned on its line.
+fn pinned_fingerings_used(
+    beat_fingering_option: &[&PitchFingering],
+    pinned_fingerings: &[PitchFingering],
+) -> bool {
+    pinned_fingerings
+        .iter()
+        .all(|pinned_fingering| beat_fingering_option.contains(&pinned_fingering))
+}
+#[cfg(test)]
+mod test_pinned_fingerings_used {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    fn fingering(string_number: u8, fret: u8) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret,
+            harmonic: None,
+        }
+    }
+
+    #[test]
+    fn no_pins() {
+        assert!(pinned_fingerings_used(&[&fingering(1, 0)], &[]));
+    }
+    #[test]
+    fn pin_used() {
+        assert!(pinned_fingerings_used(
+            &[&fingering(2, 5)],
+            &[fingering(2, 5)]
+        ));
+    }
+    #[test]
+    fn pin_not_used() {
+        assert!(!pinned_fingerings_used(
+            &[&fingering(1, 0)],
+            &[fingering(2, 5)]
+        ));
+    }
+}
+
+/// Checks that the techniques of a beat fingering combination can be played: bends and legato
+/// techniques need fretted notes rather than harmonics, and open strings cannot be bent.
+fn techniques_playable(
+    beat_fingering_option: &[&PitchFingering],
+    techniques: &[Option<Technique>],
+) -> bool {
+    beat_fingering_option
+        .iter()
+        .zip(techniques)
+        .all(|(fingering, technique)| match technique {
+            Some(Technique::Bend(_)) => fingering.fret > 0 && fingering.harmonic.is_none(),
+            Some(technique) if technique.is_legato() => fingering.harmonic.is_none(),
+            _ => true,
+        })
+}
+#[cfg(test)]
+mod test_techniques_playable {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    fn fingering(fret: u8) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(1).unwrap(),
+            fret,
+            harmonic: None,
+        }
+    }
+    fn harmonic_fingering() -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(5).unwrap(),
+            fret: 7,
+            harmonic: Some(Harmonic::Natural),
+        }
+    }
+
+    #[test]
+    fn fretted_bend() {
+        assert!(techniques_playable(
+            &[&fingering(5)],
+            &[Some(Technique::Bend(2))]
+        ));
+    }
+    #[test]
+    fn open_string_bend() {
+        assert!(!techniques_playable(
+            &[&fingering(0)],
+            &[Some(Technique::Bend(2))]
+        ));
+        assert!(techniques_playable(
+            &[&fingering(0)],
+            &[Some(Technique::Vibrato)]
+        ));
+    }
+    #[test]
+    fn harmonic() {
+        assert!(!techniques_playable(
+            &[&harmonic_fingering()],
+            &[Some(Technique::Bend(2))]
+        ));
+        assert!(!techniques_playable(
+            &[&harmonic_fingering()],
+            &[Some(Technique::HammerOn)]
+        ));
+        assert!(techniques_playable(
+            &[&harmonic_fingering()],
+            &[Some(Technique::Vibrato)]
+        ));
+    }
+    #[test]
+    fn no_techniques() {
+        assert!(techniques_playable(&[&fingering(0)], &[]));
+    }
+}
+
+/// Checks that every voice of a beat fingering combination is played on higher strings than the
+/// voices below it (e.g. the melody above the bass line).
+fn voices_ordered(beat_fingering_option: &[&PitchFingering], voices: &[u8]) -> bool {
+    beat_fingering_option
+        .iter()
+        .zip(voices)
+        .tuple_combinations()
+        .all(
+            |((fingering_a, voice_a), (fingering_b, voice_b))| match voice_a.cmp(voice_b) {
+                Ordering::Less => fingering_a.string_number > fingering_b.string_number,
+                Ordering::Greater => fingering_a.string_number < fingering_b.string_number,
+                Ordering::Equal => true,
+            },
+        )
+}
+#[cfg(test)]
+mod test_voices_ordered {
+    use super::*;
+
+    fn fingering(string_number: u8) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret: 2,
+            harmonic: None,
+        }
+    }
+
+    #[test]
+    fn melody_above_bass() {
+        assert!(voices_ordered(
+            &[&fingering(5), &fingering(2), &fingering(1)],
+            &[0, 1, 1]
+        ));
+        assert!(voices_ordered(&[&fingering(1), &fingering(5)], &[1, 0]));
+    }
+    #[test]
+    fn melody_below_bass() {
+        assert!(!voices_ordered(&[&fingering(3), &fingering(4)], &[0, 1]));
+        assert!(!voices_ordered(
+            &[&fingering(5), &fingering(4), &fingering(6)],
+            &[0, 0, 1]
+        ));
+    }
+    #[test]
+    fn single_voice() {
+        assert!(voices_ordered(&[&fingering(3), &fingering(4)], &[]));
+        assert!(voices_ordered(&[&fingering(3), &fingering(4)], &[0, 0]));
+    }
+}
+
+/// Groups the pitches of a beat by voice, from the lowest voice.
+fn voice_pitches(
+    beat_fingerings_per_pitch: &[PitchVec<PitchFingering>],
+    voices: &[u8],
+) -> Vec<Vec<Pitch>> {
+    let pitches = beat_fingerings_per_pitch
+        .iter()
+        .filter_map(|pitch_fingerings| pitch_fingerings.first())
+        .map(|pitch_fingering| pitch_fingering.pitch);
+    pitches
+        .zip(voices)
+        .into_group_map_by(|(_, &voice)| voice)
+        .into_iter()
+        .sorted_by_key(|(voice, _)| *voice)
+        .map(|(_, voice_pitches)| {
+            voice_pitches
+                .into_iter()
+                .map(|(pitch, _)| pitch)
+                .collect_vec()
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_voice_pitches {
+    use super::*;
+    use crate::guitar::generate_pitch_fingerings;
+
+    #[test]
+    fn simple() {
+        let guitar = Guitar::default();
+        let beat_fingerings_per_pitch = [Pitch::A2, Pitch::C4, Pitch::E4]
+            .iter()
+            .map(|pitch| generate_pitch_fingerings(&guitar.string_ranges, pitch))
+            .collect_vec();
+        assert_eq!(
+            voice_pitches(&beat_fingerings_per_pitch, &[0, 1, 1]),
+            vec![vec![Pitch::A2], vec![Pitch::C4, Pitch::E4]]
+        );
+    }
+}
+
+/// Calculates the difference between the maximum and minimum non-zero
+/// fret numbers in a given vector of fingerings.
+fn calc_fret_span(beat_fingering_candidate: Vec<&PitchFingering>) -> Option<u8> {
+    let beat_fingering_option_fret_numbers = beat_fingering_candidate
+        .iter()
+        .filter(|fingering| fingering.fret != 0)
+        .map(|fingering| fingering.fret);
+
+    let min_non_zero_fret = beat_fingering_option_fret_numbers.clone().min()?;
+    let max_non_zero_fret = match beat_fingering_option_fret_numbers.clone().max() {
+        None => unreachable!("A maximum should exist if a minimum exists."),
+        Some(fret_num) => fret_num,
+    };
+
+    Some(max_non_zero_fret - min_non_zero_fret)
+}
+#[cfg(test)]
+mod test_calc_fret_span {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    #[test]
+    fn simple() {
+        let fingering_1 = PitchFingering {
+            pitch: Pitch::B6,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+
+        assert_eq!(calc_fret_span(vec![&fingering_1]).unwrap(), 0);
+    }
+    #[test]
+    fn complex() {
+        let fingering_1 = PitchFingering {
+            pitch: Pitch::CSharpDFlat2,
+            string_number: StringNumber::new(1).unwrap(),
+            fret: 1,
+            harmonic: None,
+        };
+        let fingering_2 = PitchFingering {
+            pitch: Pitch::F4,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 3,
+            harmonic: None,
+        };
+        let fingering_3 = PitchFingering {
+            pitch: Pitch::A5,
+            string_number: StringNumber::new(4).unwrap(),
+            fret: 4,
+            harmonic: None,
+        };
+        let fingering_4 = PitchFingering {
+            pitch: Pitch::DSharpEFlat6,
+            string_number: StringNumber::new(11).unwrap(),
+            fret: 0,
+            harmonic: None,
+        };
+        let beat_fingering_option: Vec<&PitchFingering> =
+            vec![&fingering_1, &fingering_2, &fingering_3, &fingering_4];
+
+        assert_eq!(calc_fret_span(beat_fingering_option).unwrap(), 3);
+    }
+    #[test]
+    fn empty_input() {
+        assert!(calc_fret_span(vec![]).is_none());
+    }
+}
+
+/// Calculates the physical distance in millimetres between the lowest and highest non-zero
+/// frets in a given vector of fingerings.
+///
+/// Returns `None` if there are no fretted fingerings or the guitar has no scale length.
+fn calc_fret_stretch_mm(
+    beat_fingering_candidate: &[&PitchFingering],
+    guitar: &Guitar,
+) -> Option<f32> {
+    let (min_non_zero_fret, max_non_zero_fret) = beat_fingering_candidate
+        .iter()
+        .filter(|fingering| fingering.fret != 0)
+        .map(|fingering| fingering.fret)
+        .minmax()
+        .into_option()?;
+
+    guitar.fret_distance_mm(min_non_zero_fret, max_non_zero_fret)
+}
+#[cfg(test)]
+mod test_calc_fret_stretch_mm {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    fn fingerings(frets: &[u8]) -> Vec<PitchFingering> {
+        frets
+            .iter()
+            .enumerate()
+            .map(|(index, &fret)| PitchFingering {
+                pitch: Pitch::A3,
+                string_number: StringNumber::new(index as u8 + 1).unwrap(),
+                fret,
+                harmonic: None,
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn no_scale_length() {
+        let fingerings = fingerings(&[1, 5]);
+        assert_eq!(
+            calc_fret_stretch_mm(&fingerings.iter().collect_vec(), &Guitar::default()),
+            None
+        );
+    }
+    #[test]
+    fn only_open_strings() {
+        let guitar = Guitar::default().with_scale_length(648).unwrap();
+        let fingerings = fingerings(&[0, 0]);
+        assert_eq!(
+            calc_fret_stretch_mm(&fingerings.iter().collect_vec(), &guitar),
+            None
+        );
+    }
+    #[test]
+    fn low_position_is_wider_than_high_position() {
+        let guitar = Guitar::default().with_scale_length(648).unwrap();
+        let low_fingerings = fingerings(&[1, 0, 5]);
+        let high_fingerings = fingerings(&[15, 19, 0]);
+
+        let low_stretch =
+            calc_fret_stretch_mm(&low_fingerings.iter().collect_vec(), &guitar).unwrap();
+        let high_stretch =
+            calc_fret_stretch_mm(&high_fingerings.iter().collect_vec(), &guitar).unwrap();
+
+        assert!((low_stretch - 126.2).abs() < 0.1);
+        assert!(low_stretch > 2.0 * high_stretch);
+    }
+}
+
+/// Finds the nodes of the beat after the current node in a list of all path nodes.
+fn calc_next_nodes(current_node: &Node, path_nodes: Vec<Node>) -> Vec<Node> {
+    let next_node_index = match current_node {
+        Node::Start => 0,
+        Node::Rest { line_index } | Node::Note { line_index, .. } => line_index + 1,
+    };
+
+    path_nodes
+        .into_iter()
+        .filter(|node| {
+            next_node_index
+                == match node {
+                    Node::Start => unreachable!("Start should never be a future node."),
+                    Node::Rest { line_index } | Node::Note { line_index, .. } => *line_index,
+                }
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_calc_next_nodes {
+    use super::*;
+
+    fn create_test_path_nodes() -> Vec<Node> {
+        vec![
+            Node::Note {
+                line_index: 0,
+                beat_fingering_combo: BeatFingeringCombo {
+                    fingering_combo: vec![],
+                    avg_non_zero_fret: Some(OrderedFloat(0.1)),
+                    non_zero_fret_span: 0,
+                    uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
+                },
+            },
+            Node::Note {
+                line_index: 0,
+                beat_fingering_combo: BeatFingeringCombo {
+                    fingering_combo: vec![],
+                    avg_non_zero_fret: Some(OrderedFloat(0.2)),
+                    non_zero_fret_span: 0,
+                    uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
+                },
+            },
+            Node::Note {
+                line_index: 1,
+                beat_fingering_combo: BeatFingeringCombo {
+                    fingering_combo: vec![],
+                    avg_non_zero_fret: Some(OrderedFloat(1.1)),
+                    non_zero_fret_span: 1,
+                    uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
+                },
+            },
+            Node::Rest { line_index: 2 },
+            Node::Rest { line_index: 3 },
+            Node::Note {
+                line_index: 4,
+                beat_fingering_combo: BeatFingeringCombo {
+                    fingering_combo: vec![],
+                    avg_non_zero_fret: Some(OrderedFloat(4.1)),
+                    non_zero_fret_span: 4,
+                    uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
+                },
+            },
+            Node::Note {
+                line_index: 4,
+                beat_fingering_combo: BeatFingeringCombo {
+                    fingering_combo: vec![],
+                    avg_non_zero_fret: Some(OrderedFloat(4.1)),
+                    non_zero_fret_span: 4,
+                    uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
                 },
             },
+        ]
+    }
+
+    #[test]
+    fn from_start_to_note() {
+        let current_node = Node::Start;
+
+        let expected_nodes = vec![
             Node::Note {
-                line_index: 1,
+                line_index: 0,
                 beat_fingering_combo: BeatFingeringCombo {
                     fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(1.1)),
-                    non_zero_fret_span: 1,
+                    avg_non_zero_fret: Some(OrderedFloat(0.1)),
+                    non_zero_fret_span: 0,
                     uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
                 },
             },
-            Node::Rest { line_index: 2 },
-            Node::Rest { line_index: 3 },
+            Node::Note {
+                line_index: 0,
+                beat_fingering_combo: BeatFingeringCombo {
+                    fingering_combo: vec![],
+                    avg_non_zero_fret: Some(OrderedFloat(0.2)),
+                    non_zero_fret_span: 0,
+                    uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
+                },
+            },
+        ];
+
+        assert_eq!(
+            calc_next_nodes(&current_node, create_test_path_nodes()),
+            expected_nodes
+        );
+    }
+    #[test]
+    fn from_note_to_note() {
+        let current_node = Node::Note {
+            line_index: 0,
+            beat_fingering_combo: BeatFingeringCombo {
+                fingering_combo: vec![],
+                avg_non_zero_fret: Some(OrderedFloat(0.1)),
+                non_zero_fret_span: 0,
+                uses_open_string: false,
+                fret_stretch_mm: None,
+                voices: vec![],
+            },
+        };
+
+        let expected_nodes = vec![Node::Note {
+            line_index: 1,
+            beat_fingering_combo: BeatFingeringCombo {
+                fingering_combo: vec![],
+                avg_non_zero_fret: Some(OrderedFloat(1.1)),
+                non_zero_fret_span: 1,
+                uses_open_string: false,
+                fret_stretch_mm: None,
+                voices: vec![],
+            },
+        }];
+
+        assert_eq!(
+            calc_next_nodes(&current_node, create_test_path_nodes()),
+            expected_nodes
+        );
+    }
+    #[test]
+    fn from_note_to_rest() {
+        let current_node = Node::Note {
+            line_index: 1,
+            beat_fingering_combo: BeatFingeringCombo {
+                fingering_combo: vec![],
+                avg_non_zero_fret: Some(OrderedFloat(1.1)),
+                non_zero_fret_span: 1,
+                uses_open_string: false,
+                fret_stretch_mm: None,
+                voices: vec![],
+            },
+        };
+
+        let expected_nodes = vec![Node::Rest { line_index: 2 }];
+
+        assert_eq!(
+            calc_next_nodes(&current_node, create_test_path_nodes()),
+            expected_nodes
+        );
+    }
+    #[test]
+    fn from_rest_to_rest() {
+        let current_node = Node::Rest { line_index: 2 };
+
+        let expected_nodes = vec![Node::Rest { line_index: 3 }];
+
+        assert_eq!(
+            calc_next_nodes(&current_node, create_test_path_nodes()),
+            expected_nodes
+        );
+    }
+    #[test]
+    fn from_rest_to_note() {
+        let current_node = Node::Rest { line_index: 3 };
+
+        let expected_nodes = vec![
             Node::Note {
                 line_index: 4,
                 beat_fingering_combo: BeatFingeringCombo {
@@ -1013,6 +2759,8 @@ mod test_calc_next_nodes {
                     avg_non_zero_fret: Some(OrderedFloat(4.1)),
                     non_zero_fret_span: 4,
                     uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
                 },
             },
             Node::Note {
@@ -1022,391 +2770,1267 @@ mod test_calc_next_nodes {
                     avg_non_zero_fret: Some(OrderedFloat(4.1)),
                     non_zero_fret_span: 4,
                     uses_open_string: false,
+                    fret_stretch_mm: None,
+                    voices: vec![],
                 },
             },
-        ]
+        ];
+
+        assert_eq!(
+            calc_next_nodes(&current_node, create_test_path_nodes()),
+            expected_nodes
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_start() {
+        calc_next_nodes(
+            &Node::Rest { line_index: 3 },
+            vec![Node::Rest { line_index: 4 }, Node::Start],
+        );
+    }
+}
+
+/// A pathfinding node together with the state of the performance that its cost depends on.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct SearchNode {
+    node: Node,
+    /// The node of the first beat of the repeated passage that the node is in, so that the
+    /// transition from the last beat of the passage back to its first beat is costed.
+    repeat_first_node: Option<Node>,
+    /// The notes from earlier beats that are still ringing, in string order.
+    ringing_notes: Vec<RingingNote>,
+    /// The position of the fretting hand after the node (see `next_hand_position`).
+    hand_position: Option<u8>,
+    /// The node of the last beat before the first ending of a repeated passage, and the hand
+    /// position after it, so that the second ending is costed from the beat it is played after.
+    ending_entry: Option<(Node, Option<u8>)>,
+}
+impl SearchNode {
+    fn start() -> Self {
+        SearchNode {
+            node: Node::Start,
+            repeat_first_node: None,
+            ringing_notes: vec![],
+            hand_position: None,
+            ending_entry: None,
+        }
+    }
+}
+
+/// A note that rings for longer than the beat it is played in.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+struct RingingNote {
+    string_number: StringNumber,
+    /// The index of the last beat that the note rings in.
+    last_beat_index: u16,
+    /// The voice of the note, which is 0 if its beat is not divided into voices.
+    voice: u8,
+}
+
+/// Finds the notes that ring after the next node is played and the number of ringing notes that
+/// the next node cuts off by playing on their strings before the end of their durations.
+fn ring_strings(
+    ringing_notes: &[RingingNote],
+    next_node: &Node,
+    beat_durations: &[BeatVec<u8>],
+) -> (Vec<RingingNote>, usize) {
+    let (next_line_index, next_fingerings, next_voices) = match next_node {
+        Node::Start => unreachable!("Start should never be a future node."),
+        Node::Rest { line_index } => (*line_index, &[][..], &[][..]),
+        Node::Note {
+            line_index,
+            beat_fingering_combo,
+        } => (
+            *line_index,
+            beat_fingering_combo.fingering_combo.as_slice(),
+            beat_fingering_combo.voices.as_slice(),
+        ),
+    };
+    let durations = beat_durations
+        .get(next_line_index as usize)
+        .map_or(&[][..], |durations| durations.as_slice());
+    let is_played = |string_number: StringNumber| {
+        next_fingerings
+            .iter()
+            .any(|fingering| fingering.string_number == string_number)
+    };
+
+    let (cut_off_notes, still_ringing_notes): (Vec<_>, Vec<_>) = ringing_notes
+        .iter()
+        .filter(|ringing_note| ringing_note.last_beat_index >= next_line_index)
+        .partition(|ringing_note| is_played(ringing_note.string_number));
+    let next_ringing_notes = still_ringing_notes
+        .into_iter()
+        .copied()
+        .chain(
+            next_fingerings
+                .iter()
+                .enumerate()
+                .zip(durations)
+                .filter(|(_, &duration)| duration > 1)
+                .map(|((fingering_index, fingering), &duration)| RingingNote {
+                    string_number: fingering.string_number,
+                    last_beat_index: next_line_index + duration as u16 - 1,
+                    voice: next_voices.get(fingering_index).copied().unwrap_or(0),
+                }),
+        )
+        .sorted()
+        .collect_vec();
+
+    (next_ringing_notes, cut_off_notes.len())
+}
+#[cfg(test)]
+mod test_ring_strings {
+    use super::test_helpers::note;
+    use super::*;
+
+    fn ringing(string_number: u8, last_beat_index: u16) -> RingingNote {
+        RingingNote {
+            string_number: StringNumber::new(string_number).unwrap(),
+            last_beat_index,
+            voice: 0,
+        }
+    }
+
+    #[test]
+    fn starts_ringing() {
+        let beat_durations = vec![vec![], vec![3, 1]];
+        assert_eq!(
+            ring_strings(&[], &note(1, &[(6, 2), (1, 2)]), &beat_durations),
+            (vec![ringing(6, 3)], 0)
+        );
+    }
+    #[test]
+    fn keeps_ringing() {
+        assert_eq!(
+            ring_strings(&[ringing(6, 3)], &note(2, &[(1, 2)]), &[]),
+            (vec![ringing(6, 3)], 0)
+        );
+        assert_eq!(
+            ring_strings(&[ringing(6, 3)], &Node::Rest { line_index: 3 }, &[]),
+            (vec![ringing(6, 3)], 0)
+        );
+    }
+    #[test]
+    fn cut_off() {
+        assert_eq!(
+            ring_strings(&[ringing(5, 2), ringing(6, 3)], &note(2, &[(6, 2)]), &[]),
+            (vec![ringing(5, 2)], 1)
+        );
+    }
+    #[test]
+    fn stops_ringing() {
+        assert_eq!(
+            ring_strings(&[ringing(6, 3)], &note(4, &[(6, 2)]), &[]),
+            (vec![], 0)
+        );
+    }
+    #[test]
+    fn voices() {
+        let Node::Note {
+            beat_fingering_combo,
+            ..
+        } = note(0, &[(5, 2), (2, 2)])
+        else {
+            unreachable!()
+        };
+        let next_node = Node::Note {
+            line_index: 0,
+            beat_fingering_combo: beat_fingering_combo.with_voices(vec![0, 1]),
+        };
+        assert_eq!(
+            ring_strings(&[], &next_node, &[vec![2, 2]]),
+            (
+                vec![
+                    RingingNote {
+                        voice: 1,
+                        ..ringing(2, 1)
+                    },
+                    ringing(5, 1)
+                ],
+                0
+            )
+        );
+    }
+}
+
+/// Counts the pairs of ringing notes and notes of the next node whose voices cross, where a note of
+/// a higher voice is on a lower string than a note of a lower voice (e.g. a melody note played
+/// below the bass note that is still ringing under it). Only beats divided into voices are checked.
+fn count_crossed_voices(ringing_notes: &[RingingNote], next_node: &Node) -> usize {
+    let Node::Note {
+        beat_fingering_combo,
+        ..
+    } = next_node
+    else {
+        return 0;
+    };
+
+    beat_fingering_combo
+        .fingering_combo
+        .iter()
+        .zip(&beat_fingering_combo.voices)
+        .cartesian_product(ringing_notes)
+        .filter(
+            |((fingering, &voice), ringing_note)| match voice.cmp(&ringing_note.voice) {
+                Ordering::Greater => fingering.string_number > ringing_note.string_number,
+                Ordering::Less => fingering.string_number < ringing_note.string_number,
+                Ordering::Equal => false,
+            },
+        )
+        .count()
+}
+#[cfg(test)]
+mod test_count_crossed_voices {
+    use super::*;
+
+    fn melody_note(string_number: u8) -> Node {
+        let fingering = PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret: 2,
+            harmonic: None,
+        };
+        Node::Note {
+            line_index: 1,
+            beat_fingering_combo: BeatFingeringCombo::new(vec![&fingering], &Guitar::default())
+                .with_voices(vec![1]),
+        }
+    }
+    fn bass_note(string_number: u8) -> RingingNote {
+        RingingNote {
+            string_number: StringNumber::new(string_number).unwrap(),
+            last_beat_index: 3,
+            voice: 0,
+        }
+    }
+
+    #[test]
+    fn melody_above_bass() {
+        assert_eq!(count_crossed_voices(&[bass_note(5)], &melody_note(3)), 0);
+    }
+    #[test]
+    fn melody_below_bass() {
+        assert_eq!(count_crossed_voices(&[bass_note(4)], &melody_note(5)), 1);
+    }
+    #[test]
+    fn rest() {
+        assert_eq!(
+            count_crossed_voices(&[bass_note(4)], &Node::Rest { line_index: 1 }),
+            0
+        );
+    }
+}
+
+/// Checks that every pitch of the current node with a legato technique reaches a fretted pitch of
+/// the next node on the same string, as the technique requires.
+fn legato_connects(
+    current_node: &Node,
+    next_node: &Node,
+    beat_techniques: &[BeatVec<Option<Technique>>],
+) -> bool {
+    let Node::Note {
+        line_index,
+        beat_fingering_combo,
+    } = current_node
+    else {
+        return true;
+    };
+    let Some(techniques) = beat_techniques.get(*line_index as usize) else {
+        return true;
+    };
+    let next_fingerings = match next_node {
+        Node::Note {
+            beat_fingering_combo,
+            ..
+        } => beat_fingering_combo.fingering_combo.as_slice(),
+        _ => &[],
+    };
+
+    beat_fingering_combo
+        .fingering_combo
+        .iter()
+        .zip(techniques)
+        .all(|(fingering, technique)| match technique {
+            Some(technique) if technique.is_legato() => next_fingerings.iter().any(|next| {
+                next.string_number == fingering.string_number
+                    && next.harmonic.is_none()
+                    && technique.connects(fingering.fret, next.fret)
+            }),
+            _ => true,
+        })
+}
+#[cfg(test)]
+mod test_legato_connects {
+    use super::test_helpers::note;
+    use super::*;
+
+    #[test]
+    fn same_string() {
+        let beat_techniques = vec![vec![None, Some(Technique::HammerOn)]];
+        assert!(legato_connects(
+            &note(0, &[(3, 0), (1, 5)]),
+            &note(1, &[(1, 7)]),
+            &beat_techniques
+        ));
+    }
+    #[test]
+    fn other_string() {
+        let beat_techniques = vec![vec![Some(Technique::Slide)]];
+        assert!(!legato_connects(
+            &note(0, &[(2, 5)]),
+            &note(1, &[(1, 7)]),
+            &beat_techniques
+        ));
+    }
+    #[test]
+    fn wrong_direction() {
+        let beat_techniques = vec![vec![Some(Technique::PullOff)]];
+        assert!(!legato_connects(
+            &note(0, &[(1, 5)]),
+            &note(1, &[(1, 7)]),
+            &beat_techniques
+        ));
+    }
+    #[test]
+    fn to_rest() {
+        let beat_techniques = vec![vec![Some(Technique::HammerOn)]];
+        assert!(!legato_connects(
+            &note(0, &[(1, 5)]),
+            &Node::Rest { line_index: 1 },
+            &beat_techniques
+        ));
+    }
+    #[test]
+    fn no_techniques() {
+        assert!(legato_connects(
+            &note(0, &[(1, 5)]),
+            &note(1, &[(2, 7)]),
+            &[]
+        ));
+    }
+}
+
+/// Calculates the next search nodes like `calc_next_nodes` with the difficulty of the arrangement
+/// style and the cost of moving every voice, leaving out the nodes that the techniques of the
+/// current node cannot connect to. The position style costs the shifts of the fretting hand
+/// between positions (see `calculate_position_difficulty`). Entering the last beat of a repeated
+/// passage additionally costs the transition back to the first beat of the passage. Cutting off
+/// notes that are still ringing and crossing the voices of ringing notes are costed per note.
+fn calc_next_search_nodes(
+    search_node: &SearchNode,
+    path_nodes: Vec<Node>,
+    open_string_cost: u16,
+    string_costs: StringCosts,
+    repeats: &[Repeat],
+    beat_articulation: &Articulation,
+    beat_pick_directions: &[Option<PickDirection>],
+) -> Vec<(SearchNode, i32)> {
+    const CUT_OFF_NOTE_COST: i32 = 500;
+    const CROSSED_VOICE_COST: i32 = 500;
+
+    let style_difficulty =
+        |hand_position: Option<u8>, current_node: &Node, next_node: &Node| match beat_articulation
+            .style
+        {
+            ArrangementStyle::Position => {
+                calculate_position_difficulty(hand_position, next_node, open_string_cost)
+            }
+            ArrangementStyle::Campanella => {
+                calculate_campanella_difficulty(current_node, next_node, open_string_cost)
+            }
+        };
+
+    let current_node = &search_node.node;
+    calc_next_nodes(current_node, path_nodes)
+        .into_iter()
+        .filter(|next_node| legato_connects(current_node, next_node, &beat_articulation.techniques))
+        .map(|next_node| {
+            let next_line_index = match next_node {
+                Node::Start => unreachable!("Start should never be a future node."),
+                Node::Rest { line_index } | Node::Note { line_index, .. } => line_index,
+            };
+
+            // The second ending is played after the last beat before the first ending, not after
+            // the first ending that precedes it in the lines
+            let enters_second_ending = repeats
+                .iter()
+                .any(|repeat| repeat.second_ending_beat_index == Some(next_line_index));
+            let (previous_node, previous_hand_position) = match &search_node.ending_entry {
+                Some((entry_node, entry_hand_position)) if enters_second_ending => {
+                    (entry_node, *entry_hand_position)
+                }
+                _ => (current_node, search_node.hand_position),
+            };
+
+            let next_hand_position = match &next_node {
+                Node::Note {
+                    beat_fingering_combo,
+                    ..
+                } => next_hand_position(
+                    previous_hand_position,
+                    &beat_fingering_combo.fingering_combo,
+                ),
+                _ => previous_hand_position,
+            };
+            let difficulty = style_difficulty(previous_hand_position, previous_node, &next_node)
+                + calc_voice_leading_cost(previous_node, &next_node)
+                + calc_string_crossing_cost(previous_node, &next_node, string_costs)
+                + calc_outside_picking_cost(previous_node, &next_node, beat_pick_directions);
+
+            let next_ending_entry = if repeats
+                .iter()
+                .any(|repeat| repeat.second_ending_entry_beat_index() == Some(next_line_index))
+            {
+                Some((next_node.clone(), next_hand_position))
+            } else if enters_second_ending {
+                None
+            } else {
+                search_node.ending_entry.clone()
+            };
+
+            let repeat = repeats.iter().find(|repeat| {
+                (repeat.first_beat_index..=repeat.last_beat_index).contains(&next_line_index)
+            });
+
+            let next_repeat_first_node = match repeat {
+                None => None,
+                Some(repeat) if repeat.first_beat_index == next_line_index => {
+                    Some(next_node.clone())
+                }
+                Some(_) => search_node.repeat_first_node.clone(),
+            };
+            let repeat_difficulty = match (repeat, &next_repeat_first_node) {
+                (Some(repeat), Some(first_node)) if repeat.last_beat_index == next_line_index => {
+                    style_difficulty(next_hand_position, &next_node, first_node)
+                }
+                _ => 0,
+            };
+
+            let (next_ringing_notes, num_cut_off_notes) = ring_strings(
+                &search_node.ringing_notes,
+                &next_node,
+                &beat_articulation.durations,
+            );
+            let num_crossed_voices = count_crossed_voices(&next_ringing_notes, &next_node);
+
+            (
+                SearchNode {
+                    node: next_node,
+                    repeat_first_node: next_repeat_first_node,
+                    ringing_notes: next_ringing_notes,
+                    hand_position: next_hand_position,
+                    ending_entry: next_ending_entry,
+                },
+                difficulty
+                    + repeat_difficulty
+                    + num_cut_off_notes as i32 * CUT_OFF_NOTE_COST
+                    + num_crossed_voices as i32 * CROSSED_VOICE_COST,
+            )
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_calc_next_search_nodes {
+    use super::test_helpers::note;
+    use super::*;
+
+    fn search_node(node: Node, repeat_first_node: Option<Node>, hand_position: u8) -> SearchNode {
+        SearchNode {
+            node,
+            repeat_first_node,
+            ringing_notes: vec![],
+            hand_position: Some(hand_position),
+            ending_entry: None,
+        }
+    }
+
+    #[test]
+    fn outside_repeat() {
+        let path_nodes = vec![note(0, &[(1, 1)]), note(1, &[(1, 6)])];
+        // 200 to shift from the first position to the third position
+        assert_eq!(
+            calc_next_search_nodes(
+                &search_node(note(0, &[(1, 1)]), None, 1),
+                path_nodes,
+                0,
+                StringCosts::default(),
+                &[],
+                &Articulation::default(),
+                &[]
+            ),
+            vec![(search_node(note(1, &[(1, 6)]), None, 3), 203)]
+        );
     }
+    #[test]
+    fn repeat_back_transition() {
+        let path_nodes = vec![note(0, &[(1, 1)]), note(1, &[(1, 6)]), note(2, &[(1, 9)])];
+        let repeats = [Repeat {
+            first_beat_index: 1,
+            last_beat_index: 2,
+            first_ending_beat_index: None,
+            second_ending_beat_index: None,
+        }];
+
+        let entered_repeat = calc_next_search_nodes(
+            &search_node(note(0, &[(1, 1)]), None, 1),
+            path_nodes.clone(),
+            0,
+            StringCosts::default(),
+            &repeats,
+            &Articulation::default(),
+            &[],
+        );
+        assert_eq!(
+            entered_repeat,
+            vec![(
+                search_node(note(1, &[(1, 6)]), Some(note(1, &[(1, 6)])), 3),
+                203
+            )]
+        );
 
+        // 306 to shift to the sixth position for the last beat and 6 to play the first beat again
+        // without leaving that position
+        assert_eq!(
+            calc_next_search_nodes(
+                &entered_repeat[0].0,
+                path_nodes,
+                0,
+                StringCosts::default(),
+                &repeats,
+                &Articulation::default(),
+                &[]
+            ),
+            vec![(
+                search_node(note(2, &[(1, 9)]), Some(note(1, &[(1, 6)])), 6),
+                312
+            )]
+        );
+    }
     #[test]
-    fn from_start_to_note() {
-        let current_node = Node::Start;
+    fn second_ending_transition() {
+        let path_nodes = vec![note(0, &[(1, 1)]), note(1, &[(1, 9)]), note(2, &[(1, 2)])];
+        let repeats = [Repeat {
+            first_beat_index: 0,
+            last_beat_index: 1,
+            first_ending_beat_index: Some(1),
+            second_ending_beat_index: Some(2),
+        }];
+        let first_ending = SearchNode {
+            ending_entry: Some((note(0, &[(1, 1)]), Some(1))),
+            ..search_node(note(1, &[(1, 9)]), Some(note(0, &[(1, 1)])), 6)
+        };
 
-        let expected_nodes_and_costs = [
-            Node::Note {
-                line_index: 0,
-                beat_fingering_combo: BeatFingeringCombo {
-                    fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(0.1)),
-                    non_zero_fret_span: 0,
-                    uses_open_string: false,
-                },
-            },
-            Node::Note {
-                line_index: 0,
-                beat_fingering_combo: BeatFingeringCombo {
-                    fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(0.2)),
-                    non_zero_fret_span: 0,
-                    uses_open_string: false,
-                },
-            },
-        ]
+        // 1 to play the second ending in the first position that the first beat left the hand
+        // in, rather than shifting down from the sixth position of the first ending
+        assert_eq!(
+            calc_next_search_nodes(
+                &first_ending,
+                path_nodes,
+                0,
+                StringCosts::default(),
+                &repeats,
+                &Articulation::default(),
+                &[]
+            ),
+            vec![(search_node(note(2, &[(1, 2)]), None, 1), 1)]
+        );
+    }
+}
+
+/// Calculates the cost of the left hand stretch of a beat fingering combination.
+///
+/// The physical stretch is used when the guitar has a scale length so that stretches in low
+/// positions cost more than the same number of frets in high positions. Otherwise, every fret
+/// of the span costs the same.
+fn calc_stretch_cost(beat_fingering_combo: &BeatFingeringCombo) -> f32 {
+    const FRET_SPAN_COST: f32 = 10.0;
+    // Average fret width in the first octave of a 648 mm scale guitar
+    const REFERENCE_FRET_WIDTH_MM: f32 = 27.0;
+
+    match beat_fingering_combo.fret_stretch_mm {
+        Some(fret_stretch_mm) => {
+            fret_stretch_mm.into_inner() / REFERENCE_FRET_WIDTH_MM * FRET_SPAN_COST
+        }
+        None => beat_fingering_combo.non_zero_fret_span as f32 * FRET_SPAN_COST,
+    }
+}
+#[cfg(test)]
+mod test_calc_stretch_cost {
+    use super::*;
+
+    #[test]
+    fn fret_span() {
+        let beat_fingering_combo = BeatFingeringCombo {
+            fingering_combo: vec![],
+            avg_non_zero_fret: Some(OrderedFloat(3.0)),
+            uses_open_string: false,
+            fret_stretch_mm: None,
+            voices: vec![],
+            non_zero_fret_span: 4,
+        };
+        assert_eq!(calc_stretch_cost(&beat_fingering_combo), 40.0);
+    }
+    #[test]
+    fn fret_stretch_mm() {
+        let beat_fingering_combo = BeatFingeringCombo {
+            fingering_combo: vec![],
+            avg_non_zero_fret: Some(OrderedFloat(3.0)),
+            uses_open_string: false,
+            fret_stretch_mm: Some(OrderedFloat(54.0)),
+            voices: vec![],
+            non_zero_fret_span: 4,
+        };
+        assert_eq!(calc_stretch_cost(&beat_fingering_combo), 20.0);
+    }
+}
+
+/// Calculates the cost of playing the harmonics of a beat fingering combination instead of fretted
+/// notes. Artificial harmonics need both hands on one note, so they cost more than natural ones.
+fn calc_harmonic_cost(beat_fingering_combo: &BeatFingeringCombo) -> f32 {
+    const NATURAL_HARMONIC_COST: f32 = 150.0;
+    const ARTIFICIAL_HARMONIC_COST: f32 = 300.0;
+
+    beat_fingering_combo
+        .fingering_combo
         .iter()
-        .map(|node| {
-            (
-                node.clone(),
-                calculate_node_difficulty(&current_node, node, 0),
-            )
+        .map(|fingering| match fingering.harmonic {
+            None => 0.0,
+            Some(Harmonic::Natural) => NATURAL_HARMONIC_COST,
+            Some(Harmonic::Artificial) => ARTIFICIAL_HARMONIC_COST,
         })
-        .collect_vec();
+        .sum()
+}
+#[cfg(test)]
+mod test_calc_harmonic_cost {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    fn fingering(string_number: u8, harmonic: Option<Harmonic>) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E5,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret: 12,
+            harmonic,
+        }
+    }
+
+    #[test]
+    fn fretted() {
+        let fingering = fingering(1, None);
+        let beat_fingering_combo = BeatFingeringCombo::new(vec![&fingering], &Guitar::default());
+        assert_eq!(calc_harmonic_cost(&beat_fingering_combo), 0.0);
+    }
+    #[test]
+    fn harmonics() {
+        let natural = fingering(1, Some(Harmonic::Natural));
+        let artificial = fingering(2, Some(Harmonic::Artificial));
+        let beat_fingering_combo =
+            BeatFingeringCombo::new(vec![&natural, &artificial], &Guitar::default());
+        assert_eq!(calc_harmonic_cost(&beat_fingering_combo), 450.0);
+    }
+}
+
+/// Calculates the cost of moving every voice from one node to the next, so that the bass line and
+/// the melody each move smoothly rather than only their average. Only beats with the same number of
+/// voices are compared.
+fn calc_voice_leading_cost(current_node: &Node, next_node: &Node) -> i32 {
+    const VOICE_FRET_DIFFERENCE_COST: f32 = 20.0;
+
+    let (
+        Node::Note {
+            beat_fingering_combo: current_combo,
+            ..
+        },
+        Node::Note {
+            beat_fingering_combo: next_combo,
+            ..
+        },
+    ) = (current_node, next_node)
+    else {
+        return 0;
+    };
+    let current_voice_frets = current_combo.voice_avg_non_zero_frets();
+    let next_voice_frets = next_combo.voice_avg_non_zero_frets();
+    if current_voice_frets.len() < 2 || current_voice_frets.len() != next_voice_frets.len() {
+        return 0;
+    }
+
+    current_voice_frets
+        .into_iter()
+        .zip(next_voice_frets)
+        .filter_map(|voice_frets| match voice_frets {
+            (Some(current_fret), Some(next_fret)) => {
+                Some((next_fret - current_fret).abs() * VOICE_FRET_DIFFERENCE_COST)
+            }
+            _ => None,
+        })
+        .sum::<f32>() as i32
+}
+#[cfg(test)]
+mod test_calc_voice_leading_cost {
+    use super::test_helpers::voiced_note;
+    use super::*;
+
+    #[test]
+    fn two_voices() {
+        // The bass moves by 2 frets and the melody by 1 fret
+        assert_eq!(
+            calc_voice_leading_cost(
+                &voiced_note(0, &[(5, 3), (2, 5)], vec![0, 1]),
+                &voiced_note(0, &[(6, 5), (2, 6)], vec![0, 1])
+            ),
+            60
+        );
+    }
+    #[test]
+    fn open_voice() {
+        assert_eq!(
+            calc_voice_leading_cost(
+                &voiced_note(0, &[(5, 0), (2, 5)], vec![0, 1]),
+                &voiced_note(0, &[(6, 5), (2, 5)], vec![0, 1])
+            ),
+            0
+        );
+    }
+    #[test]
+    fn without_voices() {
+        assert_eq!(
+            calc_voice_leading_cost(
+                &voiced_note(0, &[(5, 3)], vec![]),
+                &voiced_note(0, &[(6, 5)], vec![])
+            ),
+            0
+        );
+        assert_eq!(
+            calc_voice_leading_cost(
+                &voiced_note(0, &[(5, 3), (2, 5)], vec![0, 1]),
+                &voiced_note(0, &[(6, 5)], vec![0])
+            ),
+            0
+        );
+        assert_eq!(
+            calc_voice_leading_cost(
+                &voiced_note(0, &[(5, 3), (2, 5)], vec![0, 1]),
+                &Node::Rest { line_index: 1 }
+            ),
+            0
+        );
+    }
+}
+
+/// Calculates the cost of the string crossing from one node to the next if the pick directions of
+/// their single notes need outside picking (see `is_outside_picking`). Nodes without a pick
+/// direction, such as chords and rests, are not costed.
+fn calc_outside_picking_cost(
+    current_node: &Node,
+    next_node: &Node,
+    beat_pick_directions: &[Option<PickDirection>],
+) -> i32 {
+    const OUTSIDE_PICKING_COST: i32 = 150;
+
+    let (
+        Node::Note {
+            line_index,
+            beat_fingering_combo: current_combo,
+        },
+        Node::Note {
+            beat_fingering_combo: next_combo,
+            ..
+        },
+    ) = (current_node, next_node)
+    else {
+        return 0;
+    };
+    let Some(Some(direction)) = beat_pick_directions.get(*line_index as usize) else {
+        return 0;
+    };
+
+    match (
+        current_combo.fingering_combo.as_slice(),
+        next_combo.fingering_combo.as_slice(),
+    ) {
+        ([current_fingering], [next_fingering])
+            if is_outside_picking(
+                current_fingering.string_number,
+                *direction,
+                next_fingering.string_number,
+            ) =>
+        {
+            OUTSIDE_PICKING_COST
+        }
+        _ => 0,
+    }
+}
+#[cfg(test)]
+mod test_calc_outside_picking_cost {
+    use super::test_helpers::note;
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let pick_directions = [Some(PickDirection::Down), Some(PickDirection::Up)];
+        assert_eq!(
+            calc_outside_picking_cost(&note(0, &[(3, 5)]), &note(1, &[(2, 5)]), &pick_directions),
+            150
+        );
+        assert_eq!(
+            calc_outside_picking_cost(&note(0, &[(3, 5)]), &note(1, &[(4, 5)]), &pick_directions),
+            0
+        );
+        assert_eq!(
+            calc_outside_picking_cost(&note(0, &[(3, 5)]), &note(1, &[(3, 5)]), &pick_directions),
+            0
+        );
+    }
+    #[test]
+    fn without_pick_directions() {
+        assert_eq!(
+            calc_outside_picking_cost(&note(0, &[(3, 5)]), &note(1, &[(2, 5)]), &[]),
+            0
+        );
+        assert_eq!(
+            calc_outside_picking_cost(
+                &Node::Rest { line_index: 0 },
+                &note(1, &[(2, 5)]),
+                &[None, Some(PickDirection::Down)]
+            ),
+            0
+        );
+    }
+}
+
+/// Calculates the cost of moving the hands across the strings from one node to the next, based on
+/// the average string of their notes (see `StringCosts`).
+fn calc_string_crossing_cost(
+    current_node: &Node,
+    next_node: &Node,
+    string_costs: StringCosts,
+) -> i32 {
+    let (
+        Node::Note {
+            beat_fingering_combo: current_combo,
+            ..
+        },
+        Node::Note {
+            beat_fingering_combo: next_combo,
+            ..
+        },
+    ) = (current_node, next_node)
+    else {
+        return 0;
+    };
+    if current_combo.fingering_combo.is_empty() || next_combo.fingering_combo.is_empty() {
+        return 0;
+    }
+    let avg_string_and_pitch = |beat_fingering_combo: &BeatFingeringCombo| {
+        let fingerings = &beat_fingering_combo.fingering_combo;
+        let num_fingerings = fingerings.len() as f32;
+        let avg_string = fingerings
+            .iter()
+            .map(|fingering| fingering.string_number.get() as f32)
+            .sum::<f32>()
+            / num_fingerings;
+        let avg_pitch = fingerings
+            .iter()
+            .map(|fingering| fingering.pitch.index() as f32)
+            .sum::<f32>()
+            / num_fingerings;
+        (avg_string, avg_pitch)
+    };
+    let (current_string, current_pitch) = avg_string_and_pitch(current_combo);
+    let (next_string, next_pitch) = avg_string_and_pitch(next_combo);
+
+    let string_distance = (next_string - current_string).abs();
+    // Higher string numbers are lower strings, so the strings and pitches usually move in opposite
+    // directions
+    let against_pitches = (next_string - current_string) * (next_pitch - current_pitch) > 0.0;
+    let crossing_cost = string_distance
+        * string_costs.crossing as f32
+        * match against_pitches {
+            true => 2.0,
+            false => 1.0,
+        };
+
+    // Skipping between two open strings does not move the fretting hand, as in bass and melody
+    // patterns on the open strings
+    let skip_cost = match (
+        current_combo.fingering_combo.len(),
+        next_combo.fingering_combo.len(),
+    ) {
+        (1, 1)
+            if current_combo.avg_non_zero_fret.is_some()
+                || next_combo.avg_non_zero_fret.is_some() =>
+        {
+            (string_distance - 1.0).max(0.0) * string_costs.skip as f32
+        }
+        _ => 0.0,
+    };
+
+    (crossing_cost + skip_cost) as i32
+}
+#[cfg(test)]
+mod test_calc_string_crossing_cost {
+    use super::test_helpers::note;
+    use super::*;
+
+    const STRING_COSTS: StringCosts = StringCosts {
+        crossing: 10,
+        skip: 50,
+    };
+
+    #[test]
+    fn same_string() {
+        assert_eq!(
+            calc_string_crossing_cost(&note(0, &[(6, 5)]), &note(0, &[(6, 8)]), STRING_COSTS),
+            0
+        );
+    }
+    #[test]
+    fn adjacent_string() {
+        assert_eq!(
+            calc_string_crossing_cost(&note(0, &[(6, 5)]), &note(0, &[(5, 3)]), STRING_COSTS),
+            10
+        );
+    }
+    #[test]
+    fn string_skip() {
+        // 5 strings crossed and 4 strings skipped
+        assert_eq!(
+            calc_string_crossing_cost(&note(0, &[(6, 5)]), &note(0, &[(1, 0)]), STRING_COSTS),
+            250
+        );
+        // Open strings are crossed without skipping them
+        assert_eq!(
+            calc_string_crossing_cost(&note(0, &[(6, 0)]), &note(0, &[(1, 0)]), STRING_COSTS),
+            50
+        );
+    }
+    #[test]
+    fn against_pitches() {
+        // A higher pitch on a lower string doubles the crossing cost
+        assert_eq!(
+            calc_string_crossing_cost(&note(0, &[(2, 0)]), &note(0, &[(3, 5)]), STRING_COSTS),
+            20
+        );
+    }
+    #[test]
+    fn chords() {
+        // Chords move by their average string without skipping strings
+        assert_eq!(
+            calc_string_crossing_cost(
+                &note(0, &[(6, 0), (5, 2)]),
+                &note(0, &[(3, 0), (2, 0)]),
+                STRING_COSTS
+            ),
+            30
+        );
+    }
+    #[test]
+    fn rest() {
+        assert_eq!(
+            calc_string_crossing_cost(
+                &Node::Rest { line_index: 0 },
+                &note(0, &[(1, 0)]),
+                STRING_COSTS
+            ),
+            0
+        );
+    }
+}
+
+/// The number of frets that the fretting hand covers in a position, one for each finger.
+const POSITION_NUM_FRETS: u8 = 4;
+
+/// Finds the position of the fretting hand, given by the fret of the index finger, after playing
+/// the fingerings from the given position. The hand stays in its position while the fretted notes
+/// are within the frets it covers (see `POSITION_NUM_FRETS`), and otherwise shifts as little as
+/// possible to reach them. Open strings and natural harmonics, which are only touched above the
+/// fret, do not move the hand. Artificial harmonics are fretted so they move the hand as fretted
+/// notes do.
+pub(crate) fn next_hand_position(
+    hand_position: Option<u8>,
+    fingerings: &[PitchFingering],
+) -> Option<u8> {
+    let fretted = fingerings
+        .iter()
+        .filter(|fingering| fingering.harmonic != Some(Harmonic::Natural))
+        .map(|fingering| fingering.fret)
+        .filter(|&fret| fret != 0);
+    let (Some(min_fret), Some(max_fret)) = (fretted.clone().min(), fretted.max()) else {
+        return hand_position;
+    };
+    let lowest_position = max_fret
+        .saturating_sub(POSITION_NUM_FRETS - 1)
+        .clamp(1, min_fret);
+
+    Some(hand_position.map_or(min_fret, |hand_position| {
+        hand_position.clamp(lowest_position, min_fret)
+    }))
+}
+#[cfg(test)]
+mod test_next_hand_position {
+    use super::*;
+
+    fn fingerings(frets: &[u8]) -> Vec<PitchFingering> {
+        frets
+            .iter()
+            .map(|&fret| PitchFingering {
+                pitch: Pitch::E4,
+                string_number: StringNumber::new(1).unwrap(),
+                fret,
+                harmonic: None,
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn first_position() {
+        assert_eq!(next_hand_position(None, &fingerings(&[5, 7])), Some(5));
+        assert_eq!(next_hand_position(None, &fingerings(&[0])), None);
+    }
+    #[test]
+    fn within_position() {
+        assert_eq!(next_hand_position(Some(5), &fingerings(&[5])), Some(5));
+        assert_eq!(next_hand_position(Some(5), &fingerings(&[8, 6])), Some(5));
+        assert_eq!(next_hand_position(Some(5), &fingerings(&[0])), Some(5));
+    }
+    #[test]
+    fn shifts() {
+        // Shifting up as little as possible so that the little finger reaches fret 10
+        assert_eq!(next_hand_position(Some(5), &fingerings(&[10])), Some(7));
+        assert_eq!(next_hand_position(Some(5), &fingerings(&[3, 0])), Some(3));
+        assert_eq!(next_hand_position(Some(1), &fingerings(&[2])), Some(1));
+    }
+    #[test]
+    fn stretch() {
+        // A chord spanning more than a position is played with the index finger on its lowest fret
+        assert_eq!(next_hand_position(Some(1), &fingerings(&[5, 10])), Some(5));
+        assert_eq!(next_hand_position(Some(9), &fingerings(&[5, 10])), Some(5));
+    }
+    #[test]
+    fn harmonics() {
+        let harmonic = |fret, harmonic| PitchFingering {
+            harmonic: Some(harmonic),
+            ..fingerings(&[fret])[0]
+        };
+        assert_eq!(
+            next_hand_position(Some(2), &[harmonic(12, Harmonic::Natural)]),
+            Some(2)
+        );
+        assert_eq!(
+            next_hand_position(None, &[harmonic(7, Harmonic::Natural)]),
+            None
+        );
+        assert_eq!(
+            next_hand_position(
+                Some(2),
+                &[harmonic(12, Harmonic::Natural), fingerings(&[3])[0]]
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            next_hand_position(Some(2), &[harmonic(7, Harmonic::Artificial)]),
+            Some(4)
+        );
+    }
+}
+
+/// Calculates the cost of playing the next node with the fretting hand in the given position, based
+/// on the distance that the hand shifts to the next position (see `next_hand_position`), the left
+/// hand stretch, the harmonics and open strings played, and the next position itself so that lower
+/// positions are preferred.
+fn calculate_position_difficulty(
+    hand_position: Option<u8>,
+    next_node: &Node,
+    open_string_cost: u16,
+) -> i32 {
+    const SHIFT_COST_PER_FRET: i32 = 100;
 
-        assert_eq!(
-            calc_next_nodes(&current_node, create_test_path_nodes(), 0),
-            expected_nodes_and_costs
-        );
-    }
-    #[test]
-    fn from_note_to_note() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(0.1)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
+    let beat_fingering_combo = match next_node {
+        Node::Start => unreachable!("Start should never be a future node."),
+        Node::Rest { .. } => return 0,
+        Node::Note {
+            beat_fingering_combo,
+            ..
+        } => beat_fingering_combo,
+    };
+    let next_position = next_hand_position(hand_position, &beat_fingering_combo.fingering_combo);
+    let shift_distance = match (hand_position, next_position) {
+        (Some(hand_position), Some(next_position)) => hand_position.abs_diff(next_position),
+        _ => 0,
+    };
 
-        let expected_nodes_and_costs = [Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(1.1)),
-                non_zero_fret_span: 1,
-                uses_open_string: false,
-            },
-        }]
-        .iter()
-        .map(|node| {
-            (
-                node.clone(),
-                calculate_node_difficulty(&current_node, node, 0),
-            )
-        })
-        .collect_vec();
+    shift_distance as i32 * SHIFT_COST_PER_FRET
+        + match beat_fingering_combo.uses_open_string {
+            true => open_string_cost as i32,
+            false => 0,
+        }
+        + calc_stretch_cost(beat_fingering_combo) as i32
+        + calc_harmonic_cost(beat_fingering_combo) as i32
+        + next_position.unwrap_or(0) as i32
+}
+#[cfg(test)]
+mod test_calculate_position_difficulty {
+    use super::test_helpers::note;
+    use super::*;
 
+    #[test]
+    fn within_position() {
+        // Staying in the fifth position
         assert_eq!(
-            calc_next_nodes(&current_node, create_test_path_nodes(), 0),
-            expected_nodes_and_costs
+            calculate_position_difficulty(Some(5), &note(0, &[(1, 7)]), 0),
+            5
         );
     }
     #[test]
-    fn from_note_to_rest() {
-        let current_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(1.1)),
-                non_zero_fret_span: 1,
-                uses_open_string: false,
-            },
-        };
-
-        let expected_nodes_and_costs = [Node::Rest { line_index: 2 }]
-            .iter()
-            .map(|node| {
-                (
-                    node.clone(),
-                    calculate_node_difficulty(&current_node, node, 0),
-                )
-            })
-            .collect_vec();
-
+    fn shift() {
+        // Shifting from the fifth position to the seventh position
         assert_eq!(
-            calc_next_nodes(&current_node, create_test_path_nodes(), 0),
-            expected_nodes_and_costs
+            calculate_position_difficulty(Some(5), &note(0, &[(1, 10)]), 0),
+            207
         );
     }
     #[test]
-    fn from_rest_to_rest() {
-        let current_node = Node::Rest { line_index: 2 };
-
-        let expected_nodes_and_costs = [Node::Rest { line_index: 3 }]
-            .iter()
-            .map(|node| {
-                (
-                    node.clone(),
-                    calculate_node_difficulty(&current_node, node, 0),
-                )
-            })
-            .collect_vec();
-
+    fn open_string() {
+        assert_eq!(
+            calculate_position_difficulty(Some(5), &note(0, &[(1, 0)]), 30),
+            35
+        );
         assert_eq!(
-            calc_next_nodes(&current_node, create_test_path_nodes(), 0),
-            expected_nodes_and_costs
+            calculate_position_difficulty(None, &note(0, &[(1, 0)]), 30),
+            30
         );
     }
     #[test]
-    fn from_rest_to_note() {
-        let current_node = Node::Rest { line_index: 3 };
-
-        let expected_nodes_and_costs = [
-            Node::Note {
-                line_index: 4,
-                beat_fingering_combo: BeatFingeringCombo {
-                    fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(4.1)),
-                    non_zero_fret_span: 4,
-                    uses_open_string: false,
-                },
-            },
-            Node::Note {
-                line_index: 4,
-                beat_fingering_combo: BeatFingeringCombo {
-                    fingering_combo: vec![],
-                    avg_non_zero_fret: Some(OrderedFloat(4.1)),
-                    non_zero_fret_span: 4,
-                    uses_open_string: false,
-                },
-            },
-        ]
-        .iter()
-        .map(|node| {
-            (
-                node.clone(),
-                calculate_node_difficulty(&current_node, node, 0),
-            )
-        })
-        .collect_vec();
-
+    fn stretch() {
+        // 10 per fret of the span of the chord
         assert_eq!(
-            calc_next_nodes(&current_node, create_test_path_nodes(), 0),
-            expected_nodes_and_costs
+            calculate_position_difficulty(Some(1), &note(0, &[(1, 1), (2, 4)]), 0),
+            31
         );
     }
-
     #[test]
-    #[should_panic]
-    fn to_start() {
-        calc_next_nodes(
-            &Node::Rest { line_index: 3 },
-            vec![Node::Rest { line_index: 4 }, Node::Start],
-            0,
+    fn rest() {
+        assert_eq!(
+            calculate_position_difficulty(Some(5), &Node::Rest { line_index: 0 }, 0),
+            0
         );
     }
 }
 
-/// Calculates the cost of transitioning from one node to another based on the
-/// average fret difference and fret span.
-fn calculate_node_difficulty(current_node: &Node, next_node: &Node, open_string_cost: u16) -> i32 {
-    let current_avg_fret = match current_node {
+/// Calculates the cost of transitioning from one node to another in the campanella style, where
+/// consecutive notes are played on different strings so that they ring over each other. Playing on
+/// a string of the previous beat and fretting notes instead of playing open strings are costed,
+/// while moving the hand costs less than shifting positions in `calculate_position_difficulty`.
+fn calculate_campanella_difficulty(
+    current_node: &Node,
+    next_node: &Node,
+    open_string_cost: u16,
+) -> i32 {
+    const REPEATED_STRING_COST: f32 = 300.0;
+    const FRETTED_NOTE_COST: f32 = 50.0;
+    const FRET_DIFFERENCE_COST: f32 = 25.0;
+
+    let (current_fingerings, current_avg_fret) = match current_node {
         Node::Note {
             beat_fingering_combo,
             ..
-        } => beat_fingering_combo.avg_non_zero_fret,
-        _ => None,
+        } => (
+            beat_fingering_combo.fingering_combo.as_slice(),
+            beat_fingering_combo.avg_non_zero_fret,
+        ),
+        _ => (&[][..], None),
     };
-
-    let (next_avg_fret, next_fret_span, next_uses_open) = match next_node {
+    let next_beat_fingering_combo = match next_node {
         Node::Start => unreachable!("Start should never be a future node."),
-        Node::Rest { .. } => (None, 0.0, false),
+        Node::Rest { .. } => return 0,
         Node::Note {
             beat_fingering_combo,
             ..
-        } => (
-            beat_fingering_combo.avg_non_zero_fret,
-            beat_fingering_combo.non_zero_fret_span as f32,
-            beat_fingering_combo.uses_open_string,
-        ),
+        } => beat_fingering_combo,
     };
 
-    let mut avg_fret_difference = 0.0;
-    if let (Some(current_avg_fret_num), Some(next_avg_fret_num)) = (current_avg_fret, next_avg_fret)
-    {
-        avg_fret_difference = (next_avg_fret_num - current_avg_fret_num).abs();
-    }
+    let avg_fret_difference = match (
+        current_avg_fret,
+        next_beat_fingering_combo.avg_non_zero_fret,
+    ) {
+        (Some(current_avg_fret), Some(next_avg_fret)) => (next_avg_fret - current_avg_fret).abs(),
+        _ => 0.0,
+    };
+    let num_repeated_strings = next_beat_fingering_combo
+        .fingering_combo
+        .iter()
+        .filter(|next_fingering| {
+            current_fingerings
+                .iter()
+                .any(|fingering| fingering.string_number == next_fingering.string_number)
+        })
+        .count();
+    let num_fretted_notes = next_beat_fingering_combo
+        .fingering_combo
+        .iter()
+        .filter(|fingering| fingering.fret > 0)
+        .count();
+    let num_open_notes = next_beat_fingering_combo.fingering_combo.len() - num_fretted_notes;
 
-    ((avg_fret_difference * 100.0)
-        + match next_uses_open {
-            true => open_string_cost as f32,
-            false => 0.0,
-        }
-        + (next_fret_span * 10.0)
-        + (next_avg_fret.unwrap_or(OrderedFloat(0.0))).into_inner()) as i32
+    (avg_fret_difference * FRET_DIFFERENCE_COST
+        + num_repeated_strings as f32 * REPEATED_STRING_COST
+        + num_fretted_notes as f32 * FRETTED_NOTE_COST
+        + num_open_notes as f32 * open_string_cost as f32
+        + calc_stretch_cost(next_beat_fingering_combo)
+        + calc_harmonic_cost(next_beat_fingering_combo)) as i32
 }
 #[cfg(test)]
-mod test_calculate_node_difficulty {
+mod test_calculate_campanella_difficulty {
+    use super::test_helpers::note;
     use super::*;
 
     #[test]
-    fn simple_no_diff() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.5)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.5)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-
-        assert_eq!(calculate_node_difficulty(&current_node, &next_node, 0), 3);
+    fn open_string() {
+        assert_eq!(
+            calculate_campanella_difficulty(&note(0, &[(2, 3)]), &note(1, &[(1, 0)]), 0),
+            0
+        );
+        assert_eq!(
+            calculate_campanella_difficulty(&note(0, &[(2, 3)]), &note(1, &[(1, 0)]), 20),
+            20
+        );
     }
     #[test]
-    fn simple_from_start() {
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.5)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-
-        assert_eq!(calculate_node_difficulty(&Node::Start, &next_node, 0), 3);
+    fn fretted_note_on_other_string() {
+        // 50 for the fretted note and 25 for each fret that the hand moves
+        assert_eq!(
+            calculate_campanella_difficulty(&note(0, &[(2, 3)]), &note(1, &[(3, 5)]), 0),
+            100
+        );
     }
     #[test]
-    fn simple_from_rest() {
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.5)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-
+    fn repeated_string() {
         assert_eq!(
-            calculate_node_difficulty(&Node::Rest { line_index: 0 }, &next_node, 0),
-            3
+            calculate_campanella_difficulty(&note(0, &[(2, 3)]), &note(1, &[(2, 5)]), 0),
+            400
         );
     }
     #[test]
-    fn simple_to_rest() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.5)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-
+    fn from_start_and_to_rest() {
         assert_eq!(
-            calculate_node_difficulty(&current_node, &Node::Rest { line_index: 1 }, 0),
+            calculate_campanella_difficulty(&Node::Start, &note(0, &[(3, 5)]), 0),
+            50
+        );
+        assert_eq!(
+            calculate_campanella_difficulty(&note(0, &[(2, 3)]), &Node::Rest { line_index: 1 }, 0),
             0
         );
     }
-    #[test]
-    fn simple_avg_fret_diff() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.0)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(1.6)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-
-        assert_eq!(calculate_node_difficulty(&current_node, &next_node, 0), 141);
-    }
-    #[test]
-    fn simple_fret_span() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(4.133333)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(4.133333)),
-                non_zero_fret_span: 3,
-                uses_open_string: false,
-            },
-        };
-
-        assert_eq!(calculate_node_difficulty(&current_node, &next_node, 0), 34);
-    }
-    #[test]
-    fn compound() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(5.0)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(2.0)),
-                non_zero_fret_span: 5,
-                uses_open_string: false,
-            },
-        };
-
-        assert_eq!(calculate_node_difficulty(&current_node, &next_node, 0), 352);
-    }
-    #[test]
-    fn complex() {
-        let current_node = Node::Note {
-            line_index: 0,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(7.3333333)),
-                non_zero_fret_span: 0,
-                uses_open_string: false,
-            },
-        };
-        let next_node = Node::Note {
-            line_index: 1,
-            beat_fingering_combo: BeatFingeringCombo {
-                fingering_combo: vec![],
-                avg_non_zero_fret: Some(OrderedFloat(3.6666666)),
-                non_zero_fret_span: 4,
-                uses_open_string: false,
-            },
-        };
-
-        assert_eq!(calculate_node_difficulty(&current_node, &next_node, 0), 410);
-    }
 }
 
 fn process_path(
     path_nodes: Vec<Node>,
     path_difficulty: i32,
-    measure_break_indices: Vec<usize>,
+    structure_lines: Vec<(usize, Line<BeatVec<PitchFingering>>)>,
 ) -> Arrangement {
     let mut lines: Vec<Line<BeatVec<PitchFingering>>> = path_nodes
         .iter()
@@ -1420,9 +4044,12 @@ fn process_path(
             } => Line::Playable(beat_fingering_combo.fingering_combo.clone()),
         })
         .collect_vec();
-    // Add measure breaks back in
-    for measure_break_index in measure_break_indices.into_iter().sorted() {
-        lines.insert(measure_break_index, Line::MeasureBreak);
+    // Add measure breaks and structure markers back in
+    for (line_index, structure_line) in structure_lines
+        .into_iter()
+        .sorted_by_key(|(line_index, _)| *line_index)
+    {
+        lines.insert(line_index, structure_line);
     }
 
     let max_fret_span: u8 = path_nodes
@@ -1439,10 +4066,22 @@ fn process_path(
         .max()
         .unwrap_or(0);
 
+    let max_fret_stretch_mm: Option<OrderedFloat<f32>> = path_nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Start | Node::Rest { .. } => None,
+            Node::Note {
+                beat_fingering_combo,
+                ..
+            } => beat_fingering_combo.fret_stretch_mm,
+        })
+        .max();
+
     Arrangement {
         lines,
         difficulty: path_difficulty,
         max_fret_span,
+        max_fret_stretch_mm,
     }
 }
 #[cfg(test)]
@@ -1457,10 +4096,13 @@ mod test_process_path {
                 pitch: Pitch::C4,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 3,
+                harmonic: None,
             }],
             avg_non_zero_fret: Some(OrderedFloat(3.0)),
             non_zero_fret_span: 0,
             uses_open_string: false,
+            fret_stretch_mm: None,
+            voices: vec![],
         };
 
         let path_nodes = vec![
@@ -1477,6 +4119,7 @@ mod test_process_path {
             lines: vec![Playable(placeholder_beat_fingering_combo.fingering_combo)],
             difficulty: 123,
             max_fret_span: 0,
+            max_fret_stretch_mm: None,
         };
 
         assert_eq!(arrangement, expected_arrangement);
@@ -1488,10 +4131,13 @@ mod test_process_path {
                 pitch: Pitch::C4,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 3,
+                harmonic: None,
             }],
             avg_non_zero_fret: Some(OrderedFloat(3.0)),
             non_zero_fret_span: 4,
             uses_open_string: false,
+            fret_stretch_mm: None,
+            voices: vec![],
         };
 
         let path_nodes = vec![
@@ -1515,7 +4161,16 @@ mod test_process_path {
             },
         ];
 
-        let arrangement = process_path(path_nodes, 321, vec![0, 2, 5, 7]);
+        let arrangement = process_path(
+            path_nodes,
+            321,
+            vec![
+                (0, MeasureBreak),
+                (2, MeasureBreak),
+                (5, MeasureBreak),
+                (7, MeasureBreak),
+            ],
+        );
 
         let expected_arrangement = Arrangement {
             lines: vec![
@@ -1531,6 +4186,7 @@ mod test_process_path {
             ],
             difficulty: 321,
             max_fret_span: 4,
+            max_fret_stretch_mm: None,
         };
 
         assert_eq!(arrangement, expected_arrangement);
@@ -0,0 +1,664 @@
+use crate::{
+    arrangement::{BeatVec, Line},
+    guitar::{Guitar, PitchFingering},
+    key::Key,
+    pitch::Pitch,
+};
+use itertools::Itertools;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Maximum number of frets between the lowest and highest fretted notes of a generated voicing.
+const MAX_VOICING_FRET_SPAN: usize = 3;
+
+/// Maximum number of fretting fingers, where a barre across the lowest fret counts as one finger.
+const MAX_FRETTING_FINGERS: usize = 4;
+
+/// Spellings of the supported chord qualities, with the spelling used for chord names first, and
+/// their intervals in semitones above the root. Simpler qualities are listed first so that they
+/// are preferred when naming chords.
+const CHORD_QUALITIES: [(&[&str], &[u8]); 21] = [
+    (&["", "maj", "M"], &[0, 4, 7]),
+    (&["m", "min", "-"], &[0, 3, 7]),
+    (&["5"], &[0, 7]),
+    (&["sus4", "sus"], &[0, 5, 7]),
+    (&["sus2"], &[0, 2, 7]),
+    (&["dim", "°", "o"], &[0, 3, 6]),
+    (&["aug", "+"], &[0, 4, 8]),
+    (&["7"], &[0, 4, 7, 10]),
+    (&["maj7", "M7", "Δ", "Δ7"], &[0, 4, 7, 11]),
+    (&["m7", "min7", "-7"], &[0, 3, 7, 10]),
+    (&["6"], &[0, 4, 7, 9]),
+    (&["m6", "min6"], &[0, 3, 7, 9]),
+    (&["m7b5", "m7♭5", "ø", "ø7"], &[0, 3, 6, 10]),
+    (&["dim7", "°7", "o7"], &[0, 3, 6, 9]),
+    (&["mMaj7", "mM7"], &[0, 3, 7, 11]),
+    (&["7sus4", "7sus"], &[0, 5, 7, 10]),
+    (&["add9"], &[0, 4, 7, 14]),
+    (&["madd9"], &[0, 3, 7, 14]),
+    (&["9"], &[0, 4, 7, 10, 14]),
+    (&["maj9", "M9"], &[0, 4, 7, 11, 14]),
+    (&["m9", "min9"], &[0, 3, 7, 10, 14]),
+];
+
+/// Matches a chord symbol made of a root, a chord quality and an optional slash bass note.
+static CHORD_SYMBOL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<root>[A-G][#♯b♭]?)(?P<quality>[^/]*)(?:/(?P<bass>[A-G][#♯b♭]?))?$")
+        .expect("Regex pattern should be valid")
+});
+
+/// Names of the pitch classes from 0 (C) to 11 (B), spelled with sharps.
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// A chord symbol such as `Cmaj7`, `G/B` or `Dsus4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordSymbol {
+    /// Pitch class of the root, from 0 (C) to 11 (B).
+    pub root: u8,
+    /// Semitones of the chord tones above the root, starting with the root itself.
+    pub intervals: Vec<u8>,
+    /// Pitch class of the bass note of a slash chord.
+    pub bass: Option<u8>,
+}
+impl ChordSymbol {
+    /// Parses a chord symbol made of a root, a chord quality and an optional slash bass note.
+    ///
+    /// The supported qualities are major (no suffix), `m`, `dim`, `aug`, `sus2`, `sus4`, `5`, `6`,
+    /// `m6`, `7`, `maj7`, `m7`, `mMaj7`, `m7b5`, `dim7`, `7sus4`, `add9`, `madd9`, `9`, `maj9` and
+    /// `m9`, along with their common alternative spellings.
+    pub fn parse(symbol: &str) -> Option<ChordSymbol> {
+        let captures = CHORD_SYMBOL_REGEX.captures(symbol)?;
+
+        let intervals = CHORD_QUALITIES
+            .iter()
+            .find(|(spellings, _)| spellings.contains(&&captures["quality"]))
+            .map(|(_, intervals)| intervals)?;
+
+        Some(ChordSymbol {
+            root: pitch_class(&captures["root"])?,
+            intervals: intervals.to_vec(),
+            bass: match captures.name("bass") {
+                Some(bass) => Some(pitch_class(bass.as_str())?),
+                None => None,
+            },
+        })
+    }
+
+    /// Identifies the chord sounded by the pitches, returning `None` if they do not form one of
+    /// the supported chord qualities.
+    ///
+    /// Every pitch must be a chord tone and every chord tone must sound, except for the fifth of
+    /// chords of four or more tones. The lowest pitch becomes the slash bass note of inversions.
+    /// Exact matches are preferred over matches without the fifth, then root position chords over
+    /// inversions, then simpler qualities.
+    pub fn identify(pitches: &[Pitch]) -> Option<ChordSymbol> {
+        let bass = pitches.iter().min()?.index() % 12;
+        let sounded_pitch_classes = pitches
+            .iter()
+            .map(|pitch| pitch.index() % 12)
+            .unique()
+            .collect_vec();
+        if sounded_pitch_classes.len() < 2 {
+            return None;
+        }
+
+        sounded_pitch_classes
+            .iter()
+            .flat_map(|&root| {
+                CHORD_QUALITIES
+                    .iter()
+                    .enumerate()
+                    .map(move |(quality_index, (_, intervals))| {
+                        let chord = ChordSymbol {
+                            root,
+                            intervals: intervals.to_vec(),
+                            bass: (root != bass).then_some(bass),
+                        };
+                        (quality_index, chord)
+                    })
+            })
+            .filter(|(_, chord)| {
+                let chord_pitch_classes = chord.pitch_classes();
+                sounded_pitch_classes
+                    .iter()
+                    .all(|pitch_class| chord_pitch_classes.contains(pitch_class))
+                    && chord
+                        .required_pitch_classes()
+                        .iter()
+                        .all(|pitch_class| sounded_pitch_classes.contains(pitch_class))
+            })
+            .min_by_key(|(quality_index, chord)| {
+                let omits_fifth = chord.pitch_classes().len() > sounded_pitch_classes.len();
+                (omits_fifth, chord.bass.is_some(), *quality_index)
+            })
+            .map(|(_, chord)| chord)
+    }
+
+    /// Returns the chord with its root and bass note transposed by a number of semitones.
+    pub fn transposed(&self, semitones: i16) -> ChordSymbol {
+        let transpose = |pitch_class: u8| (pitch_class as i16 + semitones).rem_euclid(12) as u8;
+        ChordSymbol {
+            root: transpose(self.root),
+            intervals: self.intervals.clone(),
+            bass: self.bass.map(transpose),
+        }
+    }
+
+    /// Names the chord with its root and bass note spelled with the accidentals of the key.
+    pub fn name_in_key(&self, key: &Key) -> String {
+        self.name_with(|pitch_class| key.spell_pitch_class(pitch_class))
+    }
+
+    fn name_with(&self, spell_pitch_class: impl Fn(u8) -> &'static str) -> String {
+        let quality = CHORD_QUALITIES
+            .iter()
+            .find(|(_, intervals)| *intervals == self.intervals.as_slice())
+            .map_or("", |(spellings, _)| spellings[0]);
+        match self.bass {
+            Some(bass) => format!(
+                "{}{quality}/{}",
+                spell_pitch_class(self.root),
+                spell_pitch_class(bass)
+            ),
+            None => format!("{}{quality}", spell_pitch_class(self.root)),
+        }
+    }
+
+    /// Lists the pitch classes of the chord tones, starting with the root.
+    pub fn pitch_classes(&self) -> Vec<u8> {
+        self.intervals
+            .iter()
+            .map(|interval| (self.root + interval) % 12)
+            .collect_vec()
+    }
+
+    /// Lists the pitch classes that a voicing must contain. The fifth may be omitted from chords
+    /// of four or more tones.
+    fn required_pitch_classes(&self) -> Vec<u8> {
+        self.intervals
+            .iter()
+            .filter(|&&interval| self.intervals.len() < 4 || interval != 7)
+            .map(|interval| (self.root + interval) % 12)
+            .chain(self.bass)
+            .unique()
+            .collect_vec()
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_parse {
+    use super::*;
+
+    #[test]
+    fn major() {
+        assert_eq!(
+            ChordSymbol::parse("C"),
+            Some(ChordSymbol {
+                root: 0,
+                intervals: vec![0, 4, 7],
+                bass: None
+            })
+        );
+    }
+    #[test]
+    fn qualities() {
+        assert_eq!(
+            ChordSymbol::parse("Cmaj7").unwrap().intervals,
+            [0, 4, 7, 11]
+        );
+        assert_eq!(ChordSymbol::parse("Dsus4").unwrap().intervals, [0, 5, 7]);
+        assert_eq!(
+            ChordSymbol::parse("F#m7b5").unwrap().intervals,
+            [0, 3, 6, 10]
+        );
+        assert_eq!(ChordSymbol::parse("Bbm").unwrap().root, 10);
+    }
+    #[test]
+    fn slash_bass() {
+        assert_eq!(
+            ChordSymbol::parse("G/B"),
+            Some(ChordSymbol {
+                root: 7,
+                intervals: vec![0, 4, 7],
+                bass: Some(11)
+            })
+        );
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(ChordSymbol::parse("Cxyz"), None);
+        assert_eq!(ChordSymbol::parse("H7"), None);
+        assert_eq!(ChordSymbol::parse("c"), None);
+        assert_eq!(ChordSymbol::parse("G/"), None);
+    }
+}
+impl std::fmt::Display for ChordSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.name_with(|pitch_class| PITCH_CLASS_NAMES[pitch_class as usize])
+        )
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_identify {
+    use super::*;
+
+    fn chord_name(pitches: &[Pitch]) -> Option<String> {
+        ChordSymbol::identify(pitches).map(|chord| chord.to_string())
+    }
+
+    #[test]
+    fn triads() {
+        assert_eq!(
+            chord_name(&[Pitch::C3, Pitch::E3, Pitch::G3, Pitch::C4, Pitch::E4]),
+            Some("C".to_owned())
+        );
+        assert_eq!(
+            chord_name(&[Pitch::A2, Pitch::E3, Pitch::A3, Pitch::C4, Pitch::E4]),
+            Some("Am".to_owned())
+        );
+        assert_eq!(
+            chord_name(&[Pitch::D3, Pitch::A3, Pitch::D4, Pitch::G4]),
+            Some("Dsus4".to_owned())
+        );
+    }
+    #[test]
+    fn inversion() {
+        assert_eq!(
+            chord_name(&[Pitch::B2, Pitch::D3, Pitch::G3, Pitch::B3, Pitch::G4]),
+            Some("G/B".to_owned())
+        );
+    }
+    #[test]
+    fn extensions() {
+        assert_eq!(
+            chord_name(&[Pitch::C3, Pitch::E3, Pitch::B3, Pitch::D4]),
+            Some("Cmaj9".to_owned())
+        );
+        assert_eq!(
+            chord_name(&[
+                Pitch::G2,
+                Pitch::B2,
+                Pitch::D3,
+                Pitch::G3,
+                Pitch::B3,
+                Pitch::F4
+            ]),
+            Some("G7".to_owned())
+        );
+    }
+    #[test]
+    fn prefers_root_position() {
+        assert_eq!(
+            chord_name(&[Pitch::C3, Pitch::E3, Pitch::G3, Pitch::A3]),
+            Some("C6".to_owned())
+        );
+        assert_eq!(
+            chord_name(&[Pitch::A2, Pitch::E3, Pitch::G3, Pitch::C4]),
+            Some("Am7".to_owned())
+        );
+    }
+    #[test]
+    fn not_a_chord() {
+        assert_eq!(chord_name(&[]), None);
+        assert_eq!(chord_name(&[Pitch::E2, Pitch::E3]), None);
+        assert_eq!(chord_name(&[Pitch::C3, Pitch::CSharpDFlat3]), None);
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_display {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        for symbol in ["C", "F#m7b5", "G/B", "A#maj7", "Dsus4"] {
+            assert_eq!(ChordSymbol::parse(symbol).unwrap().to_string(), symbol);
+        }
+        assert_eq!(ChordSymbol::parse("Bbmin").unwrap().to_string(), "A#m");
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_transposed {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        assert_eq!(
+            ChordSymbol::parse("G/B").unwrap().transposed(2),
+            ChordSymbol::parse("A/C#").unwrap()
+        );
+        assert_eq!(
+            ChordSymbol::parse("Cmaj7").unwrap().transposed(-1),
+            ChordSymbol::parse("Bmaj7").unwrap()
+        );
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_name_in_key {
+    use super::*;
+    use crate::key::Mode;
+
+    #[test]
+    fn flat_key() {
+        let f_major = Key {
+            tonic: 5,
+            mode: Mode::Major,
+        };
+        assert_eq!(
+            ChordSymbol::parse("A#maj7").unwrap().name_in_key(&f_major),
+            "Bbmaj7"
+        );
+        assert_eq!(
+            ChordSymbol::parse("C/A#").unwrap().name_in_key(&f_major),
+            "C/Bb"
+        );
+    }
+    #[test]
+    fn raised_leading_tone_in_minor_key() {
+        let d_minor = Key {
+            tonic: 2,
+            mode: Mode::Minor,
+        };
+        assert_eq!(
+            ChordSymbol::parse("A/Db").unwrap().name_in_key(&d_minor),
+            "A/C#"
+        );
+        assert_eq!(
+            ChordSymbol::parse("Gm").unwrap().name_in_key(&d_minor),
+            "Gm"
+        );
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_pitch_classes {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        assert_eq!(
+            ChordSymbol::parse("Am7").unwrap().pitch_classes(),
+            vec![9, 0, 4, 7]
+        );
+    }
+}
+#[cfg(test)]
+mod test_chord_symbol_required_pitch_classes {
+    use super::*;
+
+    #[test]
+    fn triad() {
+        assert_eq!(
+            ChordSymbol::parse("G/B").unwrap().required_pitch_classes(),
+            vec![7, 11, 2]
+        );
+    }
+    #[test]
+    fn seventh_omits_fifth() {
+        assert_eq!(
+            ChordSymbol::parse("C7").unwrap().required_pitch_classes(),
+            vec![0, 4, 10]
+        );
+    }
+}
+
+/// Converts a note name (a letter with an optional accidental) into its pitch class.
+pub(crate) fn pitch_class(note_name: &str) -> Option<u8> {
+    let mut chars = note_name.chars();
+    let natural_pitch_class: i8 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental: i8 = match chars.next() {
+        None => 0,
+        Some('#' | '♯') => 1,
+        Some('b' | '♭') => -1,
+        Some(_) => return None,
+    };
+    Some((natural_pitch_class + accidental).rem_euclid(12) as u8)
+}
+#[cfg(test)]
+mod test_pitch_class {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        assert_eq!(pitch_class("C"), Some(0));
+        assert_eq!(pitch_class("F#"), Some(6));
+        assert_eq!(pitch_class("Cb"), Some(11));
+        assert_eq!(pitch_class("B♯"), Some(0));
+        assert_eq!(pitch_class("X"), None);
+    }
+}
+
+/// Generates a playable voicing of the chord on the guitar, returning its pitches from lowest to
+/// highest, or `None` if the chord cannot be voiced.
+///
+/// Voicings sound a contiguous set of strings whose lowest pitch is the bass note (the root, or
+/// the slash bass note), contain every required chord tone and fit within a hand position of
+/// open strings and frets no more than `MAX_VOICING_FRET_SPAN` apart. Voicings with fewer muted
+/// strings, in lower positions and with smaller stretches are preferred.
+pub fn voice_chord(chord: &ChordSymbol, guitar: &Guitar) -> Option<Vec<Pitch>> {
+    let chord_pitch_classes = chord.pitch_classes();
+    let required_pitch_classes = chord.required_pitch_classes();
+    let bass_pitch_class = chord.bass.unwrap_or(chord.root);
+    let min_num_strings = required_pitch_classes.len().max(3).min(guitar.tuning.len());
+
+    // Strings ordered from the lowest pitched string to the highest pitched string
+    let string_ranges = guitar.string_ranges.values().rev().collect_vec();
+    let num_strings = string_ranges.len();
+
+    let mut best_voicing: Option<(usize, Vec<Pitch>)> = None;
+    for position_fret in 1..=(guitar.num_frets as usize).max(1) {
+        let position_frets = position_fret..=position_fret + MAX_VOICING_FRET_SPAN;
+        let string_options = string_ranges
+            .iter()
+            .map(|string_range| {
+                string_range
+                    .iter()
+                    .enumerate()
+                    .filter(|(fret, pitch)| {
+                        (*fret == 0 || position_frets.contains(fret))
+                            && chord_pitch_classes.contains(&(pitch.index() % 12))
+                    })
+                    .map(|(fret, pitch)| (fret, *pitch))
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        for lowest_string in 0..num_strings {
+            for highest_string in (lowest_string + min_num_strings - 1)..num_strings {
+                let voicings = string_options[lowest_string..=highest_string]
+                    .iter()
+                    .multi_cartesian_product();
+                for voicing in voicings {
+                    let pitches = voicing.iter().map(|(_, pitch)| *pitch).collect_vec();
+                    let frets = voicing.iter().map(|(fret, _)| *fret).collect_vec();
+                    let lowest_pitch = *pitches.iter().min().expect("Voicing is not empty.");
+                    if lowest_pitch != pitches[0]
+                        || !pitches.iter().all_unique()
+                        || lowest_pitch.index() % 12 != bass_pitch_class
+                        || !required_pitch_classes.iter().all(|pitch_class| {
+                            pitches.iter().any(|p| p.index() % 12 == *pitch_class)
+                        })
+                        || num_fretting_fingers(&frets) > MAX_FRETTING_FINGERS
+                    {
+                        continue;
+                    }
+
+                    let num_muted_strings = num_strings - pitches.len();
+                    let lowest_fret = frets.iter().filter(|&&fret| fret > 0).min().unwrap_or(&0);
+                    let highest_fret = frets.iter().max().unwrap_or(&0);
+                    let score = num_muted_strings * 10 + lowest_fret * 2 + highest_fret;
+                    if best_voicing
+                        .as_ref()
+                        .is_none_or(|(best_score, _)| score < *best_score)
+                    {
+                        best_voicing = Some((score, pitches.into_iter().sorted().collect_vec()));
+                    }
+                }
+            }
+        }
+    }
+
+    best_voicing.map(|(_, pitches)| pitches)
+}
+#[cfg(test)]
+mod test_voice_chord {
+    use super::*;
+
+    fn voicing(symbol: &str) -> Option<Vec<Pitch>> {
+        voice_chord(&ChordSymbol::parse(symbol).unwrap(), &Guitar::default())
+    }
+
+    #[test]
+    fn no_unisons() {
+        assert_eq!(
+            voicing("Am"),
+            Some(vec![Pitch::A2, Pitch::E3, Pitch::A3, Pitch::C4, Pitch::E4])
+        );
+    }
+    #[test]
+    fn open_chords() {
+        assert_eq!(
+            voicing("C"),
+            Some(vec![Pitch::C3, Pitch::E3, Pitch::G3, Pitch::C4, Pitch::E4])
+        );
+        assert_eq!(
+            voicing("E"),
+            Some(vec![
+                Pitch::E2,
+                Pitch::B2,
+                Pitch::E3,
+                Pitch::GSharpAFlat3,
+                Pitch::B3,
+                Pitch::E4
+            ])
+        );
+    }
+    #[test]
+    fn slash_chord_bass() {
+        let pitches = voicing("G/B").unwrap();
+        assert_eq!(pitches[0].index() % 12, 11);
+    }
+    #[test]
+    fn contains_required_tones() {
+        let pitches = voicing("Cmaj7").unwrap();
+        let pitch_classes = pitches.iter().map(|pitch| pitch.index() % 12).collect_vec();
+        assert_eq!(pitch_classes[0], 0);
+        assert!([0, 4, 11]
+            .iter()
+            .all(|pitch_class| pitch_classes.contains(pitch_class)));
+    }
+    #[test]
+    fn playable_on_guitar() {
+        let guitar = Guitar::default();
+        for symbol in ["Dsus4", "F#m7b5", "Bb", "Ebmaj7", "Adim7", "C#m9"] {
+            let pitches = voicing(symbol).unwrap();
+            assert!(pitches.len() >= 3, "{symbol}");
+            assert!(
+                pitches.iter().all(|pitch| guitar.range.contains(pitch)),
+                "{symbol}"
+            );
+        }
+    }
+    #[test]
+    fn unplayable() {
+        let guitar = Guitar::new(
+            crate::guitar::create_string_tuning(&[Pitch::E4, Pitch::B3]),
+            0,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            voice_chord(&ChordSymbol::parse("C").unwrap(), &guitar),
+            None
+        );
+    }
+}
+
+/// Counts the fingers needed to fret the frets, with the frets at the lowest fretted position
+/// barred by a single finger.
+fn num_fretting_fingers(frets: &[usize]) -> usize {
+    let fretted = frets.iter().filter(|&&fret| fret > 0).collect_vec();
+    match fretted.iter().min() {
+        None => 0,
+        Some(&&lowest_fret) => 1 + fretted.iter().filter(|&&&fret| fret > lowest_fret).count(),
+    }
+}
+#[cfg(test)]
+mod test_num_fretting_fingers {
+    use super::*;
+
+    #[test]
+    fn open() {
+        assert_eq!(num_fretting_fingers(&[0, 0, 0]), 0);
+    }
+    #[test]
+    fn barre() {
+        assert_eq!(num_fretting_fingers(&[1, 3, 3, 2, 1, 1]), 4);
+    }
+    #[test]
+    fn open_chord() {
+        assert_eq!(num_fretting_fingers(&[3, 2, 0, 1, 0]), 3);
+    }
+}
+
+/// Identifies the chord of every line of an arrangement, returning `None` for lines that are not
+/// playable or do not form a chord.
+pub fn line_chords(
+    arrangement_lines: &[Line<BeatVec<PitchFingering>>],
+) -> Vec<Option<ChordSymbol>> {
+    arrangement_lines
+        .iter()
+        .map(|line| match line {
+            Line::Playable(pitch_fingerings) => ChordSymbol::identify(
+                &pitch_fingerings
+                    .iter()
+                    .map(|pitch_fingering| pitch_fingering.pitch)
+                    .collect_vec(),
+            ),
+            _ => None,
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_line_chords {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    #[test]
+    fn simple() {
+        let pitch_fingering = |pitch, string_number, fret| PitchFingering {
+            pitch,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret,
+            harmonic: None,
+        };
+        let arrangement_lines = vec![
+            Line::Playable(vec![
+                pitch_fingering(Pitch::E2, 6, 0),
+                pitch_fingering(Pitch::B2, 5, 2),
+                pitch_fingering(Pitch::GSharpAFlat3, 3, 1),
+            ]),
+            Line::Rest,
+            Line::MeasureBreak,
+            Line::Playable(vec![pitch_fingering(Pitch::E2, 6, 0)]),
+        ];
+        assert_eq!(
+            line_chords(&arrangement_lines)
+                .into_iter()
+                .map(|chord| chord.map(|chord| chord.to_string()))
+                .collect_vec(),
+            vec![Some("E".to_owned()), None, None, None]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{guitar::PitchFingering, parser::SourceSpan, pitch::Pitch, technique::Technique};
+use serde::Serialize;
+use thiserror::Error;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors returned by the library, with the structured details needed to point at the cause.
+///
+/// Line numbers and columns are one-based, matching the way editors number them.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind")]
+pub enum Error {
+    #[error("Input '{token}' on line {line_number} could not be parsed into a pitch.")]
+    UnparsableInput {
+        line_number: usize,
+        column: usize,
+        token: String,
+    },
+    #[error("Chord '{chord}' on line {line_number} cannot be voiced on the configured guitar.")]
+    UnplayableChord { chord: String, line_number: usize },
+    #[error("Pitch {pitch} on line {line_number} cannot be played on any strings of the configured guitar.")]
+    ImpossiblePitch {
+        pitch: Pitch,
+        line_number: usize,
+        /// The span of the pitch token in the input, if the pitch was parsed from input text.
+        span: Option<SourceSpan>,
+    },
+    #[error("The spelled pitch has letter '{letter}' and accidental {accidental}. The letter must be from A to G and the accidental from -2 to 2.")]
+    InvalidSpelledPitch { letter: char, accidental: i8 },
+    #[error("Pitch {pitch} offset by {offset} pitches results in a pitch out of range.")]
+    PitchOutOfRange { pitch: Pitch, offset: i16 },
+    #[error("Pitch {pitch} on line {line_number} transposed by {semitones} semitones results in a pitch out of range.")]
+    TransposedPitchOutOfRange {
+        pitch: Pitch,
+        semitones: i16,
+        line_number: usize,
+    },
+    #[error("Pitch {pitch} on line {line_number} transposed to {transposed_pitch} is outside the range of the configured guitar.")]
+    TransposedPitchOutOfGuitarRange {
+        pitch: Pitch,
+        transposed_pitch: Pitch,
+        line_number: usize,
+    },
+    #[error("Directive '@{directive}' on line {line_number} is not a known directive.")]
+    UnknownDirective {
+        directive: String,
+        line_number: usize,
+    },
+    #[error("Directive '@{directive}' on line {line_number} must come before the first line of pitches.")]
+    MisplacedHeaderDirective {
+        directive: String,
+        line_number: usize,
+    },
+    #[error("Directive '@{directive}' on line {line_number} has an invalid value '{value}'.")]
+    InvalidDirective {
+        directive: String,
+        value: String,
+        line_number: usize,
+    },
+    #[error("The {technique} from pitch {pitch} on line {line_number} has no pitch to reach on the next beat.")]
+    UnconnectedTechnique {
+        pitch: Pitch,
+        technique: Technique,
+        line_number: usize,
+    },
+    #[error("The voices on line {line_number} ({}) cannot each be played on higher strings than the voices below them.", voices.iter().map(|voice| voice.iter().map(|pitch| pitch.to_string()).collect::<Vec<_>>().join(" ")).collect::<Vec<_>>().join(" | "))]
+    UnorderedVoices {
+        line_number: usize,
+        /// The pitches of every voice of the line, from the lowest voice.
+        voices: Vec<Vec<Pitch>>,
+    },
+    #[error("A guitar cannot have a string number of zero (0). Guitar string numbering commences at one (1).")]
+    StringNumberZero,
+    #[error("The string number ({string_number}) is too high. The maximum is {max}.")]
+    StringNumberTooHigh { string_number: u8, max: u8 },
+    #[error("Too many frets ({num_frets}). The maximum is {max}.")]
+    TooManyFrets { num_frets: u8, max: u8 },
+    #[error("Too many frets ({num_frets}) for string starting at pitch {open_string_pitch}. The highest pitch is {highest_pitch}, which would only exist at fret number {highest_pitch_fret}.")]
+    StringRangeExceeded {
+        num_frets: u8,
+        open_string_pitch: Pitch,
+        highest_pitch: Pitch,
+        highest_pitch_fret: u8,
+    },
+    #[error("The capo fret ({capo}) is too high. The maximum is {max}.")]
+    CapoTooHigh { capo: u8, max: u8 },
+    #[error("The scale length ({scale_length} mm) is out of range. The scale length must be between {min} mm and {max} mm.")]
+    ScaleLengthOutOfRange {
+        scale_length: u16,
+        min: u16,
+        max: u16,
+    },
+    #[error("No arrangements were requested.")]
+    NoArrangementsRequested,
+    #[error("Too many arrangements to calculate. The maximum is {max}.")]
+    TooManyArrangementsRequested { max: u8 },
+    #[error("No arrangements could be calculated.")]
+    NoArrangementsCalculated,
+    #[error("No tuning recommendations were requested.")]
+    NoTuningRecommendationsRequested,
+    #[error("The input cannot be played in any of the candidate tunings.")]
+    NoPlayableTuning,
+    #[error("The project document is not valid JSON: {message}")]
+    InvalidProjectJson { message: String },
+    #[error("The project version ({version}) is not a whole number that fits in 32 bits.")]
+    InvalidProjectVersion { version: String },
+    #[error(
+        "The project version ({version}) is newer than the latest supported version ({latest})."
+    )]
+    UnsupportedProjectVersion { version: u32, latest: u32 },
+    #[error("The project document is invalid: {message}")]
+    InvalidProject { message: String },
+    #[error("The fingering pinned on line {line_number} ({fingering:?}) does not play a pitch of that line on the configured guitar.")]
+    InvalidPinnedFingering {
+        line_number: usize,
+        fingering: PitchFingering,
+    },
+    #[error("The fingerings pinned on line {line_number} share a string or play a pitch more often than the line does.")]
+    ConflictingPinnedFingerings { line_number: usize },
+    #[error("{}", errors.iter().map(|error| error.to_string()).collect::<Vec<_>>().join("\n"))]
+    Multiple { errors: Vec<Error> },
+}
+impl Error {
+    /// Combines errors into a single error, returning `None` if there are no errors.
+    pub fn from_errors(mut errors: Vec<Error>) -> Option<Error> {
+        match errors.len() {
+            0 => None,
+            1 => errors.pop(),
+            _ => Some(Error::Multiple { errors }),
+        }
+    }
+}
+#[cfg(test)]
+mod test_from_errors {
+    use super::*;
+
+    #[test]
+    fn no_errors() {
+        assert_eq!(Error::from_errors(vec![]), None);
+    }
+    #[test]
+    fn single_error() {
+        assert_eq!(
+            Error::from_errors(vec![Error::NoArrangementsRequested]),
+            Some(Error::NoArrangementsRequested)
+        );
+    }
+    #[test]
+    fn multiple_errors() {
+        let error = Error::from_errors(vec![
+            Error::NoArrangementsRequested,
+            Error::NoArrangementsCalculated,
+        ])
+        .unwrap();
+        assert_eq!(
+            format!("{error}"),
+            "No arrangements were requested.\nNo arrangements could be calculated."
+        );
+    }
+}
+
+/// The error representation sent across the WASM boundary, with the error message alongside the
+/// structured details.
+#[derive(Serialize)]
+struct ErrorReport<'a> {
+    message: String,
+    #[serde(flatten)]
+    error: &'a Error,
+}
+
+impl From<Error> for wasm_bindgen::JsValue {
+    fn from(error: Error) -> Self {
+        let report = ErrorReport {
+            message: error.to_string(),
+            error: &error,
+        };
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        report
+            .serialize(&serializer)
+            .unwrap_or_else(|_| wasm_bindgen::JsValue::from_str(&report.message))
+    }
+}
+#[cfg(test)]
+mod test_error_report {
+    use super::*;
+
+    #[test]
+    fn structured_fields() {
+        let error = Error::UnparsableInput {
+            line_number: 3,
+            column: 5,
+            token: "xyz".to_owned(),
+        };
+        let report = serde_json::to_value(ErrorReport {
+            message: error.to_string(),
+            error: &error,
+        })
+        .unwrap();
+
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "message": "Input 'xyz' on line 3 could not be parsed into a pitch.",
+                "kind": "UnparsableInput",
+                "line_number": 3,
+                "column": 5,
+                "token": "xyz",
+            })
+        );
+    }
+    #[test]
+    fn nested_errors() {
+        let error = Error::Multiple {
+            errors: vec![Error::ImpossiblePitch {
+                pitch: Pitch::C1,
+                line_number: 2,
+                span: None,
+            }],
+        };
+        let report = serde_json::to_value(ErrorReport {
+            message: error.to_string(),
+            error: &error,
+        })
+        .unwrap();
+
+        assert_eq!(report["kind"], "Multiple");
+        assert_eq!(report["errors"][0]["kind"], "ImpossiblePitch");
+        assert_eq!(report["errors"][0]["pitch"], "C1");
+    }
+}
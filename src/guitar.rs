@@ -1,16 +1,48 @@
+use crate::error::{Error, Result};
 use crate::{arrangement::PitchVec, pitch::Pitch, string_number::StringNumber};
-use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt,
 };
 use strum::IntoEnumIterator;
+use strum_macros::EnumString;
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// A harmonic played instead of a fretted note.
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub enum Harmonic {
+    /// Natural harmonic of the open string, touched above the fret (rendered as `<12>`).
+    Natural,
+    /// Artificial harmonic of the fretted note, touched 12 frets above the fret (rendered as
+    /// `AH17` for fret 5).
+    Artificial,
+}
+
+/// The harmonics that are considered as fingering candidates alongside fretted notes.
+#[derive(
+    Debug, Clone, Copy, Default, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema, EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum HarmonicCandidates {
+    #[default]
+    None,
+    Natural,
+    /// Both natural and artificial harmonics.
+    Artificial,
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
 pub struct PitchFingering {
     pub string_number: StringNumber,
     pub fret: u8,
     pub pitch: Pitch,
+    /// The harmonic played at the fret, or `None` for a fretted note.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub harmonic: Option<Harmonic>,
 }
 impl fmt::Debug for PitchFingering {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -18,7 +50,11 @@ impl fmt::Debug for PitchFingering {
             f,
             "{} | {:?} => {}",
             self.pitch, self.string_number, self.fret
-        )
+        )?;
+        match self.harmonic {
+            Some(harmonic) => write!(f, " ({harmonic:?} harmonic)"),
+            None => Ok(()),
+        }
     }
 }
 #[cfg(test)]
@@ -30,10 +66,25 @@ mod test_pitch_fingering_debug {
             pitch: Pitch::ASharpBFlat4,
             string_number: StringNumber::new(2).unwrap(),
             fret: 3,
+            harmonic: None,
         };
 
         assert_eq!(format!("{:?}", pitch_fingering), "A♯B♭4 | 2_B => 3");
     }
+    #[test]
+    fn harmonic() {
+        let pitch_fingering = PitchFingering {
+            pitch: Pitch::E5,
+            string_number: StringNumber::new(1).unwrap(),
+            fret: 12,
+            harmonic: Some(Harmonic::Natural),
+        };
+
+        assert_eq!(
+            format!("{:?}", pitch_fingering),
+            "E5 | 1_e => 12 (Natural harmonic)"
+        );
+    }
 }
 
 pub const STD_6_STRING_TUNING_OPEN_PITCHES: [Pitch; 6] = [
@@ -49,7 +100,7 @@ pub const STD_6_STRING_TUNING_OPEN_PITCHES: [Pitch; 6] = [
 /// Arguments:
 ///
 /// * `open_string_pitches`: An array slice containing the pitches of the open strings in a guitar
-/// starting at string 1 (the highest string), and ending with string _N_ (the lowest string) where _N_ > 1.
+///   starting at string 1 (the highest string), and ending with string _N_ (the lowest string) where _N_ > 1.
 pub fn create_string_tuning(open_string_pitches: &[Pitch]) -> BTreeMap<StringNumber, Pitch> {
     open_string_pitches
         .iter()
@@ -58,11 +109,21 @@ pub fn create_string_tuning(open_string_pitches: &[Pitch]) -> BTreeMap<StringNum
         .collect::<BTreeMap<StringNumber, Pitch>>()
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "RawGuitar")]
 pub struct Guitar {
     pub tuning: BTreeMap<StringNumber, Pitch>,
     pub num_frets: u8,
+    pub capo: u8,
+    pub scale_length: Option<u16>,
+    /// The harmonics considered as fingering candidates. Harmonics do not extend the range.
+    pub harmonics: HarmonicCandidates,
+    /// The pitches playable on the guitar, calculated from the other fields and never deserialized.
+    #[serde(skip_deserializing)]
     pub range: BTreeSet<Pitch>,
+    /// The pitches playable on every string, calculated from the other fields and never
+    /// deserialized.
+    #[serde(skip_deserializing)]
     pub string_ranges: BTreeMap<StringNumber, Vec<Pitch>>,
 }
 impl Default for Guitar {
@@ -101,10 +162,51 @@ impl Guitar {
         Ok(Guitar {
             tuning: adjusted_tuning,
             num_frets,
+            capo,
+            scale_length: None,
+            harmonics: HarmonicCandidates::default(),
             range,
             string_ranges,
         })
     }
+
+    /// Sets the scale length (the vibrating length of the open strings from the nut to the
+    /// saddle) in millimetres, which enables the physical stretch calculations.
+    pub fn with_scale_length(mut self, scale_length: u16) -> Result<Self> {
+        check_scale_length(scale_length)?;
+        self.scale_length = Some(scale_length);
+        Ok(self)
+    }
+
+    /// Sets the harmonics that are considered as fingering candidates.
+    pub fn with_harmonics(mut self, harmonics: HarmonicCandidates) -> Self {
+        self.harmonics = harmonics;
+        self
+    }
+
+    /// Calculates the physical distance in millimetres between two frets (relative to the capo)
+    /// using the equal temperament fret formula.
+    ///
+    /// Returns `None` if the guitar has no scale length.
+    pub fn fret_distance_mm(&self, lower_fret: u8, upper_fret: u8) -> Option<f32> {
+        let scale_length = self.scale_length? as f32;
+        let distance_from_nut =
+            |fret: u8| scale_length * (1.0 - 2_f32.powf(-((fret + self.capo) as f32) / 12.0));
+
+        Some((distance_from_nut(upper_fret) - distance_from_nut(lower_fret)).abs())
+    }
+
+    /// Lists the pitches that the guitar can play: the pitches of its range and the pitches of
+    /// the harmonics that it considers, which can be above the range.
+    pub fn playable_pitches(&self) -> BTreeSet<Pitch> {
+        Pitch::iter()
+            .filter(|pitch| {
+                self.range.contains(pitch)
+                    || !generate_harmonic_fingerings(&self.string_ranges, pitch, self.harmonics)
+                        .is_empty()
+            })
+            .collect()
+    }
 }
 #[cfg(test)]
 mod test_create_guitar {
@@ -119,6 +221,9 @@ mod test_create_guitar {
         let expected_guitar = Guitar {
             tuning: tuning.clone(),
             num_frets: NUM_FRETS,
+            capo: 0,
+            scale_length: None,
+            harmonics: HarmonicCandidates::None,
             range: BTreeSet::from([
                 Pitch::E2,
                 Pitch::F2,
@@ -192,6 +297,9 @@ mod test_create_guitar {
         let expected_guitar = Guitar {
             tuning: create_string_tuning(&[Pitch::GSharpAFlat4, Pitch::DSharpEFlat4, Pitch::B3]),
             num_frets: NUM_FRETS - CAPO,
+            capo: CAPO,
+            scale_length: None,
+            harmonics: HarmonicCandidates::None,
             range: BTreeSet::from([
                 Pitch::G5,
                 Pitch::D4,
@@ -295,6 +403,9 @@ mod test_create_guitar {
         let expected_guitar = Guitar {
             tuning: tuning.clone(),
             num_frets: NUM_FRETS,
+            capo: 0,
+            scale_length: None,
+            harmonics: HarmonicCandidates::None,
             range: BTreeSet::from([
                 Pitch::E2,
                 Pitch::F2,
@@ -494,13 +605,173 @@ mod test_create_guitar {
     }
 }
 
+/// The fields a guitar is deserialized from. The deserialized guitar is rebuilt with
+/// `Guitar::new` so that invalid guitars are rejected and the ranges are recalculated. The
+/// tuning and number of frets are serialized relative to the capo.
+#[derive(Deserialize)]
+struct RawGuitar {
+    tuning: BTreeMap<StringNumber, Pitch>,
+    num_frets: u8,
+    capo: u8,
+    scale_length: Option<u16>,
+    harmonics: HarmonicCandidates,
+}
+impl TryFrom<RawGuitar> for Guitar {
+    type Error = Error;
+
+    fn try_from(raw_guitar: RawGuitar) -> Result<Self> {
+        let tuning = raw_guitar
+            .tuning
+            .into_iter()
+            .map(|(string_number, pitch)| {
+                Ok((string_number, pitch.plus_offset(-(raw_guitar.capo as i16))?))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        let num_frets = raw_guitar.num_frets.saturating_add(raw_guitar.capo);
+
+        let guitar =
+            Guitar::new(tuning, num_frets, raw_guitar.capo)?.with_harmonics(raw_guitar.harmonics);
+        match raw_guitar.scale_length {
+            Some(scale_length) => guitar.with_scale_length(scale_length),
+            None => Ok(guitar),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_guitar_serde {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let guitar = Guitar::default().with_scale_length(648).unwrap();
+        let serialized = serde_json::to_string(&guitar).unwrap();
+        assert_eq!(serde_json::from_str::<Guitar>(&serialized).unwrap(), guitar);
+    }
+    #[test]
+    fn round_trip_with_capo() {
+        let tuning = create_string_tuning(&STD_6_STRING_TUNING_OPEN_PITCHES);
+        let guitar = Guitar::new(tuning, 18, 2).unwrap();
+        let serialized = serde_json::to_string(&guitar).unwrap();
+        assert_eq!(serde_json::from_str::<Guitar>(&serialized).unwrap(), guitar);
+    }
+    #[test]
+    fn recalculates_ranges() {
+        let mut serialized = serde_json::to_value(Guitar::default()).unwrap();
+        serialized["range"] = serde_json::json!([]);
+        serialized["string_ranges"] = serde_json::json!({});
+        assert_eq!(
+            serde_json::from_value::<Guitar>(serialized.clone()).unwrap(),
+            Guitar::default()
+        );
+
+        let fields = serialized.as_object_mut().unwrap();
+        fields.remove("range");
+        fields.remove("string_ranges");
+        assert_eq!(
+            serde_json::from_value::<Guitar>(serialized).unwrap(),
+            Guitar::default()
+        );
+    }
+    #[test]
+    fn invalid_guitars() {
+        let mut too_many_frets = serde_json::to_value(Guitar::default()).unwrap();
+        too_many_frets["num_frets"] = serde_json::json!(40);
+        let error = serde_json::from_value::<Guitar>(too_many_frets).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "Too many frets (40). The maximum is 30."
+        );
+
+        let mut capo_too_high = serde_json::to_value(Guitar::default()).unwrap();
+        capo_too_high["capo"] = serde_json::json!(9);
+        assert!(serde_json::from_value::<Guitar>(capo_too_high).is_err());
+
+        let mut scale_length_out_of_range = serde_json::to_value(Guitar::default()).unwrap();
+        scale_length_out_of_range["scale_length"] = serde_json::json!(1);
+        assert!(serde_json::from_value::<Guitar>(scale_length_out_of_range).is_err());
+    }
+    #[test]
+    fn field_names() {
+        let serialized = serde_json::to_value(Guitar::default()).unwrap();
+        assert_eq!(serialized["tuning"]["1"], "E4");
+        assert_eq!(serialized["num_frets"], 18);
+        assert_eq!(serialized["capo"], 0);
+        assert!(serialized["scale_length"].is_null());
+    }
+}
+#[cfg(test)]
+mod test_fret_distance_mm {
+    use super::*;
+
+    #[test]
+    fn no_scale_length() {
+        assert_eq!(Guitar::default().fret_distance_mm(1, 5), None);
+    }
+    #[test]
+    fn octave_is_half_the_scale_length() {
+        let guitar = Guitar::default().with_scale_length(650).unwrap();
+        let distance = guitar.fret_distance_mm(0, 12).unwrap();
+        assert!((distance - 325.0).abs() < 0.01);
+    }
+    #[test]
+    fn low_position_stretch_is_wider() {
+        let guitar = Guitar::default().with_scale_length(648).unwrap();
+        let low_stretch = guitar.fret_distance_mm(1, 5).unwrap();
+        let high_stretch = guitar.fret_distance_mm(15, 19).unwrap();
+        assert!(low_stretch > 2.0 * high_stretch);
+        assert_eq!(guitar.fret_distance_mm(5, 1), guitar.fret_distance_mm(1, 5));
+    }
+    #[test]
+    fn capo_shifts_frets() {
+        let tuning = create_string_tuning(&STD_6_STRING_TUNING_OPEN_PITCHES);
+        let guitar = Guitar::new(tuning, 18, 2)
+            .unwrap()
+            .with_scale_length(648)
+            .unwrap();
+        let open_guitar = Guitar::default().with_scale_length(648).unwrap();
+        assert_eq!(
+            guitar.fret_distance_mm(1, 3),
+            open_guitar.fret_distance_mm(3, 5)
+        );
+    }
+    #[test]
+    fn invalid_scale_length() {
+        let error = Guitar::default().with_scale_length(20).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "The scale length (20 mm) is out of range. The scale length must be between 300 mm and 1000 mm."
+        );
+    }
+}
+#[cfg(test)]
+mod test_playable_pitches {
+    use super::*;
+
+    #[test]
+    fn without_harmonics() {
+        let guitar = Guitar::default();
+        assert_eq!(guitar.playable_pitches(), guitar.range);
+    }
+    #[test]
+    fn with_harmonics() {
+        let guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        let playable_pitches = guitar.playable_pitches();
+        assert!(playable_pitches.is_superset(&guitar.range));
+        assert!(!guitar.range.contains(&Pitch::B5));
+        assert!(playable_pitches.contains(&Pitch::B5));
+        assert!(!playable_pitches.contains(&Pitch::C6));
+    }
+}
+
 /// Check if the number of frets is within a maximum limit and returns an error if it exceeds the limit.
 fn check_fret_number(num_frets: u8) -> Result<()> {
     const MAX_NUM_FRETS: u8 = 30;
     if num_frets > MAX_NUM_FRETS {
-        return Err(anyhow!(
-            "Too many frets ({num_frets}). The maximum is {MAX_NUM_FRETS}."
-        ));
+        return Err(Error::TooManyFrets {
+            num_frets,
+            max: MAX_NUM_FRETS,
+        });
     }
 
     Ok(())
@@ -528,9 +799,10 @@ mod test_check_fret_number {
 fn check_capo_number(capo: u8) -> Result<()> {
     const MAX_CAPO: u8 = 8;
     if capo > MAX_CAPO {
-        return Err(anyhow!(
-            "The capo fret ({capo}) is too high. The maximum is {MAX_CAPO}."
-        ));
+        return Err(Error::CapoTooHigh {
+            capo,
+            max: MAX_CAPO,
+        });
     }
     Ok(())
 }
@@ -555,6 +827,36 @@ mod test_check_capo_number {
     }
 }
 
+/// Check if the scale length in millimetres is within the range of fretted string instruments.
+fn check_scale_length(scale_length: u16) -> Result<()> {
+    const MIN_SCALE_LENGTH: u16 = 300;
+    const MAX_SCALE_LENGTH: u16 = 1000;
+    if !(MIN_SCALE_LENGTH..=MAX_SCALE_LENGTH).contains(&scale_length) {
+        return Err(Error::ScaleLengthOutOfRange {
+            scale_length,
+            min: MIN_SCALE_LENGTH,
+            max: MAX_SCALE_LENGTH,
+        });
+    }
+    Ok(())
+}
+#[cfg(test)]
+mod test_check_scale_length {
+    use super::*;
+    #[test]
+    fn valid() {
+        assert!(check_scale_length(300).is_ok());
+        assert!(check_scale_length(648).is_ok());
+        assert!(check_scale_length(1000).is_ok());
+    }
+    #[test]
+    fn invalid() {
+        assert!(check_scale_length(0).is_err());
+        assert!(check_scale_length(299).is_err());
+        assert!(check_scale_length(1001).is_err());
+    }
+}
+
 /// Generates a vector of pitches representing the range of the string.
 ///
 /// Arguments:
@@ -577,10 +879,13 @@ fn create_string_range(open_string_pitch: &Pitch, num_frets: u8) -> Result<Vec<P
                 .last()
                 .expect("The Pitch enum should not be empty.");
             let highest_pitch_fret = highest_pitch.index() - open_string_pitch.index();
-            let err_msg = format!("Too many frets ({num_frets}) for string starting at pitch {open_string_pitch}. \
-                The highest pitch is {highest_pitch}, which would only exist at fret number {highest_pitch_fret}.");
 
-            Err(anyhow!(err_msg))
+            Err(Error::StringRangeExceeded {
+                num_frets,
+                open_string_pitch: *open_string_pitch,
+                highest_pitch: *highest_pitch,
+                highest_pitch_fret,
+            })
         }
     }
 }
@@ -648,6 +953,7 @@ pub fn generate_pitch_fingerings(
                     pitch: *pitch,
                     string_number: *string_number,
                     fret: fret_number as u8,
+                    harmonic: None,
                 })
         })
         .collect();
@@ -697,7 +1003,8 @@ mod test_generate_pitch_fingering {
             vec![PitchFingering {
                 pitch: Pitch::E2,
                 string_number: StringNumber::new(6).unwrap(),
-                fret: 0
+                fret: 0,
+                harmonic: None,
             }]
         );
         assert_eq!(
@@ -706,17 +1013,20 @@ mod test_generate_pitch_fingering {
                 PitchFingering {
                     pitch: Pitch::D3,
                     string_number: StringNumber::new(4).unwrap(),
-                    fret: 0
+                    fret: 0,
+                    harmonic: None,
                 },
                 PitchFingering {
                     pitch: Pitch::D3,
                     string_number: StringNumber::new(5).unwrap(),
-                    fret: 5
+                    fret: 5,
+                    harmonic: None,
                 },
                 PitchFingering {
                     pitch: Pitch::D3,
                     string_number: StringNumber::new(6).unwrap(),
-                    fret: 10
+                    fret: 10,
+                    harmonic: None,
                 }
             ]
         );
@@ -726,17 +1036,20 @@ mod test_generate_pitch_fingering {
                 PitchFingering {
                     pitch: Pitch::CSharpDFlat4,
                     string_number: StringNumber::new(2).unwrap(),
-                    fret: 2
+                    fret: 2,
+                    harmonic: None,
                 },
                 PitchFingering {
                     pitch: Pitch::CSharpDFlat4,
                     string_number: StringNumber::new(3).unwrap(),
-                    fret: 6
+                    fret: 6,
+                    harmonic: None,
                 },
                 PitchFingering {
                     pitch: Pitch::CSharpDFlat4,
                     string_number: StringNumber::new(4).unwrap(),
-                    fret: 11
+                    fret: 11,
+                    harmonic: None,
                 }
             ]
         );
@@ -762,7 +1075,8 @@ mod test_generate_pitch_fingering {
             vec![PitchFingering {
                 pitch: Pitch::DSharpEFlat4,
                 string_number: StringNumber::new(2).unwrap(),
-                fret: 0
+                fret: 0,
+                harmonic: None,
             }]
         );
         assert_eq!(
@@ -771,12 +1085,14 @@ mod test_generate_pitch_fingering {
                 PitchFingering {
                     pitch: Pitch::ASharpBFlat4,
                     string_number: StringNumber::new(1).unwrap(),
-                    fret: 3
+                    fret: 3,
+                    harmonic: None,
                 },
                 PitchFingering {
                     pitch: Pitch::ASharpBFlat4,
                     string_number: StringNumber::new(2).unwrap(),
-                    fret: 7
+                    fret: 7,
+                    harmonic: None,
                 }
             ]
         );
@@ -818,7 +1134,8 @@ mod test_generate_pitch_fingering {
             vec![PitchFingering {
                 pitch: Pitch::E3,
                 string_number: StringNumber::new(4).unwrap(),
-                fret: 2
+                fret: 2,
+                harmonic: None,
             }]
         );
         Ok(())
@@ -865,3 +1182,107 @@ mod test_generate_pitch_fingering {
         Ok(())
     }
 }
+
+/// The frets above which natural harmonics are touched, with the number of semitones that each
+/// harmonic sounds above the open string.
+const NATURAL_HARMONIC_NODES: [(u8, i16); 4] = [(12, 12), (7, 19), (5, 24), (4, 28)];
+
+/// Returns the harmonic fingerings of the pitch on each string of the guitar, which are
+/// candidates alongside the fretted fingerings from `generate_pitch_fingerings`.
+///
+/// Natural harmonics are only generated at frets on the fretboard. Artificial harmonics sound an
+/// octave above the fretted note and are generated for every fret except the open string, whose
+/// octave harmonic is the natural harmonic at the 12th fret.
+pub fn generate_harmonic_fingerings(
+    string_ranges: &BTreeMap<StringNumber, Vec<Pitch>>,
+    pitch: &Pitch,
+    harmonics: HarmonicCandidates,
+) -> PitchVec<PitchFingering> {
+    let harmonic_fingering = |string_number: &StringNumber, fret: u8, harmonic| PitchFingering {
+        pitch: *pitch,
+        string_number: *string_number,
+        fret,
+        harmonic: Some(harmonic),
+    };
+
+    string_ranges
+        .iter()
+        .flat_map(|(string_number, string_range)| {
+            let natural_fingerings = NATURAL_HARMONIC_NODES
+                .into_iter()
+                .filter(|&(fret, semitones)| {
+                    (fret as usize) < string_range.len()
+                        && string_range[0].plus_offset(semitones).ok() == Some(*pitch)
+                })
+                .map(|(fret, _)| harmonic_fingering(string_number, fret, Harmonic::Natural));
+            let artificial_fingerings = string_range
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter(|(_, fretted_pitch)| fretted_pitch.plus_offset(12).ok() == Some(*pitch))
+                .map(|(fret, _)| {
+                    harmonic_fingering(string_number, fret as u8, Harmonic::Artificial)
+                });
+
+            match harmonics {
+                HarmonicCandidates::None => vec![],
+                HarmonicCandidates::Natural => natural_fingerings.collect_vec(),
+                HarmonicCandidates::Artificial => natural_fingerings
+                    .chain(artificial_fingerings)
+                    .collect_vec(),
+            }
+        })
+        .collect()
+}
+#[cfg(test)]
+mod test_generate_harmonic_fingerings {
+    use super::*;
+
+    fn string_ranges() -> BTreeMap<StringNumber, Vec<Pitch>> {
+        BTreeMap::from([(
+            StringNumber::new(1).unwrap(),
+            create_string_range(&Pitch::E4, 12).unwrap(),
+        )])
+    }
+    fn fingering(fret: u8, harmonic: Harmonic) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E6,
+            string_number: StringNumber::new(1).unwrap(),
+            fret,
+            harmonic: Some(harmonic),
+        }
+    }
+
+    #[test]
+    fn natural() {
+        assert_eq!(
+            generate_harmonic_fingerings(&string_ranges(), &Pitch::E6, HarmonicCandidates::Natural),
+            vec![fingering(5, Harmonic::Natural)]
+        );
+        assert_eq!(
+            generate_harmonic_fingerings(&string_ranges(), &Pitch::F6, HarmonicCandidates::Natural),
+            vec![]
+        );
+    }
+    #[test]
+    fn artificial() {
+        assert_eq!(
+            generate_harmonic_fingerings(
+                &string_ranges(),
+                &Pitch::E6,
+                HarmonicCandidates::Artificial
+            ),
+            vec![
+                fingering(5, Harmonic::Natural),
+                fingering(12, Harmonic::Artificial)
+            ]
+        );
+    }
+    #[test]
+    fn none() {
+        assert_eq!(
+            generate_harmonic_fingerings(&string_ranges(), &Pitch::E6, HarmonicCandidates::None),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{
+    arrangement::{BeatVec, Line},
+    pitch::Pitch,
+};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Krumhansl-Kessler key profile of major keys, from the tonic upwards in semitones.
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+
+/// Krumhansl-Kessler key profile of minor keys, from the tonic upwards in semitones.
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Number of sharps (positive) or flats (negative) in the key signature of the major key of every
+/// tonic pitch class, from 0 (C) to 11 (B). Enharmonic keys use the spelling with fewer accidentals.
+const MAJOR_KEY_FIFTHS: [i8; 12] = [0, -5, 2, -3, 4, -1, 6, 1, -4, 3, -2, 5];
+
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+/// A major or minor key.
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub struct Key {
+    /// Pitch class of the tonic, from 0 (C) to 11 (B).
+    pub tonic: u8,
+    pub mode: Mode,
+}
+impl Key {
+    /// Returns the number of sharps (positive) or flats (negative) in the key signature.
+    pub fn fifths(&self) -> i8 {
+        let relative_major_tonic = match self.mode {
+            Mode::Major => self.tonic,
+            Mode::Minor => (self.tonic + 3) % 12,
+        };
+        MAJOR_KEY_FIFTHS[relative_major_tonic as usize]
+    }
+
+    /// Spells the pitch class with the accidentals of the key signature, as a letter followed by
+    /// `#`, `##` or `b` if needed. The raised 6th and 7th of a minor key are spelled by raising the
+    /// 6th and 7th of the key signature (e.g. C# in D minor and B in C minor).
+    pub fn spell_pitch_class(&self, pitch_class: u8) -> &'static str {
+        if self.mode == Mode::Minor {
+            for signature_degree in [8, 10] {
+                if pitch_class == (self.tonic + signature_degree + 1) % 12 {
+                    return raised_name(
+                        self.spell_signature_pitch_class((self.tonic + signature_degree) % 12),
+                    );
+                }
+            }
+        }
+        self.spell_signature_pitch_class(pitch_class)
+    }
+
+    /// Spells the pitch class with the accidentals of the key signature only.
+    fn spell_signature_pitch_class(&self, pitch_class: u8) -> &'static str {
+        const SHARP_NAMES: [&str; 12] = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ];
+        const FLAT_NAMES: [&str; 12] = [
+            "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+        ];
+
+        match (pitch_class, self.fifths()) {
+            // The leading tone of F# major (and D# minor) is E#
+            (5, 6) => "E#",
+            (_, 0..) => SHARP_NAMES[pitch_class as usize],
+            (_, _) => FLAT_NAMES[pitch_class as usize],
+        }
+    }
+
+    /// Spells the pitch in plain text (e.g. `D#3` or `Eb3`) with the accidentals of the key
+    /// signature.
+    pub fn spell(&self, pitch: Pitch) -> String {
+        let pitch_class = pitch.index() % 12;
+        let name = self.spell_pitch_class(pitch_class);
+        // B# is written in the octave below the C that it sounds as
+        let octave =
+            (pitch.index() / 12) as i16 - i16::from(name.starts_with('B') && pitch_class < 2);
+        format!("{name}{octave}")
+    }
+}
+/// Raises the spelling of a pitch class by a semitone while keeping its letter.
+fn raised_name(name: &str) -> &'static str {
+    match name {
+        "C" => "C#",
+        "D" => "D#",
+        "E" => "E#",
+        "F" => "F#",
+        "G" => "G#",
+        "A" => "A#",
+        "B" => "B#",
+        "C#" => "C##",
+        "D#" => "D##",
+        "E#" => "E##",
+        "F#" => "F##",
+        "G#" => "G##",
+        "A#" => "A##",
+        "Db" => "D",
+        "Eb" => "E",
+        "Gb" => "G",
+        "Ab" => "A",
+        "Bb" => "B",
+        _ => unreachable!("The key signature should not spell a pitch class as {name}."),
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match self.mode {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+        };
+        write!(f, "{} {}", self.spell_pitch_class(self.tonic), mode)
+    }
+}
+#[cfg(test)]
+mod test_key {
+    use super::*;
+
+    const E_MINOR: Key = Key {
+        tonic: 4,
+        mode: Mode::Minor,
+    };
+    const B_FLAT_MAJOR: Key = Key {
+        tonic: 10,
+        mode: Mode::Major,
+    };
+
+    #[test]
+    fn fifths() {
+        assert_eq!(E_MINOR.fifths(), 1);
+        assert_eq!(B_FLAT_MAJOR.fifths(), -2);
+        assert_eq!(
+            Key {
+                tonic: 1,
+                mode: Mode::Major
+            }
+            .fifths(),
+            -5
+        );
+    }
+    #[test]
+    fn spell() {
+        assert_eq!(E_MINOR.spell(Pitch::FSharpGFlat3), "F#3");
+        assert_eq!(E_MINOR.spell(Pitch::DSharpEFlat4), "D#4");
+        assert_eq!(B_FLAT_MAJOR.spell(Pitch::DSharpEFlat4), "Eb4");
+        assert_eq!(B_FLAT_MAJOR.spell(Pitch::E2), "E2");
+    }
+    #[test]
+    fn six_sharps() {
+        let d_sharp_minor = Key {
+            tonic: 3,
+            mode: Mode::Minor,
+        };
+        assert_eq!(d_sharp_minor.fifths(), 6);
+        assert_eq!(d_sharp_minor.spell(Pitch::F4), "E#4");
+        assert_eq!(d_sharp_minor.to_string(), "D# minor");
+    }
+    #[test]
+    fn raised_minor_degrees() {
+        let d_minor = Key {
+            tonic: 2,
+            mode: Mode::Minor,
+        };
+        assert_eq!(d_minor.spell(Pitch::CSharpDFlat4), "C#4");
+        assert_eq!(d_minor.spell(Pitch::B3), "B3");
+        assert_eq!(d_minor.spell(Pitch::ASharpBFlat3), "Bb3");
+
+        let g_minor = Key {
+            tonic: 7,
+            mode: Mode::Minor,
+        };
+        assert_eq!(g_minor.spell(Pitch::FSharpGFlat4), "F#4");
+        assert_eq!(g_minor.spell(Pitch::E4), "E4");
+        assert_eq!(g_minor.spell(Pitch::DSharpEFlat4), "Eb4");
+
+        let c_minor = Key {
+            tonic: 0,
+            mode: Mode::Minor,
+        };
+        assert_eq!(c_minor.spell(Pitch::B3), "B3");
+        assert_eq!(c_minor.spell(Pitch::A3), "A3");
+        assert_eq!(c_minor.spell(Pitch::GSharpAFlat3), "Ab3");
+
+        let g_sharp_minor = Key {
+            tonic: 8,
+            mode: Mode::Minor,
+        };
+        assert_eq!(g_sharp_minor.spell(Pitch::G4), "F##4");
+
+        let c_sharp_minor = Key {
+            tonic: 1,
+            mode: Mode::Minor,
+        };
+        assert_eq!(c_sharp_minor.spell(Pitch::C4), "B#3");
+    }
+    #[test]
+    fn display() {
+        assert_eq!(E_MINOR.to_string(), "E minor");
+        assert_eq!(B_FLAT_MAJOR.to_string(), "Bb major");
+    }
+}
+
+/// Estimates the key of the input lines with the Krumhansl-Schmuckler algorithm, correlating the
+/// number of times every pitch class is played with the profile of every major and minor key.
+///
+/// Returns `None` if the input has no pitches or every pitch class is played equally often.
+pub fn detect_key(input_lines: &[Line<BeatVec<Pitch>>]) -> Option<Key> {
+    let mut pitch_class_counts = [0.0; 12];
+    for line in input_lines {
+        if let Line::Playable(pitches) = line {
+            for pitch in pitches {
+                pitch_class_counts[(pitch.index() % 12) as usize] += 1.0;
+            }
+        }
+    }
+
+    [Mode::Major, Mode::Minor]
+        .into_iter()
+        .cartesian_product(0..12)
+        .filter_map(|(mode, tonic)| {
+            let profile = match mode {
+                Mode::Major => MAJOR_PROFILE,
+                Mode::Minor => MINOR_PROFILE,
+            };
+            let rotated_profile = (0..12)
+                .map(|pitch_class| profile[(pitch_class + 12 - tonic as usize) % 12])
+                .collect_vec();
+            correlation(&pitch_class_counts, &rotated_profile)
+                .map(|correlation| (Key { tonic, mode }, correlation))
+        })
+        .fold(
+            None,
+            |best: Option<(Key, f64)>, (key, correlation)| match best {
+                Some((_, best_correlation)) if best_correlation >= correlation => best,
+                _ => Some((key, correlation)),
+            },
+        )
+        .map(|(key, _)| key)
+}
+#[cfg(test)]
+mod test_detect_key {
+    use super::*;
+    use crate::parser::parse_lines;
+
+    fn key_of(input: &str) -> Option<Key> {
+        detect_key(&parse_lines(input.to_owned()).unwrap())
+    }
+
+    #[test]
+    fn major_scale() {
+        assert_eq!(
+            key_of("G3\nA3\nB3\nC4\nD4\nE4\nF#4\nG4\nD4\nB3\nG3"),
+            Some(Key {
+                tonic: 7,
+                mode: Mode::Major
+            })
+        );
+    }
+    #[test]
+    fn minor_arpeggios() {
+        assert_eq!(
+            key_of("E2B2E3G3\nB2F#3B3D#4\nE2B2E3G3\nA2E3A3C4\nE3G3B3"),
+            Some(Key {
+                tonic: 4,
+                mode: Mode::Minor
+            })
+        );
+    }
+    #[test]
+    fn flat_key() {
+        assert_eq!(
+            key_of("Bb2\nD3\nF3\nBb3\nEb3\nG3\nF3\nA3\nBb3").map(|key| key.to_string()),
+            Some("Bb major".to_owned())
+        );
+    }
+    #[test]
+    fn no_pitches() {
+        assert_eq!(key_of("\n---\n"), None);
+    }
+}
+
+/// Calculates the Pearson correlation coefficient of the values, returning `None` if either set of
+/// values is constant.
+fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let mean_a = a.iter().sum::<f64>() / a.len() as f64;
+    let mean_b = b.iter().sum::<f64>() / b.len() as f64;
+
+    let covariance: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| (a - mean_a) * (b - mean_b))
+        .sum();
+    let variance_a: f64 = a.iter().map(|a| (a - mean_a).powi(2)).sum();
+    let variance_b: f64 = b.iter().map(|b| (b - mean_b).powi(2)).sum();
+
+    match variance_a * variance_b {
+        0.0 => None,
+        variance_product => Some(covariance / variance_product.sqrt()),
+    }
+}
+#[cfg(test)]
+mod test_correlation {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        assert_eq!(correlation(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), Some(1.0));
+        assert_eq!(correlation(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]), Some(-1.0));
+    }
+    #[test]
+    fn constant() {
+        assert_eq!(correlation(&[1.0, 1.0, 1.0], &[1.0, 2.0, 3.0]), None);
+    }
+}
@@ -1,17 +1,32 @@
-use anyhow::{anyhow, Result};
-use arrangement::BeatVec;
+use arrangement::{first_arranged_index, Arrangement, BeatVec, Line, StringCosts};
+use chord::line_chords;
+use error::Result;
 use guitar::Guitar;
 use itertools::Itertools;
-use pitch::Pitch;
+use key::{detect_key, Key};
+use parser::{InputHeader, InputMode, LineSpans, ParsedInput};
+use picking::{
+    assign_pick_directions, assign_picking_fingers, PickDirection, PickStyle, PickingFinger,
+};
+use renderer::{BeatLayout, TabAnnotations};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 pub mod arrangement;
+pub mod chord;
+pub mod error;
 pub mod guitar;
+pub mod key;
 pub mod parser;
+pub mod picking;
 pub mod pitch;
+pub mod project;
 pub mod renderer;
+pub mod schema;
 pub mod string_number;
+pub mod technique;
+pub mod transpose;
+pub mod tuning_recommender;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompositionInput {
@@ -19,11 +34,25 @@ pub struct CompositionInput {
     pub tuning_name: String,
     pub guitar_num_frets: u8,
     pub guitar_capo: u8,
+    pub guitar_scale_length: Option<u16>,
     pub num_arrangements: u8,
     pub width: u16,
     pub padding: u8,
     pub playback_index: Option<u16>,
     pub open_string_cost: u16,
+    #[serde(default)]
+    pub string_costs: StringCosts,
+    /// How single notes are picked with a plectrum, unless the input sets it with `@pick`.
+    #[serde(default)]
+    pub pick_style: Option<PickStyle>,
+    #[serde(default)]
+    pub input_mode: InputMode,
+    #[serde(default)]
+    pub show_chord_names: bool,
+    #[serde(default)]
+    pub show_picking_fingers: bool,
+    #[serde(default)]
+    pub show_hand_positions: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -31,17 +60,35 @@ pub struct Composition {
     pub tab: String,
     pub pitches: Vec<BeatVec<String>>,
     pub max_fret_span: u8,
+    pub max_fret_stretch_mm: Option<f32>,
+    pub arrangement: Arrangement,
+    pub line_spans: Vec<LineSpans>,
+    /// The number of beats that every pitch of every arrangement line rings for.
+    pub durations: Vec<BeatVec<u8>>,
+    /// The voice of every pitch of every arrangement line, from 0 for the lowest voice.
+    pub voices: Vec<BeatVec<u8>>,
+    pub beats: Vec<BeatLayout>,
+    /// The name of the chord of every arrangement line, if it forms a chord.
+    pub chord_names: Vec<Option<String>>,
+    /// The picking finger of every pitch of every arrangement line.
+    pub picking_fingers: Vec<BeatVec<PickingFinger>>,
+    /// The plectrum pick direction of every arrangement line that is a single note, or empty if
+    /// no pick style is set, either with `@pick` or in the composition input.
+    pub pick_directions: Vec<Option<PickDirection>>,
+    /// The position of the fretting hand (the fret of the index finger) in every arrangement line.
+    pub hand_positions: Vec<Option<u8>>,
+    /// The estimated key of the input, used to spell the pitches and chord names.
+    pub key: Option<Key>,
+    /// The title, tempo and other settings given by directives at the start of the input.
+    pub header: InputHeader,
 }
 
 #[wasm_bindgen]
 #[cfg(not(tarpaulin_include))]
-pub fn wasm_create_guitar_compositions(input: JsValue) -> Result<JsValue, JsError> {
+pub fn wasm_create_guitar_compositions(input: JsValue) -> Result<JsValue, JsValue> {
     let composition_input: CompositionInput = serde_wasm_bindgen::from_value(input)?;
 
-    let compositions = match wrapper_create_arrangements(composition_input) {
-        Ok(compositions) => compositions,
-        Err(e) => return Err(JsError::new(&e.to_string())),
-    };
+    let compositions = wrapper_create_arrangements(composition_input)?;
 
     Ok(serde_wasm_bindgen::to_value(&compositions)?)
 }
@@ -54,55 +101,57 @@ pub fn wrapper_create_arrangements(
         tuning_name,
         guitar_num_frets,
         guitar_capo,
+        guitar_scale_length,
         num_arrangements,
         width,
         padding,
         playback_index,
         open_string_cost,
+        string_costs,
+        pick_style,
+        input_mode,
+        show_chord_names,
+        show_picking_fingers,
+        show_hand_positions,
     } = composition_input;
 
-    let input_lines: Vec<arrangement::Line<Vec<Pitch>>> = match parser::parse_lines(input_pitches) {
-        Ok(input_lines) => input_lines,
-        Err(e) => return Err(anyhow!(format!("{}", e))),
-    };
-
-    let first_playable_index = input_lines
-        .iter()
-        .position(|line| matches!(line, arrangement::Line::Playable(_)))
-        .unwrap_or(0);
-
-    let pitches: Vec<BeatVec<String>> = input_lines
-        .iter()
-        .skip(first_playable_index)
-        .map(|line| match line {
-            arrangement::Line::Playable(pitches) => {
-                pitches.iter().map(|p| p.plain_text()).collect()
-            }
-            arrangement::Line::Rest => vec!["REST".to_owned()],
-            arrangement::Line::MeasureBreak => vec!["MEASURE_BREAK".to_owned()],
-        })
-        .collect_vec();
-
-    let tuning = parser::create_string_tuning_offset(parser::parse_tuning(&tuning_name));
+    let guitar = create_input_guitar(
+        &input_pitches,
+        &tuning_name,
+        guitar_num_frets,
+        guitar_capo,
+        guitar_scale_length,
+    )?;
 
-    let guitar = Guitar::new(tuning, guitar_num_frets, guitar_capo)?;
+    let parsed_input = parser::parse_input_in_mode(input_pitches, input_mode, &guitar)?
+        .with_default_pick_style(pick_style);
 
-    let arrangements = match arrangement::create_arrangements(
+    let arrangements = arrangement::create_arrangements_with_articulation(
         guitar.clone(),
-        input_lines,
+        parsed_input.lines.clone(),
+        parsed_input.articulation(),
         num_arrangements,
         open_string_cost,
-    ) {
-        Ok(arrangements) => arrangements,
-        Err(e) => return Err(anyhow!(format!("{}", e))),
-    };
+        string_costs,
+    )
+    .map_err(|error| parsed_input.restore_input_line_numbers(error))?;
 
     let compositions = arrangements
-        .iter()
-        .map(|arrangement| Composition {
-            tab: renderer::render_tab(&arrangement.lines, &guitar, width, padding, playback_index),
-            pitches: pitches.clone(),
-            max_fret_span: arrangement.max_fret_span(),
+        .into_iter()
+        .map(|arrangement| {
+            create_composition(
+                arrangement,
+                &guitar,
+                &parsed_input,
+                width,
+                padding,
+                playback_index,
+                ShownAnnotations {
+                    chord_names: show_chord_names,
+                    picking_fingers: show_picking_fingers,
+                    hand_positions: show_hand_positions,
+                },
+            )
         })
         .collect_vec();
 
@@ -111,22 +160,68 @@ pub fn wrapper_create_arrangements(
 #[cfg(test)]
 mod test_wrapper_create_arrangements {
     use super::*;
+    use guitar::PitchFingering;
+    use key::Mode;
+    use parser::SourceSpan;
+    use pitch::Pitch;
+    use string_number::StringNumber;
 
     #[test]
     fn valid_input() {
         let composition_input = CompositionInput {
-            pitches: "E2\nA2\nD3\n\nG3\nB3\n---\nE4".to_owned(),
-            tuning_name: "standard".to_string(),
-            guitar_num_frets: 20,
-            guitar_capo: 0,
-            num_arrangements: 1,
-            width: 30,
             padding: 2,
             playback_index: Some(3),
-            open_string_cost: 0,
+            ..composition_input("E2\nA2\nD3\n\nG3\nB3\n---\nE4")
+        };
+
+        let open_string = |pitch, string_number| {
+            Line::Playable(vec![PitchFingering {
+                pitch,
+                string_number: StringNumber::new(string_number).unwrap(),
+                fret: 0,
+                harmonic: None,
+            }])
+        };
+        let span = |line_number, start_byte, end_byte| SourceSpan {
+            line_number,
+            start_byte,
+            end_byte,
+            start_column: 1,
+            end_column: 1 + end_byte - start_byte,
+        };
+        let pitch_line_spans = |line_number, start_byte, end_byte| LineSpans {
+            line: span(line_number, start_byte, end_byte),
+            pitches: vec![span(line_number, start_byte, end_byte)],
+        };
+        let other_line_spans = |line_number, start_byte, end_byte| LineSpans {
+            line: span(line_number, start_byte, end_byte),
+            pitches: vec![],
+        };
+        let beat = |beat_index, line_index, start_column| BeatLayout {
+            beat_index,
+            line_index,
+            row_group_index: 0,
+            start_row: 1,
+            end_row: 7,
+            start_column,
+            end_column: start_column + 1,
         };
 
         let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].arrangement.lines,
+            vec![
+                open_string(Pitch::E2, 6),
+                open_string(Pitch::A2, 5),
+                open_string(Pitch::D3, 4),
+                Line::Rest,
+                open_string(Pitch::G3, 3),
+                open_string(Pitch::B3, 2),
+                Line::MeasureBreak,
+                open_string(Pitch::E4, 1),
+            ]
+        );
+        assert_eq!(compositions[0].arrangement.difficulty(), 8);
         let expected_composition = Composition {
             tab: "           ▼\n--------------------|--0------\n-----------------0--|---------\n--------------0-----|---------\n--------0-----------|---------\n-----0--------------|---------\n--0-----------------|---------\n           ▲\n".to_owned(),
             pitches: vec![
@@ -140,6 +235,47 @@ mod test_wrapper_create_arrangements {
                 vec!["E4".to_owned()]
                 ],
             max_fret_span: 0,
+            max_fret_stretch_mm: None,
+            arrangement: compositions[0].arrangement.clone(),
+            line_spans: vec![
+                pitch_line_spans(1, 0, 2),
+                pitch_line_spans(2, 3, 5),
+                pitch_line_spans(3, 6, 8),
+                other_line_spans(4, 9, 9),
+                pitch_line_spans(5, 10, 12),
+                pitch_line_spans(6, 13, 15),
+                other_line_spans(7, 16, 19),
+                pitch_line_spans(8, 20, 22),
+            ],
+            durations: vec![vec![1], vec![1], vec![1], vec![], vec![1], vec![1], vec![], vec![1]],
+            voices: vec![vec![0], vec![0], vec![0], vec![], vec![0], vec![0], vec![], vec![0]],
+            beats: vec![
+                beat(0, 0, 2),
+                beat(1, 1, 5),
+                beat(2, 2, 8),
+                beat(3, 3, 11),
+                beat(4, 4, 14),
+                beat(5, 5, 17),
+                beat(6, 7, 23),
+            ],
+            chord_names: vec![None; 8],
+            picking_fingers: vec![
+                vec![PickingFinger::Thumb],
+                vec![PickingFinger::Thumb],
+                vec![PickingFinger::Thumb],
+                vec![],
+                vec![PickingFinger::Index],
+                vec![PickingFinger::Middle],
+                vec![],
+                vec![PickingFinger::Ring],
+            ],
+            pick_directions: vec![],
+            hand_positions: vec![None; 8],
+            key: Some(Key {
+                tonic: 4,
+                mode: Mode::Minor,
+            }),
+            header: InputHeader::default(),
         };
 
         assert_eq!(compositions[0], expected_composition);
@@ -147,18 +283,26 @@ mod test_wrapper_create_arrangements {
     #[test]
     fn empty_input() {
         let composition_input = CompositionInput {
-            pitches: "\n\n\n---\n \n".to_owned(),
-            tuning_name: "standard".to_string(),
-            guitar_num_frets: 20,
-            guitar_capo: 0,
             num_arrangements: 2,
-            width: 30,
             padding: 2,
             playback_index: Some(3),
-            open_string_cost: 0,
+            ..composition_input("\n\n\n---\n \n")
+        };
+
+        let line_spans = |line_number, start_byte, end_byte| LineSpans {
+            line: SourceSpan {
+                line_number,
+                start_byte,
+                end_byte,
+                start_column: 1,
+                end_column: 1 + end_byte - start_byte,
+            },
+            pitches: vec![],
         };
 
         let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert!(compositions[0].arrangement.lines.is_empty());
+        assert_eq!(compositions[0].arrangement.difficulty(), 0);
         let expected_compositions = vec![
             Composition {
                 tab: "".to_owned(),
@@ -170,6 +314,24 @@ mod test_wrapper_create_arrangements {
                     vec!["REST".to_owned()]
                 ],
                 max_fret_span: 0,
+                max_fret_stretch_mm: None,
+                arrangement: compositions[0].arrangement.clone(),
+                line_spans: vec![
+                    line_spans(1, 0, 0),
+                    line_spans(2, 1, 1),
+                    line_spans(3, 2, 2),
+                    line_spans(4, 3, 6),
+                    line_spans(5, 7, 8),
+                ],
+                durations: vec![vec![]; 5],
+                voices: vec![vec![]; 5],
+                beats: vec![],
+                chord_names: vec![],
+                picking_fingers: vec![],
+                pick_directions: vec![],
+                hand_positions: vec![],
+                key: None,
+                header: InputHeader::default(),
             };
             2
         ];
@@ -179,16 +341,560 @@ mod test_wrapper_create_arrangements {
     #[test]
     fn invalid_input() {
         let composition_input = CompositionInput {
-            pitches: "E2\nA2\nD3\n???\nG3\nB3\nE4".to_owned(),
-            tuning_name: "standard".to_string(),
-            guitar_num_frets: 20,
-            guitar_capo: 0,
-            num_arrangements: 1,
             width: 20,
             padding: 2,
             playback_index: Some(3),
-            open_string_cost: 0,
+            ..composition_input("E2\nA2\nD3\n???\nG3\nB3\nE4")
         };
         assert!(wrapper_create_arrangements(composition_input).is_err());
     }
+    #[test]
+    fn scale_length() {
+        let composition_input = CompositionInput {
+            guitar_scale_length: Some(648),
+            width: 20,
+            padding: 2,
+            ..composition_input("G2B3")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert!(compositions[0].max_fret_stretch_mm.is_some());
+    }
+    #[test]
+    fn header() {
+        let composition_input = CompositionInput {
+            width: 20,
+            padding: 2,
+            ..composition_input("@title Drop D riff\n@tuning dropd\n@tempo 120\nD2\nD2")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].header,
+            InputHeader {
+                title: Some("Drop D riff".to_owned()),
+                tuning_name: Some("dropd".to_owned()),
+                tempo: Some(120),
+                ..Default::default()
+            }
+        );
+        assert!(compositions[0].tab.contains("\n--0--0---"));
+    }
+    #[test]
+    fn techniques() {
+        let composition_input = CompositionInput {
+            width: 20,
+            ..composition_input("\nA3h\nB3\nB3p\nA3\nE4^2")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert!(compositions[0].tab.contains("\n-2h-4-4p-2----------"));
+        // The bend is played in the same position as the pull-off
+        assert!(compositions[0].tab.contains("\n-----------5b7------"));
+    }
+    #[test]
+    fn sustained_notes() {
+        let composition_input = CompositionInput {
+            width: 20,
+            ..composition_input("A2:3\nC3\nD3")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        // C3 is played on the low E string while the A2 on the A string rings
+        assert!(compositions[0].tab.contains("\n-0------"));
+        assert!(compositions[0].tab.contains("\n---8-10-"));
+    }
+    #[test]
+    fn campanella_style() {
+        let composition_input = CompositionInput {
+            width: 20,
+            ..composition_input("@style campanella\nA3\nB3\nC4\nD4")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert!(compositions[0].tab.contains("\n---0---3-"));
+        assert!(compositions[0].tab.contains("\n-2---5---"));
+    }
+    #[test]
+    fn voices() {
+        let composition_input = CompositionInput {
+            width: 20,
+            ..composition_input("C3 | B2")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input.clone()).unwrap();
+        assert!(compositions[0].tab.contains("\n-2---"));
+        assert!(compositions[0].tab.contains("\n-8---"));
+
+        // The melody rests on the first line while the bass note sounds
+        let compositions = wrapper_create_arrangements(CompositionInput {
+            pitches: "A2 |\n| E4".to_owned(),
+            ..composition_input
+        })
+        .unwrap();
+        assert_eq!(compositions[0].voices, vec![vec![0], vec![1]]);
+    }
+    #[test]
+    fn sustained_voice() {
+        let composition_input = CompositionInput {
+            width: 20,
+            ..composition_input("A2:2 | D3\n| B2")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(compositions[0].durations, vec![vec![2, 1], vec![1]]);
+        assert_eq!(compositions[0].voices, vec![vec![0, 1], vec![1]]);
+        // The melody B2 is played above the bass A2 that is still ringing under it
+        assert!(compositions[0].tab.contains("\n---2----"));
+        assert!(compositions[0].tab.contains("\n-5------"));
+    }
+    #[test]
+    fn harmonics() {
+        let composition_input = CompositionInput {
+            guitar_num_frets: 18,
+            width: 20,
+            ..composition_input("@harmonics natural\nE6\nB5")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input.clone()).unwrap();
+        assert!(compositions[0].tab.starts_with("\n-<5>-<7>---"));
+
+        let compositions = wrapper_create_arrangements(CompositionInput {
+            pitches: "@harmonics artificial\nF6".to_owned(),
+            ..composition_input
+        })
+        .unwrap();
+        assert!(compositions[0].tab.contains("-AH25-"));
+    }
+    #[test]
+    fn chord_names() {
+        let composition_input = CompositionInput {
+            show_chord_names: true,
+            ..composition_input("Am\nE4\nG/B")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input.clone()).unwrap();
+        assert_eq!(
+            compositions[0].chord_names,
+            vec![Some("Am".to_owned()), None, Some("G/B".to_owned())]
+        );
+        assert!(compositions[0].tab.starts_with(" Am  G/B\n"));
+        assert_eq!(compositions[0].beats[0].start_row, 2);
+
+        let compositions = wrapper_create_arrangements(CompositionInput {
+            pitches: "[Verse]\nA2\n[Am]\nCmaj".to_owned(),
+            ..composition_input
+        })
+        .unwrap();
+        // The name of the C chord would touch the name before it, so it is moved after it
+        assert!(compositions[0].tab.starts_with(" Verse\n   Am C\n"));
+    }
+    #[test]
+    fn pick_directions() {
+        let composition_input = composition_input("@pick alternate\nG3\nC4\nE4\nC4\nG3");
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].pick_directions,
+            vec![
+                Some(PickDirection::Down),
+                Some(PickDirection::Up),
+                Some(PickDirection::Down),
+                Some(PickDirection::Up),
+                Some(PickDirection::Down)
+            ]
+        );
+        assert!(compositions[0].tab.starts_with(" ⊓ V ⊓ V ⊓\n"));
+        assert_eq!(compositions[0].beats[0].start_row, 2);
+    }
+    #[test]
+    fn pick_style() {
+        let composition_input = CompositionInput {
+            pick_style: Some(PickStyle::Alternate),
+            ..composition_input("G3\nC4\nE4")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input.clone()).unwrap();
+        assert_eq!(
+            compositions[0].pick_directions,
+            vec![
+                Some(PickDirection::Down),
+                Some(PickDirection::Up),
+                Some(PickDirection::Down)
+            ]
+        );
+        assert!(compositions[0].tab.starts_with(" ⊓ V ⊓\n"));
+
+        // The `@pick` directive takes precedence over the pick style of the input
+        let compositions = wrapper_create_arrangements(CompositionInput {
+            pitches: "@pick economy\nG3\nC4\nE4".to_owned(),
+            ..composition_input
+        })
+        .unwrap();
+        assert_eq!(compositions[0].header.pick_style, Some(PickStyle::Economy));
+    }
+    #[test]
+    fn picking_fingers() {
+        let composition_input = CompositionInput {
+            show_picking_fingers: true,
+            ..composition_input("E2\nG3\nG3\nE4")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].picking_fingers,
+            vec![
+                vec![PickingFinger::Thumb],
+                vec![PickingFinger::Index],
+                vec![PickingFinger::Middle],
+                vec![PickingFinger::Ring]
+            ]
+        );
+        assert!(compositions[0].tab.starts_with(" p i m a\n"));
+        assert_eq!(compositions[0].beats[0].start_row, 2);
+    }
+    #[test]
+    fn hand_positions() {
+        let composition_input = CompositionInput {
+            show_hand_positions: true,
+            ..composition_input("A2\nC3\nE3\nG5\nA5\nC6")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].hand_positions,
+            vec![None, Some(8), Some(9), Some(17), Some(17), Some(17)]
+        );
+        assert!(compositions[0]
+            .tab
+            .starts_with("   Pos. VIII Pos. IX Pos. XVII\n"));
+        assert_eq!(compositions[0].beats[0].start_row, 2);
+    }
+    #[test]
+    fn key_spelling() {
+        let composition_input = composition_input("E2\nF#2\nG2\nB2\nD#3\nE3");
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].key.map(|key| key.to_string()),
+            Some("E minor".to_owned())
+        );
+        assert_eq!(compositions[0].pitches[1], vec!["F#2".to_owned()]);
+        assert_eq!(compositions[0].pitches[4], vec!["D#3".to_owned()]);
+    }
+    #[test]
+    fn written_spelling() {
+        let composition_input = composition_input("F#3\nGb3\nE#4\nAbb3\nAm");
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].pitches[..4],
+            vec![
+                vec!["F#3".to_owned()],
+                vec!["Gb3".to_owned()],
+                vec!["E#4".to_owned()],
+                vec!["Abb3".to_owned()],
+            ]
+        );
+        assert_eq!(compositions[0].pitches[4].len(), 5);
+    }
+    #[test]
+    fn relative_input_mode() {
+        let composition_input = CompositionInput {
+            width: 20,
+            padding: 2,
+            input_mode: InputMode::Relative,
+            ..composition_input("E2\nA\nD\nG\nB'")
+        };
+
+        let compositions = wrapper_create_arrangements(composition_input).unwrap();
+        assert_eq!(
+            compositions[0].pitches,
+            vec![
+                vec!["E2".to_owned()],
+                vec!["A2".to_owned()],
+                vec!["D3".to_owned()],
+                vec!["G3".to_owned()],
+                vec!["B4".to_owned()],
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn composition_input(pitches: &str) -> CompositionInput {
+    CompositionInput {
+        pitches: pitches.to_owned(),
+        tuning_name: "standard".to_owned(),
+        guitar_num_frets: 20,
+        guitar_capo: 0,
+        guitar_scale_length: None,
+        num_arrangements: 1,
+        width: 30,
+        padding: 1,
+        playback_index: None,
+        open_string_cost: 0,
+        string_costs: StringCosts::default(),
+        pick_style: None,
+        input_mode: InputMode::Absolute,
+        show_chord_names: false,
+        show_picking_fingers: false,
+        show_hand_positions: false,
+    }
+}
+
+/// Creates a guitar from the tuning name, number of frets, capo and optional scale length.
+pub fn create_guitar(
+    tuning_name: &str,
+    guitar_num_frets: u8,
+    guitar_capo: u8,
+    guitar_scale_length: Option<u16>,
+) -> Result<Guitar> {
+    let tuning = parser::create_string_tuning_offset(parser::parse_tuning(tuning_name));
+
+    let guitar = Guitar::new(tuning, guitar_num_frets, guitar_capo)?;
+    match guitar_scale_length {
+        Some(scale_length) => guitar.with_scale_length(scale_length),
+        None => Ok(guitar),
+    }
+}
+#[cfg(test)]
+mod test_create_guitar {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let guitar = create_guitar("dropd", 20, 2, Some(648)).unwrap();
+        assert_eq!(guitar.num_frets, 18);
+        assert_eq!(guitar.capo, 2);
+        assert_eq!(guitar.scale_length, Some(648));
+        assert_eq!(
+            guitar.tuning[&string_number::StringNumber::new(6).unwrap()],
+            pitch::Pitch::E2
+        );
+    }
+    #[test]
+    fn invalid() {
+        assert!(create_guitar("standard", 20, 10, None).is_err());
+    }
+}
+
+/// Creates the guitar that the input pitches are arranged on. The tuning, capo, number of frets and
+/// harmonics given by directives at the start of the input override the given settings.
+pub fn create_input_guitar(
+    input_pitches: &str,
+    tuning_name: &str,
+    guitar_num_frets: u8,
+    guitar_capo: u8,
+    guitar_scale_length: Option<u16>,
+) -> Result<Guitar> {
+    let header = parser::parse_input_header(input_pitches)?;
+    let guitar = create_guitar(
+        header.tuning_name.as_deref().unwrap_or(tuning_name),
+        header.num_frets.unwrap_or(guitar_num_frets),
+        header.capo.unwrap_or(guitar_capo),
+        guitar_scale_length,
+    )?;
+    Ok(match header.harmonics {
+        Some(harmonics) => guitar.with_harmonics(harmonics),
+        None => guitar,
+    })
+}
+#[cfg(test)]
+mod test_create_input_guitar {
+    use super::*;
+
+    #[test]
+    fn header_overrides() {
+        let guitar =
+            create_input_guitar("@tuning dropd\n@capo 2\nD2", "standard", 20, 0, None).unwrap();
+        assert_eq!(guitar, create_guitar("dropd", 20, 2, None).unwrap());
+    }
+    #[test]
+    fn standard_tuning_overrides() {
+        let guitar = create_input_guitar("@tuning standard\nE2", "dropd", 20, 0, None).unwrap();
+        assert_eq!(guitar, create_guitar("standard", 20, 0, None).unwrap());
+    }
+    #[test]
+    fn harmonics() {
+        let guitar =
+            create_input_guitar("@harmonics natural\nE6", "standard", 20, 0, None).unwrap();
+        assert_eq!(guitar.harmonics, guitar::HarmonicCandidates::Natural);
+    }
+    #[test]
+    fn no_header() {
+        assert_eq!(
+            create_input_guitar("E2\n@capo 2", "openg", 18, 1, Some(648)).unwrap(),
+            create_guitar("openg", 18, 1, Some(648)).unwrap()
+        );
+    }
+    #[test]
+    fn invalid_header() {
+        assert!(create_input_guitar("@frets many", "standard", 18, 0, None).is_err());
+        assert!(create_input_guitar("@capo 30", "standard", 18, 0, None).is_err());
+    }
+}
+
+/// Lists the plain text pitches of every line from the first arranged line onwards. Pitches keep
+/// their spelling as written in the input, otherwise they are spelled with the accidentals of the
+/// key if it is known.
+pub(crate) fn composition_pitches(
+    parsed_input: &ParsedInput,
+    key: Option<&Key>,
+) -> Vec<BeatVec<String>> {
+    parsed_input
+        .lines
+        .iter()
+        .zip(&parsed_input.spellings)
+        .skip(first_arranged_index(&parsed_input.lines))
+        .map(|(line, spellings)| match line {
+            Line::Playable(pitches) => pitches
+                .iter()
+                .zip(spellings)
+                .map(|(pitch, spelled_pitch)| match (spelled_pitch, key) {
+                    (Some(spelled_pitch), _) => spelled_pitch.plain_text(),
+                    (None, Some(key)) => key.spell(*pitch),
+                    (None, None) => pitch.plain_text(),
+                })
+                .collect(),
+            Line::Rest => vec!["REST".to_owned()],
+            Line::MeasureBreak => vec!["MEASURE_BREAK".to_owned()],
+            Line::RepeatStart => vec!["REPEAT_START".to_owned()],
+            Line::RepeatEnd => vec!["REPEAT_END".to_owned()],
+            Line::Ending(ending_number) => vec![format!("ENDING_{ending_number}")],
+            Line::Section(name) => vec![format!("SECTION_{name}")],
+        })
+        .collect_vec()
+}
+
+/// Lists the input spans of every line from the first arranged line onwards, aligned with the
+/// composition pitches.
+pub(crate) fn composition_line_spans(parsed_input: &ParsedInput) -> Vec<LineSpans> {
+    parsed_input
+        .spans
+        .iter()
+        .skip(first_arranged_index(&parsed_input.lines))
+        .cloned()
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_composition_line_spans {
+    use super::*;
+
+    #[test]
+    fn aligned_with_pitches() {
+        let parsed_input = parser::parse_input("\n-\n E2\n\nA2 ".to_owned()).unwrap();
+
+        let line_spans = composition_line_spans(&parsed_input);
+        assert_eq!(
+            line_spans.len(),
+            composition_pitches(&parsed_input, None).len()
+        );
+        assert_eq!(line_spans[0].line.line_number, 3);
+        assert_eq!(line_spans[0].pitches[0].start_byte, 4);
+        assert_eq!(line_spans[2].pitches[0].start_byte, 8);
+    }
+}
+
+/// Lists the values of every parsed line (e.g. the techniques of its pitches) from the first
+/// arranged line onwards, aligned with the arrangement lines.
+fn arranged_line_values<T: Clone>(parsed_input: &ParsedInput, line_values: &[T]) -> Vec<T> {
+    line_values
+        .iter()
+        .skip(first_arranged_index(&parsed_input.lines))
+        .cloned()
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_arranged_line_values {
+    use super::*;
+
+    #[test]
+    fn skips_leading_lines() {
+        let parsed_input = parser::parse_input("\n-\nA2:2 | E4\n|C4".to_owned()).unwrap();
+        assert_eq!(
+            arranged_line_values(&parsed_input, &parsed_input.durations),
+            vec![vec![2, 1], vec![1]]
+        );
+        assert_eq!(
+            arranged_line_values(&parsed_input, &parsed_input.voices),
+            vec![vec![0, 1], vec![1]]
+        );
+    }
+}
+
+/// The optional annotations to show in the tab of a composition.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ShownAnnotations {
+    pub chord_names: bool,
+    pub picking_fingers: bool,
+    pub hand_positions: bool,
+}
+
+pub(crate) fn create_composition(
+    arrangement: Arrangement,
+    guitar: &Guitar,
+    parsed_input: &ParsedInput,
+    width: u16,
+    padding: u8,
+    playback_index: Option<u16>,
+    shown_annotations: ShownAnnotations,
+) -> Composition {
+    let key = detect_key(&parsed_input.lines);
+    let chord_names = line_chords(&arrangement.lines)
+        .into_iter()
+        .map(|chord| {
+            chord.map(|chord| match &key {
+                Some(key) => chord.name_in_key(key),
+                None => chord.to_string(),
+            })
+        })
+        .collect_vec();
+    let picking_fingers = assign_picking_fingers(&arrangement.lines, guitar.string_ranges.len());
+    let pick_directions = parsed_input.header.pick_style.map_or(vec![], |pick_style| {
+        assign_pick_directions(&arrangement.lines, pick_style)
+    });
+    let techniques = arranged_line_values(parsed_input, &parsed_input.techniques);
+    let hand_positions = arrangement.hand_positions();
+    let tab_render = renderer::render_tab_layout(
+        &arrangement.lines,
+        guitar,
+        width,
+        padding,
+        playback_index,
+        TabAnnotations {
+            chord_names: shown_annotations
+                .chord_names
+                .then_some(chord_names.as_slice()),
+            techniques: Some(&techniques),
+            picking_fingers: shown_annotations
+                .picking_fingers
+                .then_some(picking_fingers.as_slice()),
+            pick_directions: parsed_input
+                .header
+                .pick_style
+                .map(|_| pick_directions.as_slice()),
+            hand_positions: shown_annotations
+                .hand_positions
+                .then_some(hand_positions.as_slice()),
+        },
+    );
+
+    Composition {
+        tab: tab_render.tab,
+        pitches: composition_pitches(parsed_input, key.as_ref()),
+        max_fret_span: arrangement.max_fret_span(),
+        max_fret_stretch_mm: arrangement.max_fret_stretch_mm(),
+        arrangement,
+        line_spans: composition_line_spans(parsed_input),
+        durations: arranged_line_values(parsed_input, &parsed_input.durations),
+        voices: arranged_line_values(parsed_input, &parsed_input.voices),
+        beats: tab_render.beats,
+        key,
+        chord_names,
+        picking_fingers,
+        pick_directions,
+        hand_positions,
+        header: parsed_input.header.clone(),
+    }
 }
@@ -1,4 +1,4 @@
-use guitar_tab_generator::*;
+use guitar_tab_generator::{arrangement::StringCosts, *};
 
 fn main() {
     let pitches = "E4
@@ -94,8 +94,9 @@ fn main() {
     .to_owned();
 
     let comp: CompositionInput = CompositionInput {
-        pitches: pitches,
+        pitches,
         guitar_capo: 0,
+        guitar_scale_length: None,
         guitar_num_frets: 18,
         tuning_name: "standard".to_owned(),
         num_arrangements: 1,
@@ -103,6 +104,12 @@ fn main() {
         padding: 2,
         playback_index: Some(1),
         open_string_cost: 1000,
+        string_costs: StringCosts::default(),
+        pick_style: None,
+        input_mode: parser::InputMode::Absolute,
+        show_chord_names: false,
+        show_picking_fingers: false,
+        show_hand_positions: false,
     };
 
     let comp = wrapper_create_arrangements(comp).unwrap();
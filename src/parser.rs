@@ -1,14 +1,25 @@
 use crate::{
-    arrangement::{BeatVec, Line},
-    guitar::{create_string_tuning, STD_6_STRING_TUNING_OPEN_PITCHES},
-    pitch::Pitch,
+    arrangement::{ArrangementStyle, Articulation, BeatVec, Line},
+    chord::{pitch_class, voice_chord, ChordSymbol},
+    error::{Error, Result},
+    guitar::{create_string_tuning, Guitar, HarmonicCandidates, STD_6_STRING_TUNING_OPEN_PITCHES},
+    picking::PickStyle,
+    pitch::{parse_accidental, Pitch, SpelledPitch},
     string_number::StringNumber,
+    technique::Technique,
+    transpose::{parse_transposition, transpose_pitches},
 };
-use anyhow::{anyhow, Result};
 use itertools::Itertools;
-use regex::{Regex, RegexBuilder};
-use std::{collections::BTreeMap, result::Result::Ok, sync::Arc};
-use std::{collections::HashSet, str::FromStr};
+use regex::{Captures, Regex, RegexBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::{
+    collections::HashSet,
+    fmt,
+    ops::{Range, RangeInclusive},
+    str::FromStr,
+};
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 use wasm_bindgen::prelude::*;
@@ -36,20 +47,21 @@ pub fn get_tuning_names() -> Result<JsValue, JsError> {
     Ok(serde_wasm_bindgen::to_value(&tuning_names)?)
 }
 
-/// Generates a open string offsets from a tuning name.
+/// Generates a open string offsets from a tuning name, ordered from string 1 (the highest
+/// string) to string 6 (the lowest string).
 ///
 /// Defaults to the standard tuning offsets if the tuning name cannot be matched.
 pub fn parse_tuning(tuning_name: &str) -> [i8; 6] {
     match TuningName::from_str(tuning_name) {
         Ok(TuningName::OpenG) => [-2, 0, 0, 0, -2, -2],
-        Ok(TuningName::OpenD) => [-2, 0, 0, -1, -2, -2],
-        Ok(TuningName::C6) => [-4, 0, -2, 0, 1, 0],
-        Ok(TuningName::Dsus4) => [-2, 0, 0, 0, -2, -2],
-        Ok(TuningName::DropD) => [-2, 0, 0, 0, 0, 0],
-        Ok(TuningName::DropC) => [-4, -2, -2, -2, -2, -2],
-        Ok(TuningName::OpenC) => [-4, -2, -2, 0, 1, 0],
-        Ok(TuningName::DropB) => [-5, -3, -3, -3, -3, -3],
-        Ok(TuningName::OpenE) => [0, -2, -2, -2, 0, 0],
+        Ok(TuningName::OpenD) => [-2, -2, -1, 0, 0, -2],
+        Ok(TuningName::C6) => [0, 1, 0, -2, 0, -4],
+        Ok(TuningName::Dsus4) => [-2, -2, 0, 0, 0, -2],
+        Ok(TuningName::DropD) => [0, 0, 0, 0, 0, -2],
+        Ok(TuningName::DropC) => [-2, -2, -2, -2, -2, -4],
+        Ok(TuningName::OpenC) => [0, 1, 0, -2, -2, -4],
+        Ok(TuningName::DropB) => [-3, -3, -3, -3, -3, -5],
+        Ok(TuningName::OpenE) => [0, 0, 1, 2, 2, 0],
         Err(_) => [0, 0, 0, 0, 0, 0],
     }
 }
@@ -62,149 +74,2255 @@ mod test_parse_tuning {
         assert_eq!(parse_tuning("standard"), [0, 0, 0, 0, 0, 0]);
     }
     #[test]
-    fn non_standard_tunings() {
-        assert_eq!(parse_tuning("openg"), [-2, 0, 0, 0, -2, -2]);
-        assert_eq!(parse_tuning("opend"), [-2, 0, 0, -1, -2, -2]);
-        assert_eq!(parse_tuning("c6"), [-4, 0, -2, 0, 1, 0]);
-        assert_eq!(parse_tuning("dadgad"), [-2, 0, 0, 0, -2, -2]);
-        assert_eq!(parse_tuning("dsus4"), [-2, 0, 0, 0, -2, -2]);
-        assert_eq!(parse_tuning("dropd"), [-2, 0, 0, 0, 0, 0]);
-        assert_eq!(parse_tuning("dropc"), [-4, -2, -2, -2, -2, -2]);
-        assert_eq!(parse_tuning("openc"), [-4, -2, -2, 0, 1, 0]);
-        assert_eq!(parse_tuning("dropb"), [-5, -3, -3, -3, -3, -3]);
-        assert_eq!(parse_tuning("opene"), [0, -2, -2, -2, 0, 0]);
+    fn non_standard_tunings() {
+        assert_eq!(parse_tuning("openg"), [-2, 0, 0, 0, -2, -2]);
+        assert_eq!(parse_tuning("opend"), [-2, -2, -1, 0, 0, -2]);
+        assert_eq!(parse_tuning("c6"), [0, 1, 0, -2, 0, -4]);
+        assert_eq!(parse_tuning("dadgad"), [-2, -2, 0, 0, 0, -2]);
+        assert_eq!(parse_tuning("dsus4"), [-2, -2, 0, 0, 0, -2]);
+        assert_eq!(parse_tuning("dropd"), [0, 0, 0, 0, 0, -2]);
+        assert_eq!(parse_tuning("dropc"), [-2, -2, -2, -2, -2, -4]);
+        assert_eq!(parse_tuning("openc"), [0, 1, 0, -2, -2, -4]);
+        assert_eq!(parse_tuning("dropb"), [-3, -3, -3, -3, -3, -5]);
+        assert_eq!(parse_tuning("opene"), [0, 0, 1, 2, 2, 0]);
+    }
+    #[test]
+    fn unknown_tuning() {
+        assert_eq!(parse_tuning("unknown_tuning"), [0, 0, 0, 0, 0, 0]);
+    }
+}
+
+/// Generates a tuning map of open string pitches from an array of pitch offsets
+/// relative to the standard 6 string tuning open pitches.
+///
+/// Ex:
+/// `create_string_tuning_offset([0, 0, 0, 0, 0, 0])` creates the standard tuning.
+pub fn create_string_tuning_offset(offsets: [i8; 6]) -> BTreeMap<StringNumber, Pitch> {
+    let offset_tuning_open_pitches: Vec<Pitch> = STD_6_STRING_TUNING_OPEN_PITCHES
+        .iter()
+        .zip(offsets)
+        .map(|(std_tuning_pitch, offset)| {
+            std_tuning_pitch
+                .plus_offset(offset as i16)
+                .expect("Tuning pitch offset should be valid.")
+        })
+        .collect();
+
+    create_string_tuning(&offset_tuning_open_pitches)
+}
+#[cfg(test)]
+mod test_create_string_tuning_offset {
+    use super::*;
+
+    #[test]
+    fn no_offset() {
+        assert_eq!(
+            create_string_tuning_offset([0, 0, 0, 0, 0, 0]),
+            create_string_tuning(&STD_6_STRING_TUNING_OPEN_PITCHES)
+        );
+    }
+    #[test]
+    fn single_offset() {
+        assert_eq!(
+            create_string_tuning_offset([-2, 0, 0, 0, 0, 0]),
+            create_string_tuning(&[
+                Pitch::D4,
+                Pitch::B3,
+                Pitch::G3,
+                Pitch::D3,
+                Pitch::A2,
+                Pitch::E2,
+            ])
+        );
+    }
+    #[test]
+    fn random_offsets() {
+        // Test case with random offsets
+        assert_eq!(
+            create_string_tuning_offset([2, -1, 3, 0, -2, 1]),
+            create_string_tuning(&[
+                Pitch::FSharpGFlat4,
+                Pitch::ASharpBFlat3,
+                Pitch::ASharpBFlat3,
+                Pitch::D3,
+                Pitch::G2,
+                Pitch::F2,
+            ])
+        );
+    }
+}
+
+/// How pitches without an octave number are interpreted.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum InputMode {
+    /// Every pitch includes its octave number (e.g. `E4`).
+    #[default]
+    Absolute,
+    /// Pitches may omit their octave number, which is chosen relative to the previous pitch.
+    /// See `parse_relative_input`.
+    Relative,
+}
+
+/// The location of a piece of the input text.
+///
+/// Byte offsets are zero-based and relative to the start of the whole input. Columns are
+/// one-based char columns within the line, matching the columns reported in parse errors. The
+/// end offset and end column point one past the last character.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SourceSpan {
+    pub line_number: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub start_column: usize,
+    pub end_column: usize,
+}
+
+/// The spans of an input line and of every pitch token parsed from it, in the same order as the
+/// pitches of the parsed line.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LineSpans {
+    pub line: SourceSpan,
+    pub pitches: Vec<SourceSpan>,
+}
+
+/// A time signature, such as 3/4.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSignature {
+    /// Number of beats in a measure.
+    pub beats: u8,
+    /// Note value of a beat, as a power of two (e.g. 4 for a quarter note).
+    pub beat_unit: u8,
+}
+impl TimeSignature {
+    /// Parses a time signature written as `beats/beat_unit` (e.g. `6/8`).
+    pub fn parse(time_signature: &str) -> Option<Self> {
+        let (beats, beat_unit) = time_signature.split_once('/')?;
+        let beats = beats.trim().parse::<u8>().ok().filter(|&beats| beats > 0)?;
+        let beat_unit = beat_unit
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|beat_unit| beat_unit.is_power_of_two())?;
+        Some(TimeSignature { beats, beat_unit })
+    }
+}
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.beat_unit)
+    }
+}
+#[cfg(test)]
+mod test_time_signature {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            TimeSignature::parse("3/4"),
+            Some(TimeSignature {
+                beats: 3,
+                beat_unit: 4
+            })
+        );
+        assert_eq!(TimeSignature::parse(" 6 / 8 ").unwrap().to_string(), "6/8");
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(TimeSignature::parse("3"), None);
+        assert_eq!(TimeSignature::parse("0/4"), None);
+        assert_eq!(TimeSignature::parse("3/5"), None);
+        assert_eq!(TimeSignature::parse("3/0"), None);
+    }
+}
+
+/// Settings given by directives at the start of the input (e.g. `@tuning DADGAD`), so that the
+/// input text is self-contained. The tuning, capo, number of frets and harmonics override the
+/// guitar settings that the input is arranged with.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputHeader {
+    pub title: Option<String>,
+    pub tuning_name: Option<String>,
+    pub capo: Option<u8>,
+    pub num_frets: Option<u8>,
+    /// Tempo in beats per minute.
+    pub tempo: Option<u16>,
+    pub time_signature: Option<TimeSignature>,
+    pub harmonics: Option<HarmonicCandidates>,
+    pub style: Option<ArrangementStyle>,
+    /// How single notes are picked with a plectrum, which makes the arrangement avoid awkward
+    /// string crossings and adds pick direction markers to the tab.
+    pub pick_style: Option<PickStyle>,
+}
+
+/// Parses the directives at the start of the input, before the first line that is not a
+/// directive, so that the guitar can be created before the rest of the input is parsed.
+pub fn parse_input_header(input: &str) -> Result<InputHeader> {
+    let mut directives = DirectiveState::default();
+    let errors = input_lines(input)
+        .enumerate()
+        .map_while(|(input_index, (_, input_line))| {
+            directives.read_directive(input_index, input_line)
+        })
+        .filter_map(|result| result.err())
+        .collect_vec();
+
+    match Error::from_errors(errors) {
+        Some(error) => Err(error),
+        None => Ok(directives.header),
+    }
+}
+#[cfg(test)]
+mod test_parse_input_header {
+    use super::*;
+
+    #[test]
+    fn full_header() {
+        let input = "@title Greensleeves // Traditional\n@tuning DADGAD\n@capo 2\n@frets 19\n@tempo 96\n@time 3/4\n@harmonics artificial\n@style campanella\n@pick alternate\nA3";
+        assert_eq!(
+            parse_input_header(input).unwrap(),
+            InputHeader {
+                title: Some("Greensleeves".to_owned()),
+                tuning_name: Some("DADGAD".to_owned()),
+                capo: Some(2),
+                num_frets: Some(19),
+                tempo: Some(96),
+                time_signature: Some(TimeSignature {
+                    beats: 3,
+                    beat_unit: 4
+                }),
+                harmonics: Some(HarmonicCandidates::Artificial),
+                style: Some(ArrangementStyle::Campanella),
+                pick_style: Some(PickStyle::Alternate),
+            }
+        );
+    }
+    #[test]
+    fn stops_at_first_line() {
+        assert_eq!(
+            parse_input_header("@capo 1\nE2\n@capo 3").unwrap(),
+            InputHeader {
+                capo: Some(1),
+                ..Default::default()
+            }
+        );
+        assert_eq!(parse_input_header("").unwrap(), InputHeader::default());
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            parse_input_header("@tuning Banjo\n@tempo 0").unwrap_err(),
+            Error::Multiple {
+                errors: vec![
+                    Error::InvalidDirective {
+                        directive: "tuning".to_owned(),
+                        value: "Banjo".to_owned(),
+                        line_number: 1
+                    },
+                    Error::InvalidDirective {
+                        directive: "tempo".to_owned(),
+                        value: "0".to_owned(),
+                        line_number: 2
+                    },
+                ]
+            }
+        );
+    }
+}
+
+/// Parsed input lines alongside the spans of the input text they were parsed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedInput {
+    pub lines: Vec<Line<BeatVec<Pitch>>>,
+    pub spans: Vec<LineSpans>,
+    /// The spelling of every pitch of every line as written in the input, or `None` for pitches
+    /// that were not written out (e.g. the pitches of a chord symbol).
+    pub spellings: Vec<BeatVec<Option<SpelledPitch>>>,
+    /// The technique written after every pitch of every line, or `None` for pitches without one.
+    pub techniques: Vec<BeatVec<Option<Technique>>>,
+    /// The number of beats that every pitch of every line rings for (e.g. 4 for `A2:4`), which is
+    /// 1 for pitches without a written duration.
+    pub durations: Vec<BeatVec<u8>>,
+    /// The voice of every pitch of every line, counting the voice separators (`|`) written before
+    /// it (e.g. 0 for the bass and 1 for the melody of `A2 | C4 E4`).
+    pub voices: Vec<BeatVec<u8>>,
+    /// The settings given by directives at the start of the input.
+    pub header: InputHeader,
+}
+impl ParsedInput {
+    /// The techniques and durations of the pitches of every line, for the arranger.
+    pub fn articulation(&self) -> Articulation {
+        Articulation {
+            style: self.header.style.unwrap_or_default(),
+            pick_style: self.header.pick_style,
+            techniques: self.techniques.clone(),
+            durations: self.durations.clone(),
+            voices: self.voices.clone(),
+            pinned_fingerings: vec![],
+        }
+    }
+    /// Sets the pick style of the header to the given one if the input does not set it with `@pick`.
+    pub fn with_default_pick_style(mut self, pick_style: Option<PickStyle>) -> Self {
+        self.header.pick_style = self.header.pick_style.or(pick_style);
+        self
+    }
+    /// Maps the line numbers of errors about the parsed lines (e.g. from the arranger), which count
+    /// parsed lines, to the numbers of the input lines they were parsed from. The two differ when
+    /// the input contains directive lines. Impossible pitches are also given the span of their
+    /// pitch token.
+    pub fn restore_input_line_numbers(&self, error: Error) -> Error {
+        match error {
+            Error::ImpossiblePitch {
+                pitch,
+                line_number,
+                span,
+            } => {
+                let line_index = line_number - 1;
+                let Some(line_spans) = self.spans.get(line_index) else {
+                    return Error::ImpossiblePitch {
+                        pitch,
+                        line_number,
+                        span,
+                    };
+                };
+                let pitch_span = match &self.lines[line_index] {
+                    Line::Playable(pitches) => pitches
+                        .iter()
+                        .position(|line_pitch| *line_pitch == pitch)
+                        .and_then(|pitch_index| line_spans.pitches.get(pitch_index))
+                        .copied(),
+                    _ => None,
+                };
+                Error::ImpossiblePitch {
+                    pitch,
+                    line_number: line_spans.line.line_number,
+                    span: span.or(pitch_span),
+                }
+            }
+            Error::UnorderedVoices {
+                line_number,
+                voices,
+            } => Error::UnorderedVoices {
+                line_number: self
+                    .spans
+                    .get(line_number - 1)
+                    .map_or(line_number, |line_spans| line_spans.line.line_number),
+                voices,
+            },
+            Error::Multiple { errors } => Error::Multiple {
+                errors: errors
+                    .into_iter()
+                    .map(|error| self.restore_input_line_numbers(error))
+                    .collect_vec(),
+            },
+            error => error,
+        }
+    }
+}
+#[cfg(test)]
+mod test_parsed_input_with_default_pick_style {
+    use super::*;
+
+    #[test]
+    fn without_directive() {
+        let parsed_input = parse_input("E2".to_owned())
+            .unwrap()
+            .with_default_pick_style(Some(PickStyle::Economy));
+        assert_eq!(parsed_input.header.pick_style, Some(PickStyle::Economy));
+        assert_eq!(
+            parsed_input.articulation().pick_style,
+            Some(PickStyle::Economy)
+        );
+    }
+    #[test]
+    fn directive_takes_precedence() {
+        let parsed_input = parse_input("@pick alternate\nE2".to_owned()).unwrap();
+        assert_eq!(
+            parsed_input
+                .clone()
+                .with_default_pick_style(Some(PickStyle::Economy))
+                .header
+                .pick_style,
+            Some(PickStyle::Alternate)
+        );
+        assert_eq!(
+            parsed_input.with_default_pick_style(None).header.pick_style,
+            Some(PickStyle::Alternate)
+        );
+    }
+}
+#[cfg(test)]
+mod test_parsed_input_restore_input_line_numbers {
+    use super::*;
+
+    #[test]
+    fn after_directive() {
+        let parsed_input = parse_input("E2\n@transpose +2\nE2".to_owned()).unwrap();
+        assert_eq!(
+            parsed_input.restore_input_line_numbers(Error::Multiple {
+                errors: vec![
+                    Error::ImpossiblePitch {
+                        pitch: Pitch::E2,
+                        line_number: 1,
+                        span: None,
+                    },
+                    Error::ImpossiblePitch {
+                        pitch: Pitch::FSharpGFlat2,
+                        line_number: 2,
+                        span: None,
+                    },
+                ]
+            }),
+            Error::Multiple {
+                errors: vec![
+                    Error::ImpossiblePitch {
+                        pitch: Pitch::E2,
+                        line_number: 1,
+                        span: Some(parsed_input.spans[0].pitches[0]),
+                    },
+                    Error::ImpossiblePitch {
+                        pitch: Pitch::FSharpGFlat2,
+                        line_number: 3,
+                        span: Some(parsed_input.spans[1].pitches[0]),
+                    },
+                ]
+            }
+        );
+    }
+    #[test]
+    fn pitch_span() {
+        let parsed_input = parse_input("E4\nE4 C1".to_owned()).unwrap();
+        assert_eq!(
+            parsed_input.restore_input_line_numbers(Error::ImpossiblePitch {
+                pitch: Pitch::C1,
+                line_number: 2,
+                span: None,
+            }),
+            Error::ImpossiblePitch {
+                pitch: Pitch::C1,
+                line_number: 2,
+                span: Some(SourceSpan {
+                    line_number: 2,
+                    start_byte: 6,
+                    end_byte: 8,
+                    start_column: 4,
+                    end_column: 6,
+                }),
+            }
+        );
+    }
+    #[test]
+    fn unordered_voices() {
+        let parsed_input = parse_input("@title Song\nE2\nA4 | A2".to_owned()).unwrap();
+        let voices = vec![vec![Pitch::A4], vec![Pitch::A2]];
+        assert_eq!(
+            parsed_input.restore_input_line_numbers(Error::UnorderedVoices {
+                line_number: 2,
+                voices: voices.clone(),
+            }),
+            Error::UnorderedVoices {
+                line_number: 3,
+                voices
+            }
+        );
+    }
+}
+
+use memoize::memoize;
+#[memoize(Capacity: 10)]
+pub fn parse_lines(input: String) -> Result<Vec<Line<BeatVec<Pitch>>>> {
+    parse_input(input).map(|parsed_input| parsed_input.lines)
+}
+#[cfg(test)]
+mod test_parse_lines {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        let input = "A3\nE2// Comment\n\nG4BB2G4\n-\nE4".to_owned();
+        let expected = vec![
+            Line::Playable(vec![Pitch::A3]),
+            Line::Playable(vec![Pitch::E2]),
+            Line::Rest,
+            Line::Playable(vec![Pitch::G4, Pitch::ASharpBFlat2, Pitch::G4]),
+            Line::MeasureBreak,
+            Line::Playable(vec![Pitch::E4]),
+        ];
+        assert_eq!(parse_lines(input).unwrap(), expected);
+    }
+    #[test]
+    fn structure() {
+        let input = "[Verse] // Comment\n|:\nA3\n1.\nE2\n:|\n2.\n[Am]\n".to_owned();
+        let lines = parse_lines(input).unwrap();
+        assert_eq!(
+            lines[..7],
+            [
+                Line::Section("Verse".to_owned()),
+                Line::RepeatStart,
+                Line::Playable(vec![Pitch::A3]),
+                Line::Ending(1),
+                Line::Playable(vec![Pitch::E2]),
+                Line::RepeatEnd,
+                Line::Ending(2),
+            ]
+        );
+        assert!(matches!(lines[7], Line::Playable(_)));
+    }
+    #[test]
+    fn invalid() {
+        let input = "A3xyz\nE2\n\nG4BB.2\n-\nE4".to_owned();
+
+        let error = parse_lines(input).unwrap_err();
+        let error_msg = format!("{error}");
+
+        assert_eq!(
+            error_msg,
+            "Input 'xyz' on line 1 could not be parsed into a pitch.\nInput 'BB.2' on line 4 could not be parsed into a pitch."
+        );
+    }
+    #[test]
+    fn invalid_structured() {
+        let input = "A3xyz\nE2\n\n  G4 BB.2\n-\nE4".to_owned();
+
+        assert_eq!(
+            parse_lines(input).unwrap_err(),
+            Error::Multiple {
+                errors: vec![
+                    Error::UnparsableInput {
+                        line_number: 1,
+                        column: 3,
+                        token: "xyz".to_owned()
+                    },
+                    Error::UnparsableInput {
+                        line_number: 4,
+                        column: 6,
+                        token: "BB.2".to_owned()
+                    },
+                ]
+            }
+        );
+    }
+}
+
+/// Parses the input lines, keeping the span of every line and pitch token so that beats can be
+/// mapped back to the input text.
+///
+/// Chord symbols are voiced on the default guitar. Use `parse_input_in_mode` to voice them on
+/// another guitar.
+pub fn parse_input(input: String) -> Result<ParsedInput> {
+    parse_input_in_mode(input, InputMode::Absolute, &Guitar::default())
+}
+#[cfg(test)]
+mod test_parse_input {
+    use super::*;
+
+    fn span(
+        line_number: usize,
+        (start_byte, end_byte): (usize, usize),
+        (start_column, end_column): (usize, usize),
+    ) -> SourceSpan {
+        SourceSpan {
+            line_number,
+            start_byte,
+            end_byte,
+            start_column,
+            end_column,
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let input = "A3 C#4\r\n\n-\n  Bb2 // Comment".to_owned();
+        let parsed_input = parse_input(input.clone()).unwrap();
+
+        assert_eq!(parsed_input.lines, parse_lines(input.clone()).unwrap());
+        assert_eq!(
+            parsed_input.spans,
+            vec![
+                LineSpans {
+                    line: span(1, (0, 6), (1, 7)),
+                    pitches: vec![span(1, (0, 2), (1, 3)), span(1, (3, 6), (4, 7))],
+                },
+                LineSpans {
+                    line: span(2, (8, 8), (1, 1)),
+                    pitches: vec![],
+                },
+                LineSpans {
+                    line: span(3, (9, 10), (1, 2)),
+                    pitches: vec![],
+                },
+                LineSpans {
+                    line: span(4, (11, 27), (1, 17)),
+                    pitches: vec![span(4, (13, 16), (3, 6))],
+                },
+            ]
+        );
+        assert_eq!(&input[13..16], "Bb2");
+    }
+    #[test]
+    fn multibyte_characters() {
+        let input = "—\n\u{a0}D#3 E 2".to_owned();
+        let parsed_input = parse_input(input.clone()).unwrap();
+
+        assert_eq!(
+            parsed_input.spans[1].pitches,
+            vec![span(2, (6, 9), (2, 5)), span(2, (10, 13), (6, 9))]
+        );
+        assert_eq!(&input[6..9], "D#3");
+        assert_eq!(&input[10..13], "E 2");
+    }
+    #[test]
+    fn spellings() {
+        let parsed_input = parse_input("F#4 Gb4\n\nAm".to_owned()).unwrap();
+
+        assert_eq!(
+            parsed_input.spellings[0],
+            vec![SpelledPitch::parse("F#4"), SpelledPitch::parse("Gb4")]
+        );
+        assert_eq!(parsed_input.spellings[1], vec![]);
+        assert!(parsed_input.spellings[2]
+            .iter()
+            .all(|spelled_pitch| spelled_pitch.is_none()));
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            parse_input("A3xyz".to_owned()).unwrap_err(),
+            parse_lines("A3xyz".to_owned()).unwrap_err()
+        );
+    }
+}
+
+/// A parsed line with its spans and the written spelling, technique, duration and voice of each of
+/// its pitches.
+type ParsedLine = (
+    Line<BeatVec<Pitch>>,
+    LineSpans,
+    BeatVec<Option<SpelledPitch>>,
+    BeatVec<Option<Technique>>,
+    BeatVec<u8>,
+    BeatVec<u8>,
+);
+
+/// Combines the parse results of every line, returning the errors of all lines that could not be
+/// parsed.
+fn collect_parsed_input(
+    line_parse_results: Vec<Result<ParsedLine>>,
+    header: InputHeader,
+) -> Result<ParsedInput> {
+    let line_errors = line_parse_results
+        .iter()
+        .filter_map(|line| line.as_ref().err())
+        .flat_map(|error| match error {
+            Error::Multiple { errors } => errors.clone(),
+            error => vec![error.clone()],
+        })
+        .collect_vec();
+    if let Some(error) = Error::from_errors(line_errors) {
+        return Err(error);
+    }
+
+    let (lines, spans, spellings, techniques, durations, voices): (
+        Vec<_>,
+        Vec<_>,
+        Vec<_>,
+        Vec<_>,
+        Vec<_>,
+        Vec<_>,
+    ) = line_parse_results
+        .into_iter()
+        .filter_map(|line| line.ok())
+        .multiunzip();
+    validate_techniques(&lines, &spans, &techniques)?;
+
+    Ok(ParsedInput {
+        lines,
+        spans,
+        spellings,
+        techniques,
+        durations,
+        voices,
+        header,
+    })
+}
+
+/// Checks that every legato technique is followed on the next beat by a pitch it can reach on the
+/// same string: a higher pitch for a hammer-on, a lower pitch for a pull-off and a different pitch
+/// for a slide.
+fn validate_techniques(
+    lines: &[Line<BeatVec<Pitch>>],
+    spans: &[LineSpans],
+    techniques: &[BeatVec<Option<Technique>>],
+) -> Result<()> {
+    let errors = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(line_index, line)| {
+            let Line::Playable(pitches) = line else {
+                return vec![];
+            };
+            let next_pitches = match lines[line_index + 1..].iter().find(|line| line.is_beat()) {
+                Some(Line::Playable(next_pitches)) => next_pitches.as_slice(),
+                _ => &[],
+            };
+            pitches
+                .iter()
+                .zip(&techniques[line_index])
+                .filter_map(|(pitch, technique)| match technique {
+                    Some(technique)
+                        if technique.is_legato()
+                            && !next_pitches
+                                .iter()
+                                .any(|next| technique.connects(pitch.index(), next.index())) =>
+                    {
+                        Some(Error::UnconnectedTechnique {
+                            pitch: *pitch,
+                            technique: *technique,
+                            line_number: spans[line_index].line.line_number,
+                        })
+                    }
+                    _ => None,
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    match Error::from_errors(errors) {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+#[cfg(test)]
+mod test_validate_techniques {
+    use super::*;
+
+    fn validate(input: &str) -> Result<()> {
+        parse_absolute_input(input.to_owned(), Guitar::default()).map(|_| ())
+    }
+
+    #[test]
+    fn connected() {
+        assert!(validate("A3h\n-\nB3\nB3p\nA3\nA3/\nE4\nE4^2\nE4~").is_ok());
+    }
+    #[test]
+    fn unconnected() {
+        assert_eq!(
+            validate("A3h\nG3\nE4/"),
+            Err(Error::Multiple {
+                errors: vec![
+                    Error::UnconnectedTechnique {
+                        pitch: Pitch::A3,
+                        technique: Technique::HammerOn,
+                        line_number: 1,
+                    },
+                    Error::UnconnectedTechnique {
+                        pitch: Pitch::E4,
+                        technique: Technique::Slide,
+                        line_number: 3,
+                    },
+                ]
+            })
+        );
+    }
+}
+
+/// Parses the input in the input mode, voicing chord symbols on the guitar and constraining
+/// relative pitches to the range of the guitar.
+pub fn parse_input_in_mode(
+    input: String,
+    input_mode: InputMode,
+    guitar: &Guitar,
+) -> Result<ParsedInput> {
+    match input_mode {
+        InputMode::Absolute => parse_absolute_input(input, guitar.clone()),
+        InputMode::Relative => parse_relative_input(input, guitar.clone()),
+    }
+}
+#[cfg(test)]
+mod test_parse_input_in_mode {
+    use super::*;
+
+    #[test]
+    fn absolute() {
+        assert_eq!(
+            parse_input_in_mode("E4".to_owned(), InputMode::Absolute, &Guitar::default()).unwrap(),
+            parse_input("E4".to_owned()).unwrap()
+        );
+        assert!(
+            parse_input_in_mode("H".to_owned(), InputMode::Absolute, &Guitar::default()).is_err()
+        );
+    }
+    #[test]
+    fn relative() {
+        let parsed_input = parse_input_in_mode(
+            "E\nF#\nG".to_owned(),
+            InputMode::Relative,
+            &Guitar::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            parsed_input.lines,
+            vec![
+                Line::Playable(vec![Pitch::E4]),
+                Line::Playable(vec![Pitch::FSharpGFlat4]),
+                Line::Playable(vec![Pitch::G4]),
+            ]
+        );
+    }
+}
+
+/// Matches an optional lowercase technique marker (see `Technique::parse`) followed by an optional
+/// number of beats that the pitch rings for (e.g. `:4`). Bends are written with `^` because a `b`
+/// followed by a number is a pitch (e.g. `E4b3` is E4 and B3).
+macro_rules! technique_and_duration_pattern {
+    () => {
+        r"(?P<technique>(?-i:[hp/~])|\^[1-4])?(?::(?P<duration>[1-9][0-9]?))?"
+    };
+}
+
+/// Matches a note letter with an optional (double) sharp or flat followed by an octave number and
+/// then the optional technique marker and duration of `technique_and_duration_pattern`.
+const PITCH_PATTERN: &str = concat!(
+    r"(?P<pitch>[A-G](?:##|♯♯|bb|♭♭|[#♯b♭x𝄪𝄫])?[0-9])",
+    technique_and_duration_pattern!()
+);
+
+/// Parses input in which every pitch includes its octave number.
+///
+/// Lines that cannot be parsed as pitches are parsed as chord symbols and voiced on the guitar.
+/// Directive lines (see `parse_directive`) are not parsed into lines.
+#[memoize(Capacity: 10)]
+pub fn parse_absolute_input(input: String, guitar: Guitar) -> Result<ParsedInput> {
+    let pitch_regex = RegexBuilder::new(PITCH_PATTERN)
+        .case_insensitive(true)
+        .build()
+        .expect("Regex pattern should be valid");
+
+    let mut directives = DirectiveState::default();
+    let mut line_parse_results = vec![];
+    for (input_index, (line_start_byte, input_line)) in input_lines(&input).enumerate() {
+        if let Some(directive_result) = directives.read_directive(input_index, input_line) {
+            if let Err(error) = directive_result {
+                line_parse_results.push(Err(error));
+            }
+            continue;
+        }
+
+        let line_content = line_content(input_line);
+        let line_parse_result = match parse_line(&pitch_regex, input_index, input_line) {
+            Ok(line) => {
+                let (pitch_ranges, spellings, techniques, durations): (
+                    Vec<_>,
+                    Vec<_>,
+                    Vec<_>,
+                    Vec<_>,
+                ) = match_pitches(&pitch_regex, &line_content)
+                    .into_iter()
+                    .map(|matched_pitch| {
+                        (
+                            matched_pitch.byte_range,
+                            Some(matched_pitch.spelled_pitch),
+                            matched_pitch.technique,
+                            matched_pitch.duration,
+                        )
+                    })
+                    .multiunzip();
+                let voices = pitch_voices(&line_content, &pitch_ranges);
+                transpose_written_line(line, spellings, directives.semitones, input_index, &guitar)
+                    .map(|(line, spellings)| {
+                        (line, pitch_ranges, spellings, techniques, durations, voices)
+                    })
+            }
+            Err(error) => {
+                parse_chord_line(input_index, &line_content, directives.semitones, &guitar)
+                    .unwrap_or(Err(error))
+                    .map(|(line, pitch_ranges)| {
+                        let num_pitches = pitch_ranges.len();
+                        let spellings = vec![None; num_pitches];
+                        let techniques = vec![None; num_pitches];
+                        (
+                            line,
+                            pitch_ranges,
+                            spellings,
+                            techniques,
+                            vec![1; num_pitches],
+                            vec![0; num_pitches],
+                        )
+                    })
+            }
+        };
+        line_parse_results.push(line_parse_result.map(
+            |(line, pitch_ranges, spellings, techniques, durations, voices)| {
+                let spans = line_spans(input_index, line_start_byte, input_line, &pitch_ranges);
+                (line, spans, spellings, techniques, durations, voices)
+            },
+        ));
+    }
+
+    collect_parsed_input(line_parse_results, directives.header)
+}
+#[cfg(test)]
+mod test_parse_absolute_input {
+    use super::*;
+
+    #[test]
+    fn chord_symbols() {
+        let parsed_input =
+            parse_absolute_input("E4\nAm // Comment\n[G7]\nG7".to_owned(), Guitar::default())
+                .unwrap();
+
+        assert_eq!(
+            parsed_input.lines[1],
+            Line::Playable(vec![Pitch::A2, Pitch::E3, Pitch::A3, Pitch::C4, Pitch::E4])
+        );
+        assert_eq!(parsed_input.lines[3], Line::Playable(vec![Pitch::G7]));
+        match &parsed_input.lines[2] {
+            Line::Playable(pitches) => assert_eq!(pitches[0], Pitch::G2),
+            line => panic!("Expected a playable line, found {line:?}"),
+        }
+        assert!(parsed_input.spans[1]
+            .pitches
+            .iter()
+            .all(|span| (span.start_byte, span.end_byte) == (3, 5)));
+    }
+    #[test]
+    fn techniques() {
+        let parsed_input =
+            parse_absolute_input("E4h A3^2\nF#4 Bb3~\nE4B2".to_owned(), Guitar::default()).unwrap();
+
+        assert_eq!(
+            parsed_input.lines[0],
+            Line::Playable(vec![Pitch::E4, Pitch::A3])
+        );
+        assert_eq!(
+            parsed_input.techniques,
+            vec![
+                vec![Some(Technique::HammerOn), Some(Technique::Bend(2))],
+                vec![None, Some(Technique::Vibrato)],
+                vec![None, None],
+            ]
+        );
+        let span = &parsed_input.spans[0].pitches[1];
+        assert_eq!((span.start_column, span.end_column), (5, 9));
+    }
+    #[test]
+    fn letter_sections() {
+        let parsed_input =
+            parse_absolute_input("[A]\nA2\n[B]\n[Bm]".to_owned(), Guitar::default()).unwrap();
+        assert_eq!(parsed_input.lines[0], Line::Section("A".to_owned()));
+        assert_eq!(parsed_input.lines[2], Line::Section("B".to_owned()));
+        assert!(matches!(parsed_input.lines[3], Line::Playable(_)));
+    }
+    #[test]
+    fn b_pitches_after_pitches() {
+        let parsed_input = parse_absolute_input(
+            "E4b3\nE4 b3\ne4b3\nG4b2\nC4B4\nE4^3".to_owned(),
+            Guitar::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed_input.lines,
+            vec![
+                Line::Playable(vec![Pitch::E4, Pitch::B3]),
+                Line::Playable(vec![Pitch::E4, Pitch::B3]),
+                Line::Playable(vec![Pitch::E4, Pitch::B3]),
+                Line::Playable(vec![Pitch::G4, Pitch::B2]),
+                Line::Playable(vec![Pitch::C4, Pitch::B4]),
+                Line::Playable(vec![Pitch::E4]),
+            ]
+        );
+        assert_eq!(parsed_input.techniques[..5], vec![vec![None, None]; 5]);
+        assert_eq!(parsed_input.techniques[5], vec![Some(Technique::Bend(3))]);
+    }
+    #[test]
+    fn durations() {
+        let parsed_input =
+            parse_absolute_input("A2:4 E4\nB3h:2\nC4\n\nAm".to_owned(), Guitar::default()).unwrap();
+
+        assert_eq!(
+            parsed_input.lines[0],
+            Line::Playable(vec![Pitch::A2, Pitch::E4])
+        );
+        assert_eq!(parsed_input.techniques[1], vec![Some(Technique::HammerOn)]);
+        assert_eq!(
+            parsed_input.durations,
+            vec![vec![4, 1], vec![2], vec![1], vec![], vec![1; 5]]
+        );
+        assert!(parse_absolute_input("A2:0".to_owned(), Guitar::default()).is_err());
+    }
+    #[test]
+    fn voices() {
+        let parsed_input =
+            parse_absolute_input("A2 | C4 E4\nE2|B3|E4\nG3\nAm".to_owned(), Guitar::default())
+                .unwrap();
+
+        assert_eq!(
+            parsed_input.lines[0],
+            Line::Playable(vec![Pitch::A2, Pitch::C4, Pitch::E4])
+        );
+        assert_eq!(
+            parsed_input.voices,
+            vec![vec![0, 1, 1], vec![0, 1, 2], vec![0], vec![0; 5]]
+        );
+        assert!(parse_absolute_input("|".to_owned(), Guitar::default()).is_err());
+    }
+    #[test]
+    fn empty_upper_voice() {
+        let parsed_input =
+            parse_absolute_input("A2 |\n| E4".to_owned(), Guitar::default()).unwrap();
+
+        assert_eq!(
+            parsed_input.lines,
+            vec![
+                Line::Playable(vec![Pitch::A2]),
+                Line::Playable(vec![Pitch::E4]),
+            ]
+        );
+        assert_eq!(parsed_input.voices, vec![vec![0], vec![1]]);
+    }
+    #[test]
+    fn transpose_directive() {
+        let parsed_input = parse_absolute_input(
+            "E2\n@transpose +2\nE2\n[Am]\n@transpose G to F\nG3".to_owned(),
+            Guitar::default(),
+        )
+        .unwrap();
+
+        assert_eq!(parsed_input.lines[0], Line::Playable(vec![Pitch::E2]));
+        assert_eq!(
+            parsed_input.lines[1],
+            Line::Playable(vec![Pitch::FSharpGFlat2])
+        );
+        match &parsed_input.lines[2] {
+            Line::Playable(pitches) => assert_eq!(pitches[0], Pitch::B2),
+            line => panic!("Expected a playable line, found {line:?}"),
+        }
+        assert_eq!(parsed_input.lines[3], Line::Playable(vec![Pitch::F3]));
+        assert_eq!(parsed_input.spellings[1], vec![SpelledPitch::parse("F#2")]);
+        assert_eq!(
+            parsed_input
+                .spans
+                .iter()
+                .map(|line_spans| line_spans.line.line_number)
+                .collect_vec(),
+            vec![1, 3, 4, 6]
+        );
+    }
+    #[test]
+    fn transposed_to_harmonic() {
+        let guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        let parsed_input = parse_absolute_input("@transpose +2\nA5".to_owned(), guitar).unwrap();
+        assert_eq!(parsed_input.lines, vec![Line::Playable(vec![Pitch::B5])]);
+    }
+    #[test]
+    fn transposed_out_of_guitar_range() {
+        assert_eq!(
+            parse_absolute_input("@transpose -2\nG2\nE2F2".to_owned(), Guitar::default())
+                .unwrap_err(),
+            Error::Multiple {
+                errors: vec![
+                    Error::TransposedPitchOutOfGuitarRange {
+                        pitch: Pitch::E2,
+                        transposed_pitch: Pitch::D2,
+                        line_number: 3
+                    },
+                    Error::TransposedPitchOutOfGuitarRange {
+                        pitch: Pitch::F2,
+                        transposed_pitch: Pitch::DSharpEFlat2,
+                        line_number: 3
+                    },
+                ]
+            }
+        );
+    }
+    #[test]
+    fn invalid_directive() {
+        assert_eq!(
+            parse_absolute_input("E2\n@transpose up".to_owned(), Guitar::default()).unwrap_err(),
+            Error::InvalidDirective {
+                directive: "transpose".to_owned(),
+                value: "up".to_owned(),
+                line_number: 2
+            }
+        );
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            parse_absolute_input("E2xyz".to_owned(), Guitar::default()).unwrap_err(),
+            Error::UnparsableInput {
+                line_number: 1,
+                column: 3,
+                token: "xyz".to_owned()
+            }
+        );
+    }
+    #[test]
+    fn note_name_without_octave() {
+        assert_eq!(
+            parse_absolute_input("E2\nE".to_owned(), Guitar::default()).unwrap_err(),
+            Error::UnparsableInput {
+                line_number: 2,
+                column: 1,
+                token: "E".to_owned()
+            }
+        );
+    }
+}
+
+/// A line of voiced chord pitches with the byte range of each pitch in the line content.
+type ChordLine = (Line<BeatVec<Pitch>>, Vec<Range<usize>>);
+
+/// Parses the line content as a chord symbol transposed by a number of semitones and voiced on the
+/// guitar, returning `None` if it is not a chord symbol. Every pitch of the voicing spans the whole
+/// chord symbol.
+///
+/// Chord symbols may be wrapped in square brackets, which is required for chord symbols that are
+/// also valid pitch lines (e.g. `[G7]` rather than the pitch `G7`). Major chords need a quality or
+/// a slash bass note (e.g. `Cmaj` or `[C/G]`), because a bare note name such as `C` is more likely a
+/// pitch missing its octave and `[C]` is a section marker (see `parse_structure_marker`).
+fn parse_chord_line(
+    input_index: usize,
+    line_content: &str,
+    semitones: i16,
+    guitar: &Guitar,
+) -> Option<Result<ChordLine>> {
+    let chord_symbol = line_content
+        .strip_prefix('[')
+        .and_then(|content| content.strip_suffix(']'))
+        .unwrap_or(line_content);
+    if pitch_class(chord_symbol).is_some() {
+        return None;
+    }
+    let chord = ChordSymbol::parse(chord_symbol)?.transposed(semitones);
+
+    Some(match voice_chord(&chord, guitar) {
+        Some(pitches) => {
+            let pitch_ranges = vec![0..line_content.len(); pitches.len()];
+            Ok((Line::Playable(pitches), pitch_ranges))
+        }
+        None => Err(Error::UnplayableChord {
+            chord: chord_symbol.to_owned(),
+            line_number: input_index + 1,
+        }),
+    })
+}
+#[cfg(test)]
+mod test_parse_chord_line {
+    use super::*;
+
+    #[test]
+    fn bracketed() {
+        let (line, pitch_ranges) = parse_chord_line(0, "[Dsus4]", 0, &Guitar::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            line,
+            Line::Playable(vec![Pitch::D3, Pitch::A3, Pitch::D4, Pitch::G4])
+        );
+        assert_eq!(pitch_ranges, vec![0..7; 4]);
+    }
+    #[test]
+    fn not_a_chord() {
+        assert!(parse_chord_line(0, "E2A3", 0, &Guitar::default()).is_none());
+    }
+    #[test]
+    fn bare_note_name() {
+        assert!(parse_chord_line(0, "E", 0, &Guitar::default()).is_none());
+        assert!(parse_chord_line(0, "Bb", 0, &Guitar::default()).is_none());
+        assert!(parse_chord_line(0, "[E]", 0, &Guitar::default()).is_none());
+        assert!(parse_chord_line(0, "Emaj", 0, &Guitar::default()).is_some());
+        assert!(parse_chord_line(0, "[Emaj]", 0, &Guitar::default()).is_some());
+        assert!(parse_chord_line(0, "E/G#", 0, &Guitar::default()).is_some());
+    }
+    #[test]
+    fn unplayable() {
+        let guitar = Guitar::new(create_string_tuning(&[Pitch::E4]), 12, 0).unwrap();
+        assert_eq!(
+            parse_chord_line(4, "[Cmaj]", 0, &guitar)
+                .unwrap()
+                .unwrap_err(),
+            Error::UnplayableChord {
+                chord: "Cmaj".to_owned(),
+                line_number: 5
+            }
+        );
+    }
+}
+
+/// An input directive, written on its own line as `@name value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Directive {
+    /// Transposes the following lines by a number of semitones (e.g. `@transpose +2` or
+    /// `@transpose G to A`). See `parse_transposition`.
+    Transpose(i16),
+    Title(String),
+    Tuning(String),
+    Capo(u8),
+    Frets(u8),
+    Tempo(u16),
+    Time(TimeSignature),
+    Harmonics(HarmonicCandidates),
+    Style(ArrangementStyle),
+    Pick(PickStyle),
+}
+impl Directive {
+    fn name(&self) -> &'static str {
+        match self {
+            Directive::Transpose(_) => "transpose",
+            Directive::Title(_) => "title",
+            Directive::Tuning(_) => "tuning",
+            Directive::Capo(_) => "capo",
+            Directive::Frets(_) => "frets",
+            Directive::Tempo(_) => "tempo",
+            Directive::Time(_) => "time",
+            Directive::Harmonics(_) => "harmonics",
+            Directive::Style(_) => "style",
+            Directive::Pick(_) => "pick",
+        }
+    }
+}
+
+/// Parses a directive line, returning `None` if the line is not a directive. Directive names are
+/// case-insensitive and comments may follow the value.
+fn parse_directive(input_index: usize, input_line: &str) -> Option<Result<Directive>> {
+    let directive_line = remove_comments(input_line).trim().strip_prefix('@')?;
+    let (name, value) = directive_line
+        .split_once(char::is_whitespace)
+        .unwrap_or((directive_line, ""));
+    let value = value.trim();
+    let line_number = input_index + 1;
+    let invalid_directive = || Error::InvalidDirective {
+        directive: name.to_owned(),
+        value: value.to_owned(),
+        line_number,
+    };
+
+    Some(match name.to_ascii_lowercase().as_str() {
+        "transpose" => parse_transposition(value)
+            .map(Directive::Transpose)
+            .ok_or_else(invalid_directive),
+        "title" if !value.is_empty() => Ok(Directive::Title(value.to_owned())),
+        "tuning"
+            if value.eq_ignore_ascii_case("standard") || TuningName::from_str(value).is_ok() =>
+        {
+            Ok(Directive::Tuning(value.to_owned()))
+        }
+        "capo" => value
+            .parse()
+            .map(Directive::Capo)
+            .map_err(|_| invalid_directive()),
+        "frets" => value
+            .parse()
+            .map(Directive::Frets)
+            .map_err(|_| invalid_directive()),
+        "tempo" => value
+            .parse()
+            .ok()
+            .filter(|&tempo| tempo > 0)
+            .map(Directive::Tempo)
+            .ok_or_else(invalid_directive),
+        "time" => TimeSignature::parse(value)
+            .map(Directive::Time)
+            .ok_or_else(invalid_directive),
+        "harmonics" => HarmonicCandidates::from_str(value)
+            .map(Directive::Harmonics)
+            .map_err(|_| invalid_directive()),
+        "style" => ArrangementStyle::from_str(value)
+            .map(Directive::Style)
+            .map_err(|_| invalid_directive()),
+        "pick" => PickStyle::from_str(value)
+            .map(Directive::Pick)
+            .map_err(|_| invalid_directive()),
+        "title" | "tuning" => Err(invalid_directive()),
+        _ => Err(Error::UnknownDirective {
+            directive: name.to_owned(),
+            line_number,
+        }),
+    })
+}
+#[cfg(test)]
+mod test_parse_directive {
+    use super::*;
+
+    #[test]
+    fn transpose() {
+        assert_eq!(
+            parse_directive(0, "@transpose +2"),
+            Some(Ok(Directive::Transpose(2)))
+        );
+        assert_eq!(
+            parse_directive(0, "  @Transpose from G to A // Capo 2"),
+            Some(Ok(Directive::Transpose(2)))
+        );
+    }
+    #[test]
+    fn not_a_directive() {
+        assert_eq!(parse_directive(0, "E2 // @transpose +2"), None);
+        assert_eq!(parse_directive(0, ""), None);
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            parse_directive(2, "@transpose up"),
+            Some(Err(Error::InvalidDirective {
+                directive: "transpose".to_owned(),
+                value: "up".to_owned(),
+                line_number: 3
+            }))
+        );
+        assert_eq!(
+            parse_directive(0, "@key G"),
+            Some(Err(Error::UnknownDirective {
+                directive: "key".to_owned(),
+                line_number: 1
+            }))
+        );
+        assert_eq!(
+            parse_directive(0, "@capo -1"),
+            Some(Err(Error::InvalidDirective {
+                directive: "capo".to_owned(),
+                value: "-1".to_owned(),
+                line_number: 1
+            }))
+        );
+        assert_eq!(
+            parse_directive(0, "@title"),
+            Some(Err(Error::InvalidDirective {
+                directive: "title".to_owned(),
+                value: "".to_owned(),
+                line_number: 1
+            }))
+        );
+    }
+    #[test]
+    fn header() {
+        assert_eq!(
+            parse_directive(0, "@title  Romance // Anonymous"),
+            Some(Ok(Directive::Title("Romance".to_owned())))
+        );
+        assert_eq!(
+            parse_directive(0, "@tuning dadgad"),
+            Some(Ok(Directive::Tuning("dadgad".to_owned())))
+        );
+        assert_eq!(
+            parse_directive(0, "@tuning Standard"),
+            Some(Ok(Directive::Tuning("Standard".to_owned())))
+        );
+        assert_eq!(
+            parse_directive(0, "@FRETS 19"),
+            Some(Ok(Directive::Frets(19)))
+        );
+        assert_eq!(
+            parse_directive(0, "@time 6/8"),
+            Some(Ok(Directive::Time(TimeSignature {
+                beats: 6,
+                beat_unit: 8
+            })))
+        );
+        assert_eq!(
+            parse_directive(0, "@harmonics artificial"),
+            Some(Ok(Directive::Harmonics(HarmonicCandidates::Artificial)))
+        );
+        assert!(matches!(
+            parse_directive(0, "@harmonics pinched"),
+            Some(Err(Error::InvalidDirective { .. }))
+        ));
+        assert_eq!(
+            parse_directive(0, "@style Campanella"),
+            Some(Ok(Directive::Style(ArrangementStyle::Campanella)))
+        );
+        assert_eq!(
+            parse_directive(0, "@pick economy"),
+            Some(Ok(Directive::Pick(PickStyle::Economy)))
+        );
+    }
+}
+
+/// The settings of the directive lines read so far.
+#[derive(Debug, Default)]
+struct DirectiveState {
+    /// Number of semitones by which the following lines are transposed.
+    semitones: i16,
+    header: InputHeader,
+    /// Whether a line that is not a directive has been read, after which only `@transpose` is
+    /// allowed.
+    past_header: bool,
+}
+impl DirectiveState {
+    /// Applies the directive of the input line, returning `None` if the line is not a directive.
+    fn read_directive(&mut self, input_index: usize, input_line: &str) -> Option<Result<()>> {
+        let directive = match parse_directive(input_index, input_line) {
+            Some(Ok(directive)) => directive,
+            Some(Err(error)) => return Some(Err(error)),
+            None => {
+                self.past_header = true;
+                return None;
+            }
+        };
+
+        let header = &mut self.header;
+        match directive {
+            Directive::Transpose(semitones) => self.semitones = semitones,
+            directive if self.past_header => {
+                return Some(Err(Error::MisplacedHeaderDirective {
+                    directive: directive.name().to_owned(),
+                    line_number: input_index + 1,
+                }))
+            }
+            Directive::Title(title) => header.title = Some(title),
+            Directive::Tuning(tuning_name) => header.tuning_name = Some(tuning_name),
+            Directive::Capo(capo) => header.capo = Some(capo),
+            Directive::Frets(num_frets) => header.num_frets = Some(num_frets),
+            Directive::Tempo(tempo) => header.tempo = Some(tempo),
+            Directive::Time(time_signature) => header.time_signature = Some(time_signature),
+            Directive::Harmonics(harmonics) => header.harmonics = Some(harmonics),
+            Directive::Style(style) => header.style = Some(style),
+            Directive::Pick(pick_style) => header.pick_style = Some(pick_style),
+        }
+        Some(Ok(()))
+    }
+}
+#[cfg(test)]
+mod test_directive_state {
+    use super::*;
+
+    #[test]
+    fn header_then_lines() {
+        let mut directives = DirectiveState::default();
+        assert_eq!(directives.read_directive(0, "@capo 3"), Some(Ok(())));
+        assert_eq!(directives.read_directive(1, "E2"), None);
+        assert_eq!(directives.read_directive(2, "@transpose -1"), Some(Ok(())));
+        assert_eq!(
+            directives.read_directive(3, "@tempo 120"),
+            Some(Err(Error::MisplacedHeaderDirective {
+                directive: "tempo".to_owned(),
+                line_number: 4
+            }))
+        );
+        assert_eq!(directives.semitones, -1);
+        assert_eq!(
+            directives.header,
+            InputHeader {
+                capo: Some(3),
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/// A line of pitches with the spelling of each pitch as written in the input.
+type SpelledLine = (Line<BeatVec<Pitch>>, BeatVec<Option<SpelledPitch>>);
+
+/// Transposes the pitches of a line written in the input by a number of semitones, keeping them
+/// within the range of the guitar. The written spellings are transposed with the pitches (see
+/// `SpelledPitch::transposed`).
+fn transpose_written_line(
+    line: Line<BeatVec<Pitch>>,
+    spellings: BeatVec<Option<SpelledPitch>>,
+    semitones: i16,
+    input_index: usize,
+    guitar: &Guitar,
+) -> Result<SpelledLine> {
+    match line {
+        Line::Playable(pitches) if semitones != 0 => {
+            let pitches = transpose_pitches(&pitches, semitones, input_index + 1, Some(guitar))?;
+            let spellings = spellings
+                .into_iter()
+                .map(|spelling| spelling.and_then(|spelling| spelling.transposed(semitones)))
+                .collect();
+            Ok((Line::Playable(pitches), spellings))
+        }
+        line => Ok((line, spellings)),
+    }
+}
+#[cfg(test)]
+mod test_transpose_written_line {
+    use super::*;
+
+    #[test]
+    fn transposed() {
+        assert_eq!(
+            transpose_written_line(
+                Line::Playable(vec![Pitch::G3]),
+                vec![SpelledPitch::parse("G3")],
+                2,
+                0,
+                &Guitar::default()
+            ),
+            Ok((
+                Line::Playable(vec![Pitch::A3]),
+                vec![SpelledPitch::parse("A3")]
+            ))
+        );
+    }
+    #[test]
+    fn transposed_spellings() {
+        assert_eq!(
+            transpose_written_line(
+                Line::Playable(vec![Pitch::FSharpGFlat3, Pitch::FSharpGFlat3]),
+                vec![SpelledPitch::parse("F#3"), SpelledPitch::parse("Gb3")],
+                -1,
+                0,
+                &Guitar::default()
+            ),
+            Ok((
+                Line::Playable(vec![Pitch::F3, Pitch::F3]),
+                vec![SpelledPitch::parse("F3"), SpelledPitch::parse("F3")]
+            ))
+        );
+    }
+    #[test]
+    fn not_transposed() {
+        assert_eq!(
+            transpose_written_line(
+                Line::Playable(vec![Pitch::G3]),
+                vec![SpelledPitch::parse("G3")],
+                0,
+                0,
+                &Guitar::default()
+            ),
+            Ok((
+                Line::Playable(vec![Pitch::G3]),
+                vec![SpelledPitch::parse("G3")]
+            ))
+        );
+    }
+}
+
+/// Parses input in which pitches may omit the octave number, LilyPond `\relative` style.
+///
+/// A pitch without an octave number is placed in the octave that puts it closest to the previous
+/// pitch by note letter (at most a fourth away), then moved up an octave for every `'` and down
+/// an octave for every `,` that follows it. The first pitch of a line is relative to the first
+/// pitch of the previous playable line and every other pitch of a line is relative to the pitch
+/// before it. The first pitch of the input is relative to the middle of the guitar range.
+///
+/// Relative pitches are moved by octaves to fit between the lowest and highest pitches that the
+/// guitar can play, including the harmonics that it considers. Pitches with an octave number are
+/// not moved and become the reference for the following pitches. Lines that cannot be parsed as
+/// pitches are parsed as chord symbols, which do not change the reference.
+#[memoize(Capacity: 10)]
+pub fn parse_relative_input(input: String, guitar: Guitar) -> Result<ParsedInput> {
+    let relative_pitch_regex =
+        Regex::new(RELATIVE_PITCH_PATTERN).expect("Regex pattern should be valid");
+
+    let lowest_pitch = *guitar
+        .range
+        .first()
+        .expect("Guitar range should not be empty.");
+    let highest_pitch = *guitar
+        .range
+        .last()
+        .expect("Guitar range should not be empty.");
+    let middle_pitch =
+        Pitch::from_repr((lowest_pitch.index() as usize + highest_pitch.index() as usize) / 2)
+            .expect("Middle of the pitch range should be a valid pitch.");
+
+    let playable_pitches = guitar.playable_pitches();
+    let pitch_range = *playable_pitches
+        .first()
+        .expect("Guitar range should not be empty.")
+        ..=*playable_pitches
+            .last()
+            .expect("Guitar range should not be empty.");
+    let mut reference_position = diatonic_position(middle_pitch);
+
+    let mut directives = DirectiveState::default();
+    let mut line_parse_results = vec![];
+    for (input_index, (line_start_byte, input_line)) in input_lines(&input).enumerate() {
+        if let Some(directive_result) = directives.read_directive(input_index, input_line) {
+            if let Err(error) = directive_result {
+                line_parse_results.push(Err(error));
+            }
+            continue;
+        }
+
+        let line_content = line_content(input_line);
+
+        if let Some(line) = parse_rest(&line_content)
+            .or(parse_measure_break(&line_content))
+            .or(parse_structure_marker(remove_comments(input_line)))
+        {
+            let spans = line_spans(input_index, line_start_byte, input_line, &[]);
+            line_parse_results.push(Ok((line, spans, vec![], vec![], vec![], vec![])));
+            continue;
+        }
+
+        let (pitch_ranges, relative_pitches): (Vec<Range<usize>>, Vec<RelativePitch>) =
+            match_relative_pitches(&relative_pitch_regex, &line_content)
+                .into_iter()
+                .unzip();
+        let matched_ranges = [
+            pitch_ranges.clone(),
+            voice_separators(&line_content, &pitch_ranges),
+        ]
+        .concat();
+        if let Some(error) = unparsable_input_error(input_index, &line_content, &matched_ranges) {
+            let error = restore_input_columns(error, remove_comments(input_line));
+            let chord_line =
+                parse_chord_line(input_index, &line_content, directives.semitones, &guitar)
+                    .unwrap_or(Err(error))
+                    .map(|(line, pitch_ranges)| {
+                        let spans =
+                            line_spans(input_index, line_start_byte, input_line, &pitch_ranges);
+                        let num_pitches = pitch_ranges.len();
+                        (
+                            line,
+                            spans,
+                            vec![None; num_pitches],
+                            vec![None; num_pitches],
+                            vec![1; num_pitches],
+                            vec![0; num_pitches],
+                        )
+                    });
+            line_parse_results.push(chord_line);
+            continue;
+        }
+
+        let (pitches, spellings): (Vec<Pitch>, Vec<SpelledPitch>) =
+            resolve_relative_pitches(&relative_pitches, &mut reference_position, &pitch_range)
+                .into_iter()
+                .unzip();
+        let spellings = spellings.into_iter().map(Some).collect_vec();
+        let (techniques, durations): (Vec<_>, Vec<_>) = relative_pitches
+            .iter()
+            .map(|relative_pitch| (relative_pitch.technique, relative_pitch.duration))
+            .unzip();
+        let voices = pitch_voices(&line_content, &pitch_ranges);
+        let spans = line_spans(input_index, line_start_byte, input_line, &pitch_ranges);
+        line_parse_results.push(
+            transpose_written_line(
+                Line::Playable(pitches),
+                spellings,
+                directives.semitones,
+                input_index,
+                &guitar,
+            )
+            .map(|(line, spellings)| (line, spans, spellings, techniques, durations, voices)),
+        );
+    }
+
+    collect_parsed_input(line_parse_results, directives.header)
+}
+#[cfg(test)]
+mod test_parse_relative_input {
+    use super::*;
+
+    fn playable_lines(input: &str) -> Vec<Line<BeatVec<Pitch>>> {
+        parse_relative_input(input.to_owned(), Guitar::default())
+            .unwrap()
+            .lines
+    }
+
+    #[test]
+    fn spellings() {
+        let parsed_input =
+            parse_relative_input("E4\nGb\nB#,".to_owned(), Guitar::default()).unwrap();
+        assert_eq!(
+            parsed_input
+                .spellings
+                .iter()
+                .flatten()
+                .map(|spelled_pitch| spelled_pitch.unwrap().plain_text())
+                .collect_vec(),
+            vec!["E4", "Gb4", "B#3"]
+        );
+        assert_eq!(parsed_input.lines[2], Line::Playable(vec![Pitch::C4]));
+    }
+    #[test]
+    fn transpose_directive() {
+        let parsed_input =
+            parse_relative_input("E4\n@transpose -P4\nF#\nG".to_owned(), Guitar::default())
+                .unwrap();
+        assert_eq!(
+            parsed_input.lines,
+            vec![
+                Line::Playable(vec![Pitch::E4]),
+                Line::Playable(vec![Pitch::CSharpDFlat4]),
+                Line::Playable(vec![Pitch::D4]),
+            ]
+        );
+    }
+    #[test]
+    fn harmonics_above_fret_range() {
+        assert_eq!(
+            playable_lines("A5\nB"),
+            vec![
+                Line::Playable(vec![Pitch::A5]),
+                Line::Playable(vec![Pitch::B4]),
+            ]
+        );
+
+        let guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        assert_eq!(
+            parse_relative_input("A5\nB".to_owned(), guitar)
+                .unwrap()
+                .lines,
+            vec![
+                Line::Playable(vec![Pitch::A5]),
+                Line::Playable(vec![Pitch::B5]),
+            ]
+        );
+    }
+    #[test]
+    fn voices() {
+        let parsed_input =
+            parse_relative_input("A2 | c' e\nd".to_owned(), Guitar::default()).unwrap();
+        assert_eq!(
+            parsed_input.lines,
+            vec![
+                Line::Playable(vec![Pitch::A2, Pitch::C4, Pitch::E4]),
+                Line::Playable(vec![Pitch::D3]),
+            ]
+        );
+        assert_eq!(parsed_input.voices, vec![vec![0, 1, 1], vec![0]]);
+    }
+    #[test]
+    fn techniques() {
+        let parsed_input =
+            parse_relative_input("E4h\nF#\nA,^2 C~".to_owned(), Guitar::default()).unwrap();
+
+        assert_eq!(
+            parsed_input.lines,
+            vec![
+                Line::Playable(vec![Pitch::E4]),
+                Line::Playable(vec![Pitch::FSharpGFlat4]),
+                Line::Playable(vec![Pitch::A3, Pitch::C4]),
+            ]
+        );
+        assert_eq!(
+            parsed_input.techniques,
+            vec![
+                vec![Some(Technique::HammerOn)],
+                vec![None],
+                vec![Some(Technique::Bend(2)), Some(Technique::Vibrato)],
+            ]
+        );
+    }
+    #[test]
+    fn durations() {
+        let parsed_input =
+            parse_relative_input("A:4 | c'\n| E:2\nF".to_owned(), Guitar::default()).unwrap();
+
+        assert_eq!(parsed_input.durations, vec![vec![4, 1], vec![2], vec![1]]);
+        assert_eq!(parsed_input.voices, vec![vec![0, 1], vec![1], vec![0]]);
+        assert!(parse_relative_input("A:0".to_owned(), Guitar::default()).is_err());
+    }
+    #[test]
+    fn structure_keeps_reference() {
+        assert_eq!(
+            playable_lines("E4\n[Chorus]\n|:\nF\n:|"),
+            vec![
+                Line::Playable(vec![Pitch::E4]),
+                Line::Section("Chorus".to_owned()),
+                Line::RepeatStart,
+                Line::Playable(vec![Pitch::F4]),
+                Line::RepeatEnd,
+            ]
+        );
+    }
+    #[test]
+    fn chords_keep_reference() {
+        let lines = playable_lines("E4\n[Gmaj]\nF");
+        assert_eq!(lines[2], Line::Playable(vec![Pitch::F4]));
+        match &lines[1] {
+            Line::Playable(pitches) => assert_eq!(pitches[0], Pitch::G2),
+            line => panic!("Expected a playable line, found {line:?}"),
+        }
+    }
+    #[test]
+    fn nearest_octave() {
+        assert_eq!(
+            playable_lines("E4\nF#\nG\nA\nC\nE\nA,\n\nC"),
+            vec![
+                Line::Playable(vec![Pitch::E4]),
+                Line::Playable(vec![Pitch::FSharpGFlat4]),
+                Line::Playable(vec![Pitch::G4]),
+                Line::Playable(vec![Pitch::A4]),
+                Line::Playable(vec![Pitch::C5]),
+                Line::Playable(vec![Pitch::E5]),
+                Line::Playable(vec![Pitch::A4]),
+                Line::Rest,
+                Line::Playable(vec![Pitch::C5]),
+            ]
+        );
+    }
+    #[test]
+    fn octave_marks() {
+        assert_eq!(
+            playable_lines("A3\nA'\nA,,\nB'"),
+            vec![
+                Line::Playable(vec![Pitch::A3]),
+                Line::Playable(vec![Pitch::A4]),
+                Line::Playable(vec![Pitch::A2]),
+                Line::Playable(vec![Pitch::B3]),
+            ]
+        );
+    }
+    #[test]
+    fn chords() {
+        assert_eq!(
+            playable_lines("A2 E' A C#\n-\nD A' D F#"),
+            vec![
+                Line::Playable(vec![Pitch::A2, Pitch::E3, Pitch::A3, Pitch::CSharpDFlat4]),
+                Line::MeasureBreak,
+                Line::Playable(vec![Pitch::D3, Pitch::A3, Pitch::D4, Pitch::FSharpGFlat4]),
+            ]
+        );
+    }
+    #[test]
+    fn constrained_to_range() {
+        assert_eq!(
+            playable_lines("E2\nD\nD'''''"),
+            vec![
+                Line::Playable(vec![Pitch::E2]),
+                Line::Playable(vec![Pitch::D3]),
+                Line::Playable(vec![Pitch::D5]),
+            ]
+        );
+    }
+    #[test]
+    fn first_pitch_near_middle_of_range() {
+        assert_eq!(playable_lines("E"), vec![Line::Playable(vec![Pitch::E4])]);
+    }
+    #[test]
+    fn spans() {
+        let parsed_input = parse_relative_input("C' Eb,\n".to_owned(), Guitar::default()).unwrap();
+        assert_eq!(
+            parsed_input.spans[0]
+                .pitches
+                .iter()
+                .map(|span| (span.start_byte, span.end_byte))
+                .collect_vec(),
+            vec![(0, 2), (3, 6)]
+        );
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            parse_relative_input("E\nF ?\nH".to_owned(), Guitar::default()).unwrap_err(),
+            Error::Multiple {
+                errors: vec![
+                    Error::UnparsableInput {
+                        line_number: 2,
+                        column: 3,
+                        token: "?".to_owned(),
+                    },
+                    Error::UnparsableInput {
+                        line_number: 3,
+                        column: 1,
+                        token: "H".to_owned(),
+                    },
+                ]
+            }
+        );
+    }
+}
+
+/// Matches a note letter with an optional (double) sharp or flat followed by either an octave
+/// number or octave marks, then the same optional technique marker and duration as
+/// `PITCH_PATTERN`. Only the note letter is case-insensitive, so that `EB` is read as two pitches
+/// while `Eb` is read as E flat.
+const RELATIVE_PITCH_PATTERN: &str = concat!(
+    r"(?P<letter>(?i:[A-G]))(?P<accidental>##|♯♯|bb|♭♭|[#♯b♭x𝄪𝄫])?(?:(?P<octave>[0-9])|(?P<octave_marks>'+|,+))?",
+    technique_and_duration_pattern!()
+);
+
+/// A pitch as written in relative input, before its octave is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RelativePitch {
+    /// Index of the note letter, from 0 (C) to 6 (B).
+    letter: i16,
+    /// Semitones added by the accidental.
+    accidental: i16,
+    octave: Option<i16>,
+    /// Octaves added by the `'` and `,` octave marks.
+    octave_shift: i16,
+    technique: Option<Technique>,
+    /// The number of beats that the pitch rings for.
+    duration: u8,
+}
+
+/// Finds the relative pitches in the input line, alongside the byte range each was matched from.
+fn match_relative_pitches(regex: &Regex, input_line: &str) -> Vec<(Range<usize>, RelativePitch)> {
+    regex
+        .captures_iter(input_line)
+        .filter_map(|captures| {
+            let letter_name = captures["letter"].to_ascii_uppercase();
+            let letter = "CDEFGAB".find(&letter_name)? as i16;
+            let accidental =
+                parse_accidental(captures.name("accidental").map_or("", |m| m.as_str()))? as i16;
+            let octave = captures.name("octave").map(|m| {
+                m.as_str()
+                    .parse::<i16>()
+                    .expect("Octave should be a digit.")
+            });
+            let octave_shift = captures.name("octave_marks").map_or(0, |m| {
+                m.as_str().matches('\'').count() as i16 - m.as_str().matches(',').count() as i16
+            });
+            let (technique, duration) = technique_and_duration(&captures)?;
+
+            Some((
+                captures
+                    .get(0)
+                    .expect("Capture should have a match.")
+                    .range(),
+                RelativePitch {
+                    letter,
+                    accidental,
+                    octave,
+                    octave_shift,
+                    technique,
+                    duration,
+                },
+            ))
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_match_relative_pitches {
+    use super::*;
+
+    fn relative_pitch_regex() -> Regex {
+        Regex::new(RELATIVE_PITCH_PATTERN).expect("Regex pattern should be valid")
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!(
+            match_relative_pitches(&relative_pitch_regex(), "eBb''F#3C,"),
+            vec![
+                (
+                    0..1,
+                    RelativePitch {
+                        letter: 2,
+                        accidental: 0,
+                        octave: None,
+                        octave_shift: 0,
+                        technique: None,
+                        duration: 1
+                    }
+                ),
+                (
+                    1..5,
+                    RelativePitch {
+                        letter: 6,
+                        accidental: -1,
+                        octave: None,
+                        octave_shift: 2,
+                        technique: None,
+                        duration: 1
+                    }
+                ),
+                (
+                    5..8,
+                    RelativePitch {
+                        letter: 3,
+                        accidental: 1,
+                        octave: Some(3),
+                        octave_shift: 0,
+                        technique: None,
+                        duration: 1
+                    }
+                ),
+                (
+                    8..10,
+                    RelativePitch {
+                        letter: 0,
+                        accidental: 0,
+                        octave: None,
+                        octave_shift: -1,
+                        technique: None,
+                        duration: 1
+                    }
+                ),
+            ]
+        );
+    }
+    #[test]
+    fn enharmonic_spellings() {
+        assert_eq!(
+            match_relative_pitches(&relative_pitch_regex(), "E#Cbbdx,")
+                .into_iter()
+                .map(|(byte_range, relative_pitch)| (byte_range, relative_pitch.accidental))
+                .collect_vec(),
+            vec![(0..2, 1), (2..5, -2), (5..8, 2)]
+        );
     }
     #[test]
-    fn unknown_tuning() {
-        assert_eq!(parse_tuning("unknown_tuning"), [0, 0, 0, 0, 0, 0]);
+    fn techniques_and_durations() {
+        assert_eq!(
+            match_relative_pitches(&relative_pitch_regex(), "Eh C'^2 A,:4 Bb~:2")
+                .into_iter()
+                .map(|(byte_range, relative_pitch)| (
+                    byte_range,
+                    relative_pitch.technique,
+                    relative_pitch.duration
+                ))
+                .collect_vec(),
+            vec![
+                (0..2, Some(Technique::HammerOn), 1),
+                (3..7, Some(Technique::Bend(2)), 1),
+                (8..12, None, 4),
+                (13..18, Some(Technique::Vibrato), 2),
+            ]
+        );
     }
 }
 
-/// Generates a tuning map of open string pitches from an array of pitch offsets
-/// relative to the standard 6 string tuning open pitches.
-///
-/// Ex:
-/// `create_string_tuning_offset([0, 0, 0, 0, 0, 0])` creates the standard tuning.
-pub fn create_string_tuning_offset(offsets: [i8; 6]) -> BTreeMap<StringNumber, Pitch> {
-    let offset_tuning_open_pitches: Vec<Pitch> = STD_6_STRING_TUNING_OPEN_PITCHES
+/// Resolves the octaves of the relative pitches of a line, updating the reference diatonic
+/// position to the first pitch of the line. Every pitch is returned with its written spelling.
+fn resolve_relative_pitches(
+    relative_pitches: &[RelativePitch],
+    reference_position: &mut i16,
+    pitch_range: &RangeInclusive<Pitch>,
+) -> Vec<(Pitch, SpelledPitch)> {
+    let mut chord_reference_position = *reference_position;
+    let pitches = relative_pitches
         .iter()
-        .zip(offsets)
-        .map(|(std_tuning_pitch, offset)| {
-            std_tuning_pitch
-                .plus_offset(offset as i16)
-                .expect("Tuning pitch offset should be valid.")
+        .map(|relative_pitch| {
+            let (pitch, position) =
+                resolve_relative_pitch(relative_pitch, chord_reference_position, pitch_range);
+            chord_reference_position = position;
+            (pitch, position)
         })
-        .collect();
+        .collect_vec();
 
-    create_string_tuning(&offset_tuning_open_pitches)
+    if let Some((_, first_position)) = pitches.first() {
+        *reference_position = *first_position;
+    }
+    pitches
+        .into_iter()
+        .zip(relative_pitches)
+        .map(|((pitch, position), relative_pitch)| {
+            let spelled_pitch = SpelledPitch {
+                letter: "CDEFGAB".as_bytes()[relative_pitch.letter as usize] as char,
+                accidental: relative_pitch.accidental as i8,
+                octave: position.div_euclid(7) as i8,
+            };
+            (pitch, spelled_pitch)
+        })
+        .collect_vec()
+}
+
+/// Resolves the pitch and diatonic position of a relative pitch.
+fn resolve_relative_pitch(
+    relative_pitch: &RelativePitch,
+    reference_position: i16,
+    pitch_range: &RangeInclusive<Pitch>,
+) -> (Pitch, i16) {
+    const NATURAL_PITCH_CLASSES: [i16; 7] = [0, 2, 4, 5, 7, 9, 11];
+    let RelativePitch {
+        letter,
+        accidental,
+        octave,
+        octave_shift,
+        ..
+    } = *relative_pitch;
+    let pitch_index =
+        |octave: i16| octave * 12 + NATURAL_PITCH_CLASSES[letter as usize] + accidental;
+
+    let mut octave = match octave {
+        Some(octave) => octave,
+        None => {
+            let mut octave = (reference_position - letter + 3).div_euclid(7) + octave_shift;
+            let lowest_index = pitch_range.start().index() as i16;
+            let highest_index = pitch_range.end().index() as i16;
+            while pitch_index(octave) < lowest_index {
+                octave += 1;
+            }
+            while pitch_index(octave) > highest_index && pitch_index(octave - 1) >= lowest_index {
+                octave -= 1;
+            }
+            octave
+        }
+    };
+    while pitch_index(octave) < 0 {
+        octave += 1;
+    }
+    while pitch_index(octave) > Pitch::B9.index() as i16 {
+        octave -= 1;
+    }
+
+    let pitch = Pitch::from_repr(pitch_index(octave) as usize)
+        .expect("Resolved pitch index should be a valid pitch.");
+    (pitch, octave * 7 + letter)
 }
 #[cfg(test)]
-mod test_create_string_tuning_offset {
+mod test_resolve_relative_pitch {
     use super::*;
 
+    fn relative_pitch(letter: i16, octave_shift: i16) -> RelativePitch {
+        RelativePitch {
+            letter,
+            accidental: 0,
+            octave: None,
+            octave_shift,
+            technique: None,
+            duration: 1,
+        }
+    }
+
     #[test]
-    fn no_offset() {
+    fn within_a_fourth() {
+        let c4_position = diatonic_position(Pitch::C4);
+        let range = Pitch::C0..=Pitch::B9;
         assert_eq!(
-            create_string_tuning_offset([0, 0, 0, 0, 0, 0]),
-            create_string_tuning(&STD_6_STRING_TUNING_OPEN_PITCHES)
+            resolve_relative_pitch(&relative_pitch(3, 0), c4_position, &range).0,
+            Pitch::F4
+        );
+        assert_eq!(
+            resolve_relative_pitch(&relative_pitch(4, 0), c4_position, &range).0,
+            Pitch::G3
+        );
+        assert_eq!(
+            resolve_relative_pitch(&relative_pitch(6, 1), c4_position, &range).0,
+            Pitch::B4
         );
     }
     #[test]
-    fn single_offset() {
+    fn accidentals_keep_written_octave() {
+        let c_flat = RelativePitch {
+            letter: 0,
+            accidental: -1,
+            octave: None,
+            octave_shift: 0,
+            technique: None,
+            duration: 1,
+        };
         assert_eq!(
-            create_string_tuning_offset([-2, 0, 0, 0, 0, 0]),
-            create_string_tuning(&[
-                Pitch::D4,
-                Pitch::B3,
-                Pitch::G3,
-                Pitch::D3,
-                Pitch::A2,
-                Pitch::E2,
-            ])
+            resolve_relative_pitch(
+                &c_flat,
+                diatonic_position(Pitch::B3),
+                &(Pitch::C0..=Pitch::B9)
+            ),
+            (Pitch::B3, diatonic_position(Pitch::C4))
         );
     }
     #[test]
-    fn random_offsets() {
-        // Test case with random offsets
+    fn pitch_limits() {
+        let range = Pitch::C0..=Pitch::B9;
         assert_eq!(
-            create_string_tuning_offset([2, -1, 3, 0, -2, 1]),
-            create_string_tuning(&[
-                Pitch::FSharpGFlat4,
-                Pitch::ASharpBFlat3,
-                Pitch::ASharpBFlat3,
-                Pitch::D3,
-                Pitch::G2,
-                Pitch::F2,
-            ])
+            resolve_relative_pitch(&relative_pitch(0, -3), 0, &range).0,
+            Pitch::C0
+        );
+        assert_eq!(
+            resolve_relative_pitch(&relative_pitch(6, 3), diatonic_position(Pitch::B9), &range).0,
+            Pitch::B9
         );
     }
 }
 
-use memoize::memoize;
-#[memoize(Capacity: 10)]
-pub fn parse_lines(input: String) -> Result<Vec<Line<BeatVec<Pitch>>>, Arc<anyhow::Error>> {
-    let pattern = r"(?P<three_char_pitch>[A-G][#|♯|b|♭][0-9])|(?P<two_char_pitch>[A-G][0-9])";
-    let pitch_regex = RegexBuilder::new(pattern)
-        .case_insensitive(true)
-        .build()
-        .expect("Regex pattern should be valid");
-
-    let line_parse_results: Vec<Result<Line<BeatVec<Pitch>>, anyhow::Error>> = input
-        .lines()
-        .enumerate()
-        .map(|(input_index, input_line)| parse_line(&pitch_regex, input_index, input_line))
-        .collect_vec();
+/// Calculates the position of the pitch on the staff, counted in note letters from C0. Sharps are
+/// placed on the note letter below them.
+fn diatonic_position(pitch: Pitch) -> i16 {
+    const PITCH_CLASS_LETTERS: [i16; 12] = [0, 0, 1, 1, 2, 3, 3, 4, 4, 5, 5, 6];
+    let index = pitch.index() as i16;
+    index / 12 * 7 + PITCH_CLASS_LETTERS[(index % 12) as usize]
+}
+#[cfg(test)]
+mod test_diatonic_position {
+    use super::*;
 
-    let unparsable_lines_error_msg = line_parse_results
-        .iter()
-        .filter_map(|line| match line {
-            Err(err) => Some(format!("{}", err)),
-            Ok(_) => None,
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
-    if !unparsable_lines_error_msg.is_empty() {
-        return Err(anyhow!(unparsable_lines_error_msg).into());
+    #[test]
+    fn simple() {
+        assert_eq!(diatonic_position(Pitch::C0), 0);
+        assert_eq!(diatonic_position(Pitch::B0), 6);
+        assert_eq!(diatonic_position(Pitch::C4), 28);
+        assert_eq!(diatonic_position(Pitch::FSharpGFlat4), 31);
     }
+}
 
-    let parsed_lines: Vec<Line<BeatVec<Pitch>>> = line_parse_results
-        .into_iter()
-        .filter_map(|line| line.ok())
-        .collect::<Vec<_>>();
-
-    Ok(parsed_lines)
+/// Splits the input into lines the same way as `str::lines`, alongside the byte offset at which
+/// each line starts.
+fn input_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .split_inclusive('\n')
+        .scan(0, |line_start_byte, input_line| {
+            let start_byte = *line_start_byte;
+            *line_start_byte += input_line.len();
+            let input_line = input_line.strip_suffix('\n').unwrap_or(input_line);
+            let input_line = input_line.strip_suffix('\r').unwrap_or(input_line);
+            Some((start_byte, input_line))
+        })
 }
 #[cfg(test)]
-mod test_parse_lines {
+mod test_input_lines {
     use super::*;
 
     #[test]
-    fn valid() {
-        let input = "A3\nE2// Comment\n\nG4BB2G4\n-\nE4".to_owned();
-        let expected = vec![
-            Line::Playable(vec![Pitch::A3]),
-            Line::Playable(vec![Pitch::E2]),
-            Line::Rest,
-            Line::Playable(vec![Pitch::G4, Pitch::ASharpBFlat2, Pitch::G4]),
-            Line::MeasureBreak,
-            Line::Playable(vec![Pitch::E4]),
-        ];
-        assert_eq!(parse_lines(input).unwrap(), expected);
+    fn simple() {
+        assert_eq!(
+            input_lines("A3\r\n\nB2\n").collect_vec(),
+            vec![(0, "A3"), (4, ""), (5, "B2")]
+        );
     }
     #[test]
-    fn invalid() {
-        let input = "A3xyz\nE2\n\nG4BB.2\n-\nE4".to_owned();
-
-        let error = parse_lines(input).unwrap_err();
-        let error_msg = format!("{error}");
-
+    fn matches_str_lines() {
+        let input = "A3\r\n\n  \r\nB2//c\n\n";
         assert_eq!(
-            error_msg,
-            "Input 'xyz' on line 1 could not be parsed into a pitch.\nInput 'BB.2' on line 4 could not be parsed into a pitch."
+            input_lines(input).map(|(_, line)| line).collect_vec(),
+            input.lines().collect_vec()
         );
     }
 }
 
+/// Calculates the span of the input line and of every pitch token in the line, given the byte
+/// ranges of the pitch tokens in the whitespace-free line content.
+///
+/// Pitches are matched after whitespace is removed, so a pitch token may span whitespace in the
+/// input line (e.g. `E 2`).
+fn line_spans(
+    input_index: usize,
+    line_start_byte: usize,
+    input_line: &str,
+    pitch_ranges: &[Range<usize>],
+) -> LineSpans {
+    let line_number = input_index + 1;
+    let content = remove_comments(input_line);
+    let content_chars = content
+        .char_indices()
+        .enumerate()
+        .filter(|(_, (_, c))| !c.is_whitespace())
+        .map(|(char_index, (byte_index, c))| (char_index, byte_index, c.len_utf8()))
+        .collect_vec();
+    let line_content = remove_whitespace(content);
+
+    let pitches = pitch_ranges
+        .iter()
+        .map(|byte_range| {
+            let first_char = line_content[..byte_range.start].chars().count();
+            let last_char = line_content[..byte_range.end].chars().count() - 1;
+            let (start_char_index, start_byte, _) = content_chars[first_char];
+            let (end_char_index, end_byte, end_len) = content_chars[last_char];
+            SourceSpan {
+                line_number,
+                start_byte: line_start_byte + start_byte,
+                end_byte: line_start_byte + end_byte + end_len,
+                start_column: start_char_index + 1,
+                end_column: end_char_index + 2,
+            }
+        })
+        .collect_vec();
+
+    LineSpans {
+        line: SourceSpan {
+            line_number,
+            start_byte: line_start_byte,
+            end_byte: line_start_byte + input_line.len(),
+            start_column: 1,
+            end_column: input_line.chars().count() + 1,
+        },
+        pitches,
+    }
+}
+
+/// Removes the comments and whitespace from an input line.
+fn line_content(input_line: &str) -> String {
+    remove_whitespace(remove_comments(input_line))
+}
+
 fn parse_line(regex: &Regex, input_index: usize, mut input_line: &str) -> Result<Line<Vec<Pitch>>> {
     input_line = remove_comments(input_line);
     let line_content: String = remove_whitespace(input_line);
@@ -215,16 +2333,18 @@ fn parse_line(regex: &Regex, input_index: usize, mut input_line: &str) -> Result
     if let Some(measure_break) = parse_measure_break(&line_content) {
         return Ok(measure_break);
     }
+    if let Some(structure_marker) = parse_structure_marker(input_line) {
+        return Ok(structure_marker);
+    }
     parse_pitch(regex, input_index, &line_content)
+        .map_err(|error| restore_input_columns(error, input_line))
 }
 #[cfg(test)]
 mod test_parse_line {
     use super::*;
 
     fn pitch_regex() -> Regex {
-        let pattern = r"(?P<three_char_pitch>[A-G][#|♯|b|♭][0-9])|(?P<two_char_pitch>[A-G][0-9])";
-
-        RegexBuilder::new(pattern)
+        RegexBuilder::new(PITCH_PATTERN)
             .case_insensitive(true)
             .build()
             .expect("Regex pattern should be valid")
@@ -265,6 +2385,17 @@ mod test_parse_line {
         );
     }
     #[test]
+    fn invalid_input_column() {
+        assert_eq!(
+            parse_line(&pitch_regex(), 2, " A3  E2 x // Comment").unwrap_err(),
+            Error::UnparsableInput {
+                line_number: 3,
+                column: 9,
+                token: "x".to_owned()
+            }
+        );
+    }
+    #[test]
     fn test_parse_line_invalid_input() {
         let error = parse_line(&pitch_regex(), 4, "  Invalid Text  ").unwrap_err();
         let error_msg = format!("{error}");
@@ -312,6 +2443,91 @@ fn remove_whitespace(input: &str) -> String {
     input.chars().filter(|c| !c.is_whitespace()).collect()
 }
 
+/// Converts the columns of unparsable input errors from columns in the whitespace-free line
+/// content to columns in the original input line.
+fn restore_input_columns(error: Error, input_line: &str) -> Error {
+    let content_columns = input_line
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(char_index, _)| char_index + 1)
+        .collect_vec();
+
+    match error {
+        Error::UnparsableInput {
+            line_number,
+            column,
+            token,
+        } => Error::UnparsableInput {
+            line_number,
+            column: content_columns.get(column - 1).copied().unwrap_or(column),
+            token,
+        },
+        Error::Multiple { errors } => Error::Multiple {
+            errors: errors
+                .into_iter()
+                .map(|error| restore_input_columns(error, input_line))
+                .collect(),
+        },
+        error => error,
+    }
+}
+#[cfg(test)]
+mod test_restore_input_columns {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let error = Error::UnparsableInput {
+            line_number: 1,
+            column: 3,
+            token: "x".to_owned(),
+        };
+        assert_eq!(
+            restore_input_columns(error, " A 3 x"),
+            Error::UnparsableInput {
+                line_number: 1,
+                column: 6,
+                token: "x".to_owned(),
+            }
+        );
+    }
+    #[test]
+    fn multiple() {
+        let error = Error::Multiple {
+            errors: vec![
+                Error::UnparsableInput {
+                    line_number: 1,
+                    column: 1,
+                    token: "Z".to_owned(),
+                },
+                Error::UnparsableInput {
+                    line_number: 1,
+                    column: 2,
+                    token: "y".to_owned(),
+                },
+            ],
+        };
+        assert_eq!(
+            restore_input_columns(error, "\tZ  y"),
+            Error::Multiple {
+                errors: vec![
+                    Error::UnparsableInput {
+                        line_number: 1,
+                        column: 2,
+                        token: "Z".to_owned(),
+                    },
+                    Error::UnparsableInput {
+                        line_number: 1,
+                        column: 5,
+                        token: "y".to_owned(),
+                    },
+                ],
+            }
+        );
+    }
+}
+
 fn parse_rest(input_line: &str) -> Option<Line<Vec<Pitch>>> {
     if input_line.is_empty() {
         return Some(Line::Rest);
@@ -332,6 +2548,78 @@ mod test_parse_rest {
     }
 }
 
+/// Parses repeat signs (`|:` and `:|`), numbered endings (e.g. `1.`) and section markers (e.g.
+/// `[Verse]` or `[A]`) from an input line without comments. Bracketed chord symbols with a quality
+/// or a slash bass note (e.g. `[G7]` or `[Am]`) are chords rather than section markers, while a
+/// bare note name such as `[A]` is a section.
+fn parse_structure_marker(input_line: &str) -> Option<Line<Vec<Pitch>>> {
+    let input_line = input_line.trim();
+    match remove_whitespace(input_line).as_str() {
+        "|:" => return Some(Line::RepeatStart),
+        ":|" => return Some(Line::RepeatEnd),
+        _ => (),
+    }
+
+    if let Some(ending_number) = input_line
+        .strip_suffix('.')
+        .and_then(|ending_number| ending_number.parse::<u8>().ok())
+        .filter(|&ending_number| ending_number > 0)
+    {
+        return Some(Line::Ending(ending_number));
+    }
+
+    let section_name = input_line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let is_chord_symbol =
+        pitch_class(section_name).is_none() && ChordSymbol::parse(section_name).is_some();
+    match section_name.is_empty() || is_chord_symbol {
+        true => None,
+        false => Some(Line::Section(section_name.to_owned())),
+    }
+}
+#[cfg(test)]
+mod test_parse_structure_marker {
+    use super::*;
+
+    #[test]
+    fn repeats() {
+        assert_eq!(parse_structure_marker("|:"), Some(Line::RepeatStart));
+        assert_eq!(parse_structure_marker(" : | "), Some(Line::RepeatEnd));
+    }
+    #[test]
+    fn endings() {
+        assert_eq!(parse_structure_marker("1."), Some(Line::Ending(1)));
+        assert_eq!(parse_structure_marker(" 2. "), Some(Line::Ending(2)));
+        assert_eq!(parse_structure_marker("0."), None);
+        assert_eq!(parse_structure_marker("1"), None);
+    }
+    #[test]
+    fn sections() {
+        assert_eq!(
+            parse_structure_marker("[Verse]"),
+            Some(Line::Section("Verse".to_owned()))
+        );
+        assert_eq!(
+            parse_structure_marker("[ Pre-Chorus 2 ]"),
+            Some(Line::Section("Pre-Chorus 2".to_owned()))
+        );
+        assert_eq!(parse_structure_marker("[G7]"), None);
+        assert_eq!(parse_structure_marker("[]"), None);
+        assert_eq!(parse_structure_marker("Verse"), None);
+    }
+    #[test]
+    fn note_names() {
+        for name in ["A", "B", "E", "Bb", "F#"] {
+            assert_eq!(
+                parse_structure_marker(&format!("[{name}]")),
+                Some(Line::Section(name.to_owned()))
+            );
+        }
+        for name in ["[Am]", "[Emaj]", "[C/G]"] {
+            assert_eq!(parse_structure_marker(name), None);
+        }
+    }
+}
+
 fn parse_measure_break(input_line: &str) -> Option<Line<Vec<Pitch>>> {
     let unique_chars: HashSet<char> = input_line.chars().collect();
     if unique_chars == HashSet::<char>::from(['-'])
@@ -387,42 +2675,24 @@ mod test_parse_measure_break {
 /// Parses input line to extract valid musical pitches, returning an error if any part of the
 /// input line cannot be parsed into a pitch.
 fn parse_pitch(regex: &Regex, input_index: usize, input_line: &str) -> Result<Line<Vec<Pitch>>> {
-    let (matched_index_ranges, matched_pitches): (Vec<Vec<usize>>, Vec<Pitch>) = regex
-        .find_iter(input_line)
-        .filter_map(|regex_match| match Pitch::from_str(regex_match.as_str()) {
-            Ok(pitch) => Some(((regex_match.start()..regex_match.end()).collect(), pitch)),
-            _ => None,
-        })
-        .unzip();
-
-    let matched_indices: HashSet<usize> = matched_index_ranges.into_iter().flatten().collect();
-    let input_indices: HashSet<usize> = (0..input_line.len()).collect();
-
-    let unmatched_indices: Vec<usize> = input_indices
-        .difference(&matched_indices)
-        .sorted()
-        .cloned()
-        .collect();
-
-    if !unmatched_indices.is_empty() {
-        let line_number = input_index + 1;
-        let consecutive_indices = consecutive_slices(&unmatched_indices);
-        let error_msg = consecutive_indices
+    let (matched_index_ranges, matched_pitches): (Vec<Range<usize>>, Vec<Pitch>) =
+        match_pitches(regex, input_line)
             .into_iter()
-            .sorted()
-            .map(|unmatched_input_indices| {
-                let first_idx = *unmatched_input_indices.first().unwrap();
-                let last_idx = *unmatched_input_indices.last().unwrap();
-                let unmatched_input = &input_line[first_idx..=last_idx];
-                format!(
-                    "Input '{}' on line {} could not be parsed into a pitch.",
-                    unmatched_input, line_number
-                )
+            .filter_map(|matched_pitch| {
+                Some((
+                    matched_pitch.byte_range,
+                    matched_pitch.spelled_pitch.pitch()?,
+                ))
             })
-            .collect::<Vec<_>>()
-            .join("\n");
+            .unzip();
+    let matched_index_ranges = [
+        voice_separators(input_line, &matched_index_ranges),
+        matched_index_ranges,
+    ]
+    .concat();
 
-        return Err(anyhow!(error_msg));
+    if let Some(error) = unparsable_input_error(input_index, input_line, &matched_index_ranges) {
+        return Err(error);
     }
 
     Ok(Line::Playable(matched_pitches))
@@ -432,9 +2702,7 @@ mod test_parse_pitch {
     use super::*;
 
     fn pitch_regex() -> Regex {
-        let pattern = r"(?P<three_char_pitch>[A-G][#|♯|b|♭][0-9])|(?P<two_char_pitch>[A-G][0-9])";
-
-        RegexBuilder::new(pattern)
+        RegexBuilder::new(PITCH_PATTERN)
             .case_insensitive(true)
             .build()
             .expect("Regex pattern should be valid")
@@ -510,9 +2778,16 @@ mod test_parse_pitch {
         assert_eq!(error_msg, expected_error_msg);
     }
     #[test]
+    fn enharmonic_spellings() {
+        assert_eq!(
+            parse_pitch(&pitch_regex(), 0, "Fb3E#3Bbb2Cx4").unwrap(),
+            Line::Playable(vec![Pitch::E3, Pitch::F3, Pitch::A2, Pitch::D4])
+        );
+    }
+    #[test]
     fn invalid_pitch() {
-        let error_msg = format!("{}", parse_pitch(&pitch_regex(), 28, "Fb3").unwrap_err());
-        let expected_error_msg = "Input 'Fb3' on line 29 could not be parsed into a pitch.";
+        let error_msg = format!("{}", parse_pitch(&pitch_regex(), 28, "Cb0").unwrap_err());
+        let expected_error_msg = "Input 'Cb0' on line 29 could not be parsed into a pitch.";
         assert_eq!(error_msg, expected_error_msg);
     }
     #[test]
@@ -526,6 +2801,167 @@ mod test_parse_pitch {
     }
 }
 
+/// Creates an error for every part of the input line that is not covered by the matched byte
+/// ranges, returning `None` if the whole line was matched.
+fn unparsable_input_error(
+    input_index: usize,
+    input_line: &str,
+    matched_index_ranges: &[Range<usize>],
+) -> Option<Error> {
+    let matched_indices: HashSet<usize> = matched_index_ranges.iter().cloned().flatten().collect();
+    let input_indices: HashSet<usize> = (0..input_line.len()).collect();
+
+    let unmatched_indices: Vec<usize> = input_indices
+        .difference(&matched_indices)
+        .sorted()
+        .cloned()
+        .collect();
+
+    let line_number = input_index + 1;
+    let consecutive_indices = consecutive_slices(&unmatched_indices);
+    let errors = consecutive_indices
+        .into_iter()
+        .sorted()
+        .map(|unmatched_input_indices| {
+            let first_idx = *unmatched_input_indices.first().unwrap();
+            let last_idx = *unmatched_input_indices.last().unwrap();
+            Error::UnparsableInput {
+                line_number,
+                column: input_line[..first_idx].chars().count() + 1,
+                token: input_line[first_idx..=last_idx].to_owned(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Error::from_errors(errors)
+}
+#[cfg(test)]
+mod test_unparsable_input_error {
+    use super::*;
+
+    #[test]
+    fn fully_matched() {
+        assert_eq!(unparsable_input_error(0, "A3B3", &[0..2, 2..4]), None);
+    }
+    #[test]
+    fn unmatched() {
+        assert_eq!(
+            unparsable_input_error(1, "xA3B3", &[1..3, 3..5]),
+            Some(Error::UnparsableInput {
+                line_number: 2,
+                column: 1,
+                token: "x".to_owned()
+            })
+        );
+    }
+}
+
+/// Finds the voice separators (`|`) of the line content that divide its pitches into voices, e.g.
+/// the separator of `A2|C4E4`. Voices on either side of a separator may be empty when their notes
+/// are still ringing from earlier lines or resting (e.g. `|D4` for a melody note over a sustained
+/// bass note and `A2|` for a bass note under a resting melody), but separators in a line without
+/// pitches are not returned, so that they are reported as unparsable input.
+fn voice_separators(line_content: &str, pitch_ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    if pitch_ranges.is_empty() {
+        return vec![];
+    }
+    line_content
+        .match_indices('|')
+        .map(|(index, _)| index..index + 1)
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_voice_separators {
+    use super::*;
+
+    #[test]
+    fn between_pitches() {
+        assert_eq!(voice_separators("A2|C4E4", &[0..2, 3..5, 5..7]), vec![2..3]);
+        assert_eq!(
+            voice_separators("E2|B3|E4", &[0..2, 3..5, 6..8]),
+            vec![2..3, 5..6]
+        );
+    }
+    #[test]
+    fn empty_voices() {
+        assert_eq!(voice_separators("|A2C4", &[1..3, 3..5]), vec![0..1]);
+        assert_eq!(voice_separators("A2||C4", &[0..2, 4..6]), vec![2..3, 3..4]);
+    }
+    #[test]
+    fn without_pitches_after() {
+        assert_eq!(voice_separators("A2C4|", &[0..2, 2..4]), vec![4..5]);
+        assert_eq!(voice_separators("A2|C4|", &[0..2, 3..5]), vec![2..3, 5..6]);
+    }
+    #[test]
+    fn without_pitches() {
+        assert_eq!(
+            voice_separators("A2C4", &[0..2, 2..4]),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(voice_separators("|", &[]), Vec::<Range<usize>>::new());
+    }
+}
+
+/// Numbers the voice of every pitch by the voice separators (`|`) written before it in the line
+/// content, from 0 for the first voice.
+fn pitch_voices(line_content: &str, pitch_ranges: &[Range<usize>]) -> BeatVec<u8> {
+    pitch_ranges
+        .iter()
+        .map(|pitch_range| line_content[..pitch_range.start].matches('|').count() as u8)
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_pitch_voices {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        assert_eq!(pitch_voices("A2|C4E4", &[0..2, 3..5, 5..7]), vec![0, 1, 1]);
+        assert_eq!(pitch_voices("A2C4", &[0..2, 2..4]), vec![0, 0]);
+    }
+}
+
+/// A pitch token matched in an input line.
+struct MatchedPitch {
+    /// The byte range of the whole token, including its technique marker and duration.
+    byte_range: Range<usize>,
+    spelled_pitch: SpelledPitch,
+    technique: Option<Technique>,
+    /// The number of beats that the pitch rings for.
+    duration: u8,
+}
+
+/// Finds the valid pitches in the input line as written, alongside their technique and duration.
+/// Pitches outside the range of `Pitch` (e.g. `Cb0`) are not matched.
+fn match_pitches(regex: &Regex, input_line: &str) -> Vec<MatchedPitch> {
+    regex
+        .captures_iter(input_line)
+        .filter_map(|captures| {
+            let token = captures.get(0)?;
+            let (technique, duration) = technique_and_duration(&captures)?;
+            SpelledPitch::parse(captures.name("pitch")?.as_str())
+                .filter(|spelled_pitch| spelled_pitch.pitch().is_some())
+                .map(|spelled_pitch| MatchedPitch {
+                    byte_range: token.range(),
+                    spelled_pitch,
+                    technique,
+                    duration,
+                })
+        })
+        .collect_vec()
+}
+
+/// Reads the technique marker and the duration (one beat if not written) of a matched pitch token.
+fn technique_and_duration(captures: &Captures) -> Option<(Option<Technique>, u8)> {
+    let technique = captures
+        .name("technique")
+        .and_then(|marker| Technique::parse(marker.as_str()));
+    let duration = captures
+        .name("duration")
+        .map_or(Some(1), |duration| duration.as_str().parse().ok())?;
+    Some((technique, duration))
+}
+
 /// Returns a vector of consecutive slices of the input numbers.
 ///
 /// This function does not sort the input vector and the consecutive slices are grouped together based
@@ -0,0 +1,440 @@
+use crate::{
+    arrangement::{BeatVec, Line},
+    guitar::PitchFingering,
+    string_number::StringNumber,
+};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use strum_macros::EnumString;
+
+/// A finger of the picking hand, named by its letter in classical guitar notation.
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub enum PickingFinger {
+    /// Thumb (`p`).
+    Thumb,
+    /// Index finger (`i`).
+    Index,
+    /// Middle finger (`m`).
+    Middle,
+    /// Ring finger (`a`).
+    Ring,
+}
+impl PickingFinger {
+    /// The finger that plays a treble string by default: the ring finger on the first string, the
+    /// middle finger on the second string and the index finger on the strings below them.
+    fn for_treble_string(string_number: u8) -> Self {
+        match string_number {
+            1 => PickingFinger::Ring,
+            2 => PickingFinger::Middle,
+            _ => PickingFinger::Index,
+        }
+    }
+
+    /// The neighbouring finger that alternates with a treble finger.
+    fn alternate(&self) -> Self {
+        match self {
+            PickingFinger::Thumb => PickingFinger::Thumb,
+            PickingFinger::Index => PickingFinger::Middle,
+            PickingFinger::Middle => PickingFinger::Index,
+            PickingFinger::Ring => PickingFinger::Middle,
+        }
+    }
+}
+impl fmt::Display for PickingFinger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PickingFinger::Thumb => write!(f, "p"),
+            PickingFinger::Index => write!(f, "i"),
+            PickingFinger::Middle => write!(f, "m"),
+            PickingFinger::Ring => write!(f, "a"),
+        }
+    }
+}
+
+/// Assigns a picking finger to every note of the arrangement lines, aligned with their fingerings,
+/// using standard fingerstyle rules:
+/// - the thumb plays the notes on the bass strings, which are the lower half of the strings;
+/// - the treble notes are played by the index finger on the third string and below, the middle
+///   finger on the second string and the ring finger on the first string, with the thumb taking
+///   any treble notes of a chord that would need a finger twice;
+/// - a single treble note does not repeat the finger of the single treble note before it, so
+///   melodies alternate fingers (e.g. `i m i` for three notes on the third string).
+pub fn assign_picking_fingers(
+    arrangement_lines: &[Line<BeatVec<PitchFingering>>],
+    num_strings: usize,
+) -> Vec<BeatVec<PickingFinger>> {
+    let is_bass_string =
+        |fingering: &PitchFingering| fingering.string_number.get() as usize > num_strings / 2;
+    let mut previous_melody_finger: Option<PickingFinger> = None;
+
+    arrangement_lines
+        .iter()
+        .map(|line| {
+            let fingerings = match line {
+                Line::Playable(fingerings) => fingerings,
+                Line::Rest => {
+                    previous_melody_finger = None;
+                    return vec![];
+                }
+                _ => return vec![],
+            };
+
+            let mut used_treble_fingers = vec![];
+            let fingers = fingerings
+                .iter()
+                .enumerate()
+                .sorted_by_key(|(_, fingering)| fingering.string_number)
+                .map(|(fingering_index, fingering)| {
+                    let finger = match is_bass_string(fingering) {
+                        true => PickingFinger::Thumb,
+                        false => {
+                            let finger =
+                                PickingFinger::for_treble_string(fingering.string_number.get());
+                            match (fingerings.len(), previous_melody_finger) {
+                                (1, Some(previous_finger)) if previous_finger == finger => {
+                                    finger.alternate()
+                                }
+                                _ if used_treble_fingers.contains(&finger) => PickingFinger::Thumb,
+                                _ => finger,
+                            }
+                        }
+                    };
+                    used_treble_fingers.push(finger);
+                    (fingering_index, finger)
+                })
+                .sorted()
+                .map(|(_, finger)| finger)
+                .collect_vec();
+
+            previous_melody_finger = match fingers.as_slice() {
+                [finger] if *finger != PickingFinger::Thumb => Some(*finger),
+                _ => None,
+            };
+            fingers
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_assign_picking_fingers {
+    use super::*;
+    use crate::{pitch::Pitch, string_number::StringNumber};
+    use PickingFinger::{Index, Middle, Ring, Thumb};
+
+    fn fingering(string_number: u8) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret: 0,
+            harmonic: None,
+        }
+    }
+    fn playable(string_numbers: &[u8]) -> Line<BeatVec<PitchFingering>> {
+        Line::Playable(string_numbers.iter().copied().map(fingering).collect_vec())
+    }
+
+    #[test]
+    fn arpeggio() {
+        let lines = vec![
+            playable(&[5]),
+            playable(&[3]),
+            playable(&[2]),
+            playable(&[1]),
+        ];
+        assert_eq!(
+            assign_picking_fingers(&lines, 6),
+            vec![vec![Thumb], vec![Index], vec![Middle], vec![Ring]]
+        );
+    }
+    #[test]
+    fn chord() {
+        let lines = vec![playable(&[1, 6, 3, 2]), playable(&[4, 5])];
+        assert_eq!(
+            assign_picking_fingers(&lines, 6),
+            vec![vec![Ring, Thumb, Index, Middle], vec![Thumb, Thumb]]
+        );
+    }
+    #[test]
+    fn alternating_melody() {
+        let lines = vec![
+            playable(&[3]),
+            playable(&[3]),
+            Line::MeasureBreak,
+            playable(&[3]),
+            Line::Rest,
+            playable(&[3]),
+        ];
+        assert_eq!(
+            assign_picking_fingers(&lines, 6),
+            vec![
+                vec![Index],
+                vec![Middle],
+                vec![],
+                vec![Index],
+                vec![],
+                vec![Index]
+            ]
+        );
+    }
+    #[test]
+    fn crossing_strings() {
+        // The middle finger on the third string is followed by the index finger on the second
+        // string rather than repeating the middle finger
+        let lines = vec![playable(&[3]), playable(&[3]), playable(&[2])];
+        assert_eq!(
+            assign_picking_fingers(&lines, 6),
+            vec![vec![Index], vec![Middle], vec![Index]]
+        );
+    }
+    #[test]
+    fn extended_range_guitar() {
+        let lines = vec![playable(&[4, 3, 8])];
+        assert_eq!(
+            assign_picking_fingers(&lines, 8),
+            vec![vec![Thumb, Index, Thumb]]
+        );
+    }
+}
+
+/// The direction of a plectrum stroke.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum PickDirection {
+    /// A downstroke (`⊓`), moving the pick towards the first string.
+    Down,
+    /// An upstroke (`V`), moving the pick towards the lowest string.
+    Up,
+}
+impl PickDirection {
+    fn reversed(&self) -> Self {
+        match self {
+            PickDirection::Down => PickDirection::Up,
+            PickDirection::Up => PickDirection::Down,
+        }
+    }
+}
+impl fmt::Display for PickDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PickDirection::Down => write!(f, "⊓"),
+            PickDirection::Up => write!(f, "V"),
+        }
+    }
+}
+
+/// How the pick directions of consecutive single notes are chosen by a plectrum player.
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema, EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum PickStyle {
+    /// Every note is picked in the opposite direction of the note before it.
+    Alternate,
+    /// Notes on the same string alternate, while a note on another string is picked in the
+    /// direction of that string so that the pick sweeps across the strings.
+    Economy,
+}
+
+/// Lists the strict alternate pick direction of every single note line, starting with a
+/// downstroke after every rest or chord. Chords and lines that are not single notes have no pick
+/// direction.
+pub fn alternate_pick_directions<T>(lines: &[Line<BeatVec<T>>]) -> Vec<Option<PickDirection>> {
+    let mut previous_direction: Option<PickDirection> = None;
+
+    lines
+        .iter()
+        .map(|line| match line {
+            Line::Playable(pitches) if pitches.len() == 1 => {
+                let direction = previous_direction
+                    .map_or(PickDirection::Down, |direction| direction.reversed());
+                previous_direction = Some(direction);
+                Some(direction)
+            }
+            Line::Playable(_) | Line::Rest => {
+                previous_direction = None;
+                None
+            }
+            _ => None,
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_alternate_pick_directions {
+    use super::*;
+    use crate::pitch::Pitch;
+    use PickDirection::{Down, Up};
+
+    #[test]
+    fn simple() {
+        let lines = vec![
+            Line::Playable(vec![Pitch::E4]),
+            Line::Playable(vec![Pitch::G4]),
+            Line::MeasureBreak,
+            Line::Playable(vec![Pitch::A4]),
+            Line::Rest,
+            Line::Playable(vec![Pitch::B4]),
+            Line::Playable(vec![Pitch::E2, Pitch::B3]),
+            Line::Playable(vec![Pitch::C4]),
+        ];
+        assert_eq!(
+            alternate_pick_directions(&lines),
+            vec![
+                Some(Down),
+                Some(Up),
+                None,
+                Some(Down),
+                None,
+                Some(Down),
+                None,
+                Some(Down)
+            ]
+        );
+    }
+}
+
+/// Assigns a pick direction to every single note line of the arrangement lines in the given pick
+/// style (see `PickStyle`), starting with a downstroke after every rest or chord. Chords and lines
+/// that are not single notes have no pick direction.
+pub fn assign_pick_directions(
+    arrangement_lines: &[Line<BeatVec<PitchFingering>>],
+    pick_style: PickStyle,
+) -> Vec<Option<PickDirection>> {
+    if pick_style == PickStyle::Alternate {
+        return alternate_pick_directions(arrangement_lines);
+    }
+
+    let mut previous_note: Option<(StringNumber, PickDirection)> = None;
+    arrangement_lines
+        .iter()
+        .map(|line| match line {
+            Line::Playable(fingerings) if fingerings.len() == 1 => {
+                let string_number = fingerings[0].string_number;
+                let direction = match previous_note {
+                    None => PickDirection::Down,
+                    Some((previous_string_number, previous_direction)) => {
+                        match string_number.cmp(&previous_string_number) {
+                            std::cmp::Ordering::Less => PickDirection::Down,
+                            std::cmp::Ordering::Greater => PickDirection::Up,
+                            std::cmp::Ordering::Equal => previous_direction.reversed(),
+                        }
+                    }
+                };
+                previous_note = Some((string_number, direction));
+                Some(direction)
+            }
+            Line::Playable(_) | Line::Rest => {
+                previous_note = None;
+                None
+            }
+            _ => None,
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_assign_pick_directions {
+    use super::*;
+    use crate::pitch::Pitch;
+    use PickDirection::{Down, Up};
+
+    fn playable(string_numbers: &[u8]) -> Line<BeatVec<PitchFingering>> {
+        Line::Playable(
+            string_numbers
+                .iter()
+                .map(|&string_number| PitchFingering {
+                    pitch: Pitch::E4,
+                    string_number: StringNumber::new(string_number).unwrap(),
+                    fret: 0,
+                    harmonic: None,
+                })
+                .collect_vec(),
+        )
+    }
+
+    #[test]
+    fn alternate() {
+        let lines = vec![
+            playable(&[3]),
+            playable(&[2]),
+            playable(&[2]),
+            playable(&[3]),
+        ];
+        assert_eq!(
+            assign_pick_directions(&lines, PickStyle::Alternate),
+            vec![Some(Down), Some(Up), Some(Down), Some(Up)]
+        );
+    }
+    #[test]
+    fn economy() {
+        let lines = vec![
+            playable(&[3]),
+            playable(&[3]),
+            playable(&[2]),
+            playable(&[1]),
+            playable(&[1]),
+            playable(&[2]),
+            Line::Rest,
+            playable(&[2]),
+            playable(&[1, 2]),
+            playable(&[1]),
+        ];
+        assert_eq!(
+            assign_pick_directions(&lines, PickStyle::Economy),
+            vec![
+                Some(Down),
+                Some(Up),
+                Some(Down),
+                Some(Down),
+                Some(Up),
+                Some(Up),
+                None,
+                Some(Down),
+                None,
+                Some(Down)
+            ]
+        );
+    }
+}
+
+/// Checks if moving from a note picked in the given direction to a note on another string is
+/// outside picking, where the pick has to jump over the next string to reach it because the stroke
+/// moved the pick away from it (e.g. a downstroke on the third string followed by an upstroke on
+/// the second string).
+pub(crate) fn is_outside_picking(
+    string_number: StringNumber,
+    direction: PickDirection,
+    next_string_number: StringNumber,
+) -> bool {
+    match direction {
+        PickDirection::Down => next_string_number < string_number,
+        PickDirection::Up => next_string_number > string_number,
+    }
+}
+#[cfg(test)]
+mod test_is_outside_picking {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let string = |string_number| StringNumber::new(string_number).unwrap();
+        assert!(is_outside_picking(
+            string(3),
+            PickDirection::Down,
+            string(2)
+        ));
+        assert!(!is_outside_picking(
+            string(3),
+            PickDirection::Down,
+            string(4)
+        ));
+        assert!(is_outside_picking(string(2), PickDirection::Up, string(3)));
+        assert!(!is_outside_picking(string(2), PickDirection::Up, string(1)));
+        assert!(!is_outside_picking(string(2), PickDirection::Up, string(2)));
+        assert!(!is_outside_picking(
+            string(2),
+            PickDirection::Down,
+            string(2)
+        ));
+    }
+}
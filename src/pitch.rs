@@ -1,9 +1,24 @@
-use anyhow::{anyhow, Result};
+use crate::error::{Error, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use strum_macros::{EnumIter, EnumString, FromRepr};
 
 #[derive(
-    Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, EnumIter, FromRepr, EnumString,
+    Debug,
+    Clone,
+    Copy,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    EnumIter,
+    FromRepr,
+    EnumString,
+    Serialize,
+    Deserialize,
+    JsonSchema,
 )]
 #[strum(ascii_case_insensitive)]
 pub enum Pitch {
@@ -218,9 +233,10 @@ impl Pitch {
     pub fn plus_offset(&self, offset: i16) -> Result<Pitch> {
         match Pitch::from_repr((self.index() as i16 + offset) as usize) {
             Some(pitch) => Ok(pitch),
-            None => Err(anyhow!(
-                "Pitch {self} offset by {offset} pitches results in a pitch out of range."
-            )),
+            None => Err(Error::PitchOutOfRange {
+                pitch: *self,
+                offset,
+            }),
         }
     }
 }
@@ -263,3 +279,270 @@ mod test_pitch_plus_offset {
         );
     }
 }
+#[cfg(test)]
+mod test_pitch_serde {
+    use super::*;
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            serde_json::to_string(&Pitch::CSharpDFlat4).unwrap(),
+            "\"CSharpDFlat4\""
+        );
+    }
+    #[test]
+    fn deserialize() {
+        assert_eq!(serde_json::from_str::<Pitch>("\"E2\"").unwrap(), Pitch::E2);
+        assert!(serde_json::from_str::<Pitch>("\"H2\"").is_err());
+    }
+}
+
+/// A pitch as written, keeping the note letter and accidental that `Pitch` does not distinguish
+/// (e.g. `F#4` and `Gb4`).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "RawSpelledPitch")]
+pub struct SpelledPitch {
+    /// Note letter, from `A` to `G`.
+    pub letter: char,
+    /// Semitones added by the accidental, from -2 (double flat) to 2 (double sharp).
+    pub accidental: i8,
+    /// Octave number of the note letter, so `B#3` sounds the same as `C4`.
+    pub octave: i8,
+}
+impl SpelledPitch {
+    /// Parses a note letter, an optional accidental and an octave number (e.g. `Bb3`, `F##4` or
+    /// `Cx2`), ignoring the case of the letters.
+    ///
+    /// Sharps are written as `#` or `♯`, flats as `b` or `♭` and double sharps and flats as two
+    /// of them, `x` or `𝄪` and `𝄫`.
+    pub fn parse(text: &str) -> Option<SpelledPitch> {
+        let mut chars = text.chars();
+        let letter = chars.next()?.to_ascii_uppercase();
+        if !('A'..='G').contains(&letter) {
+            return None;
+        }
+        let rest = chars.as_str().to_lowercase();
+        let octave_start = rest.find(|c: char| c.is_ascii_digit())?;
+        let (accidental, octave) = rest.split_at(octave_start);
+
+        Some(SpelledPitch {
+            letter,
+            accidental: parse_accidental(accidental)?,
+            octave: match octave.len() {
+                1 => octave.parse().ok()?,
+                _ => return None,
+            },
+        })
+    }
+
+    /// Returns the sounding pitch, or `None` if it is outside the range of `Pitch` or the letter
+    /// is not from `A` to `G`.
+    pub fn pitch(&self) -> Option<Pitch> {
+        const NATURAL_PITCH_CLASSES: [i16; 7] = [9, 11, 0, 2, 4, 5, 7];
+        let letter_index = "ABCDEFG".find(self.letter)?;
+        let index =
+            self.octave as i16 * 12 + NATURAL_PITCH_CLASSES[letter_index] + self.accidental as i16;
+        usize::try_from(index).ok().and_then(Pitch::from_repr)
+    }
+
+    /// Transposes the spelling by a number of semitones, spelling the transposed pitch with the
+    /// fewest accidentals so that accidentals do not pile up (e.g. `C##4` transposed down a
+    /// semitone is `C#4` rather than `B##3`). Between spellings with as few accidentals, the one
+    /// closest to moving the note letter by the interval most often written for that number of
+    /// semitones (e.g. a major second for 2 semitones and an augmented fourth for 6) is chosen.
+    /// Returns `None` if the letter is not from `A` to `G`.
+    pub fn transposed(&self, semitones: i16) -> Option<SpelledPitch> {
+        const NATURAL_PITCH_CLASSES: [i16; 7] = [0, 2, 4, 5, 7, 9, 11];
+        const LETTER_STEPS: [i16; 12] = [0, 1, 1, 2, 2, 3, 3, 4, 5, 5, 6, 6];
+        let letter_index = "CDEFGAB".find(self.letter)?;
+        let index = self.octave as i16 * 12
+            + NATURAL_PITCH_CLASSES[letter_index]
+            + self.accidental as i16
+            + semitones;
+        let interval_position = self.octave as i16 * 7
+            + letter_index as i16
+            + semitones.div_euclid(12) * 7
+            + LETTER_STEPS[semitones.rem_euclid(12) as usize];
+
+        (interval_position - 3..=interval_position + 3)
+            .filter_map(|position| {
+                let letter_index = position.rem_euclid(7) as usize;
+                let octave = position.div_euclid(7);
+                let accidental = index - octave * 12 - NATURAL_PITCH_CLASSES[letter_index];
+                (-2..=2)
+                    .contains(&accidental)
+                    .then_some((position, accidental, octave))
+            })
+            .min_by_key(|(position, accidental, _)| {
+                (accidental.abs(), (position - interval_position).abs())
+            })
+            .and_then(|(position, accidental, octave)| {
+                Some(SpelledPitch {
+                    letter: "CDEFGAB".as_bytes()[position.rem_euclid(7) as usize] as char,
+                    accidental: accidental as i8,
+                    octave: i8::try_from(octave).ok()?,
+                })
+            })
+    }
+
+    /// Spells the pitch in plain text, writing double sharps and flats as `##` and `bb`.
+    pub fn plain_text(&self) -> String {
+        let accidental = match self.accidental {
+            ..=-1 => "b".repeat(self.accidental.unsigned_abs() as usize),
+            _ => "#".repeat(self.accidental as usize),
+        };
+        format!("{}{}{}", self.letter, accidental, self.octave)
+    }
+}
+#[cfg(test)]
+mod test_spelled_pitch {
+    use super::*;
+
+    fn spelled_pitch(letter: char, accidental: i8, octave: i8) -> SpelledPitch {
+        SpelledPitch {
+            letter,
+            accidental,
+            octave,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(SpelledPitch::parse("F#4"), Some(spelled_pitch('F', 1, 4)));
+        assert_eq!(SpelledPitch::parse("gB4"), Some(spelled_pitch('G', -1, 4)));
+        assert_eq!(SpelledPitch::parse("E2"), Some(spelled_pitch('E', 0, 2)));
+        assert_eq!(SpelledPitch::parse("C𝄪3"), Some(spelled_pitch('C', 2, 3)));
+        assert_eq!(SpelledPitch::parse("Dx3"), Some(spelled_pitch('D', 2, 3)));
+        assert_eq!(SpelledPitch::parse("Bbb2"), Some(spelled_pitch('B', -2, 2)));
+        assert_eq!(SpelledPitch::parse("A♭♭2"), Some(spelled_pitch('A', -2, 2)));
+    }
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(SpelledPitch::parse("H2"), None);
+        assert_eq!(SpelledPitch::parse("C"), None);
+        assert_eq!(SpelledPitch::parse("C#b2"), None);
+        assert_eq!(SpelledPitch::parse("C###2"), None);
+        assert_eq!(SpelledPitch::parse("C12"), None);
+    }
+    #[test]
+    fn pitch() {
+        assert_eq!(spelled_pitch('F', 1, 4).pitch(), Some(Pitch::FSharpGFlat4));
+        assert_eq!(spelled_pitch('G', -1, 4).pitch(), Some(Pitch::FSharpGFlat4));
+        assert_eq!(spelled_pitch('B', 1, 3).pitch(), Some(Pitch::C4));
+        assert_eq!(spelled_pitch('C', -1, 4).pitch(), Some(Pitch::B3));
+        assert_eq!(spelled_pitch('C', -1, 0).pitch(), None);
+        assert_eq!(spelled_pitch('B', 1, 9).pitch(), None);
+        assert_eq!(spelled_pitch('H', 0, 4).pitch(), None);
+        assert_eq!(spelled_pitch('a', 0, 4).pitch(), None);
+    }
+    #[test]
+    fn transposed() {
+        let transposed = |text: &str, semitones: i16| {
+            SpelledPitch::parse(text)
+                .unwrap()
+                .transposed(semitones)
+                .map(|spelled_pitch| spelled_pitch.plain_text())
+        };
+        assert_eq!(transposed("F#3", 2).as_deref(), Some("G#3"));
+        assert_eq!(transposed("Bb3", 2).as_deref(), Some("C4"));
+        assert_eq!(transposed("Gb4", -1).as_deref(), Some("F4"));
+        assert_eq!(transposed("E3", 6).as_deref(), Some("A#3"));
+        assert_eq!(transposed("B#3", 1).as_deref(), Some("C#4"));
+        assert_eq!(transposed("Eb4", -14).as_deref(), Some("Db3"));
+        assert_eq!(transposed("C4", 0).as_deref(), Some("C4"));
+    }
+    #[test]
+    fn transposed_with_fewest_accidentals() {
+        let transposed = |text: &str, semitones: i16| {
+            SpelledPitch::parse(text)
+                .unwrap()
+                .transposed(semitones)
+                .map(|spelled_pitch| spelled_pitch.plain_text())
+        };
+        assert_eq!(transposed("C##4", -1).as_deref(), Some("C#4"));
+        assert_eq!(transposed("Bb3", 1).as_deref(), Some("B3"));
+        assert_eq!(transposed("Dbb4", 1).as_deref(), Some("Db4"));
+        assert_eq!(transposed("F#3", -1).as_deref(), Some("F3"));
+        assert_eq!(transposed("B##3", 6).as_deref(), Some("G4"));
+        assert_eq!(spelled_pitch('H', 0, 4).transposed(2), None);
+    }
+    #[test]
+    fn plain_text() {
+        assert_eq!(spelled_pitch('F', 1, 4).plain_text(), "F#4");
+        assert_eq!(spelled_pitch('G', -1, 4).plain_text(), "Gb4");
+        assert_eq!(spelled_pitch('C', 2, 3).plain_text(), "C##3");
+        assert_eq!(spelled_pitch('B', -2, 2).plain_text(), "Bbb2");
+        assert_eq!(spelled_pitch('E', 0, 2).plain_text(), "E2");
+    }
+}
+
+/// The fields a spelled pitch is deserialized from, so that letters other than `A` to `G` and
+/// accidentals beyond double sharps and flats are rejected.
+#[derive(Deserialize)]
+struct RawSpelledPitch {
+    letter: char,
+    accidental: i8,
+    octave: i8,
+}
+impl TryFrom<RawSpelledPitch> for SpelledPitch {
+    type Error = Error;
+
+    fn try_from(raw_spelled_pitch: RawSpelledPitch) -> Result<Self> {
+        let RawSpelledPitch {
+            letter,
+            accidental,
+            octave,
+        } = raw_spelled_pitch;
+        if !('A'..='G').contains(&letter) || !(-2..=2).contains(&accidental) {
+            return Err(Error::InvalidSpelledPitch { letter, accidental });
+        }
+        Ok(SpelledPitch {
+            letter,
+            accidental,
+            octave,
+        })
+    }
+}
+#[cfg(test)]
+mod test_spelled_pitch_serde {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let spelled_pitch = SpelledPitch::parse("Gb4").unwrap();
+        let serialized = serde_json::to_string(&spelled_pitch).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SpelledPitch>(&serialized).unwrap(),
+            spelled_pitch
+        );
+    }
+    #[test]
+    fn invalid_letter() {
+        let error = serde_json::from_str::<SpelledPitch>(
+            r#"{"letter": "H", "accidental": 0, "octave": 4}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The spelled pitch has letter 'H' and accidental 0."));
+    }
+    #[test]
+    fn invalid_accidental() {
+        assert!(serde_json::from_str::<SpelledPitch>(
+            r#"{"letter": "C", "accidental": 3, "octave": 4}"#,
+        )
+        .is_err());
+    }
+}
+
+/// Converts a written accidental into the number of semitones it adds.
+pub(crate) fn parse_accidental(accidental: &str) -> Option<i8> {
+    match accidental {
+        "" => Some(0),
+        "#" | "♯" => Some(1),
+        "b" | "♭" => Some(-1),
+        "##" | "♯♯" | "x" | "𝄪" => Some(2),
+        "bb" | "♭♭" | "𝄫" => Some(-2),
+        _ => None,
+    }
+}
@@ -0,0 +1,813 @@
+use crate::{
+    arrangement::{
+        create_arrangements_with_articulation, pitch_fingering_candidates, Arrangement,
+        Articulation, BeatVec, Line, StringCosts,
+    },
+    create_composition, create_input_guitar,
+    error::{Error, Result},
+    guitar::{Guitar, PitchFingering},
+    parser::{parse_input_in_mode, InputMode, ParsedInput},
+    picking::PickStyle,
+    pitch::Pitch,
+    Composition, CompositionInput, ShownAnnotations,
+};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+/// Version of the project document written by `save_project`.
+///
+/// Version history:
+/// * 0: Unversioned `CompositionInput` documents.
+/// * 1: Input, guitar and cost settings with pinned fingerings and the selected arrangement.
+pub const PROJECT_VERSION: u32 = 1;
+
+/// A fingering chosen by the user for a pitch on an input line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PinnedFingering {
+    /// The number of the input line, counting from one.
+    pub line_number: usize,
+    pub fingering: PitchFingering,
+}
+
+/// A saved project containing everything needed to reproduce a composition, including the
+/// arrangement that the user selected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Project {
+    pub version: u32,
+    pub pitches: String,
+    pub tuning_name: String,
+    pub guitar_num_frets: u8,
+    pub guitar_capo: u8,
+    pub guitar_scale_length: Option<u16>,
+    pub open_string_cost: u16,
+    #[serde(default)]
+    pub string_costs: StringCosts,
+    #[serde(default)]
+    pub pick_style: Option<PickStyle>,
+    #[serde(default)]
+    pub input_mode: InputMode,
+    #[serde(default)]
+    pub show_chord_names: bool,
+    #[serde(default)]
+    pub show_picking_fingers: bool,
+    #[serde(default)]
+    pub show_hand_positions: bool,
+    pub pinned_fingerings: Vec<PinnedFingering>,
+    pub selected_arrangement: Option<Arrangement>,
+}
+impl Project {
+    pub fn new(composition_input: &CompositionInput) -> Self {
+        Project {
+            version: PROJECT_VERSION,
+            pitches: composition_input.pitches.clone(),
+            tuning_name: composition_input.tuning_name.clone(),
+            guitar_num_frets: composition_input.guitar_num_frets,
+            guitar_capo: composition_input.guitar_capo,
+            guitar_scale_length: composition_input.guitar_scale_length,
+            open_string_cost: composition_input.open_string_cost,
+            string_costs: composition_input.string_costs,
+            pick_style: composition_input.pick_style,
+            input_mode: composition_input.input_mode,
+            show_chord_names: composition_input.show_chord_names,
+            show_picking_fingers: composition_input.show_picking_fingers,
+            show_hand_positions: composition_input.show_hand_positions,
+            pinned_fingerings: vec![],
+            selected_arrangement: None,
+        }
+    }
+
+    /// Creates the guitar of the project, with the guitar settings overridden by directives at the
+    /// start of the input pitches.
+    pub fn guitar(&self) -> Result<Guitar> {
+        create_input_guitar(
+            &self.pitches,
+            &self.tuning_name,
+            self.guitar_num_frets,
+            self.guitar_capo,
+            self.guitar_scale_length,
+        )
+    }
+}
+
+#[wasm_bindgen]
+#[cfg(not(tarpaulin_include))]
+pub fn wasm_load_project(project_document: String) -> Result<JsValue, JsValue> {
+    let project = load_project(&project_document)?;
+
+    Ok(serde_wasm_bindgen::to_value(&project)?)
+}
+
+#[wasm_bindgen]
+#[cfg(not(tarpaulin_include))]
+pub fn wasm_save_project(project: JsValue) -> Result<String, JsValue> {
+    let project: Project = serde_wasm_bindgen::from_value(project)?;
+
+    Ok(save_project(&project)?)
+}
+
+#[wasm_bindgen]
+#[cfg(not(tarpaulin_include))]
+pub fn wasm_render_project(
+    project: JsValue,
+    width: u16,
+    padding: u8,
+    playback_index: Option<u16>,
+) -> Result<JsValue, JsValue> {
+    let project: Project = serde_wasm_bindgen::from_value(project)?;
+
+    let composition = render_project(&project, width, padding, playback_index)?;
+
+    Ok(serde_wasm_bindgen::to_value(&composition)?)
+}
+
+/// Serializes the project into a JSON project document of the current version.
+pub fn save_project(project: &Project) -> Result<String> {
+    let project = Project {
+        version: PROJECT_VERSION,
+        ..project.clone()
+    };
+    serde_json::to_string_pretty(&project).map_err(|e| Error::InvalidProject {
+        message: e.to_string(),
+    })
+}
+#[cfg(test)]
+mod test_save_project {
+    use super::*;
+    use crate::{arrangement::create_arrangements, parser::parse_lines};
+
+    #[test]
+    fn round_trip() {
+        let mut project = Project::new(&test_composition_input());
+        project.selected_arrangement = Some(
+            create_arrangements(
+                project.guitar().unwrap(),
+                parse_lines(project.pitches.clone()).unwrap(),
+                2,
+                0,
+            )
+            .unwrap()[1]
+                .clone(),
+        );
+
+        let project_document = save_project(&project).unwrap();
+
+        assert_eq!(load_project(&project_document).unwrap(), project);
+    }
+    #[test]
+    fn writes_current_version() {
+        let project = Project {
+            version: 0,
+            ..Project::new(&test_composition_input())
+        };
+
+        let project_document = save_project(&project).unwrap();
+
+        assert!(project_document.contains(&format!("\"version\": {PROJECT_VERSION}")));
+    }
+}
+
+/// Deserializes a JSON project document, migrating documents of older versions to the current
+/// version.
+pub fn load_project(project_document: &str) -> Result<Project> {
+    let mut document: Value =
+        serde_json::from_str(project_document).map_err(|e| Error::InvalidProjectJson {
+            message: e.to_string(),
+        })?;
+
+    let mut version = match document.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| Error::InvalidProjectVersion {
+                version: version.to_string(),
+            })?,
+    };
+    if version > PROJECT_VERSION {
+        return Err(Error::UnsupportedProjectVersion {
+            version,
+            latest: PROJECT_VERSION,
+        });
+    }
+
+    while version < PROJECT_VERSION {
+        document = migrate_project_document(version, document)?;
+        version += 1;
+    }
+
+    let project: Project = serde_json::from_value(document).map_err(|e| Error::InvalidProject {
+        message: e.to_string(),
+    })?;
+
+    if !project.pinned_fingerings.is_empty() {
+        let guitar = project.guitar()?;
+        let parsed_input =
+            parse_input_in_mode(project.pitches.clone(), project.input_mode, &guitar)?;
+        pinned_line_fingerings(&project.pinned_fingerings, &parsed_input, &guitar)?;
+    }
+
+    Ok(project)
+}
+#[cfg(test)]
+mod test_load_project {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    #[test]
+    fn unversioned_composition_input() {
+        let composition_input = test_composition_input();
+        let project_document = serde_json::to_string(&composition_input).unwrap();
+
+        assert_eq!(
+            load_project(&project_document).unwrap(),
+            Project::new(&composition_input)
+        );
+
+        let composition_input = CompositionInput {
+            pitches: "E\nA,\nD".to_owned(),
+            tuning_name: "dropd".to_owned(),
+            guitar_num_frets: 22,
+            guitar_capo: 2,
+            guitar_scale_length: Some(648),
+            open_string_cost: 5,
+            string_costs: StringCosts {
+                crossing: 3,
+                skip: 7,
+            },
+            pick_style: Some(PickStyle::Economy),
+            input_mode: InputMode::Relative,
+            show_chord_names: true,
+            show_picking_fingers: true,
+            show_hand_positions: true,
+            ..test_composition_input()
+        };
+        let project_document = serde_json::to_string(&composition_input).unwrap();
+
+        let project = load_project(&project_document).unwrap();
+        assert_eq!(project, Project::new(&composition_input));
+        assert_eq!(
+            project,
+            Project {
+                version: PROJECT_VERSION,
+                pitches: "E\nA,\nD".to_owned(),
+                tuning_name: "dropd".to_owned(),
+                guitar_num_frets: 22,
+                guitar_capo: 2,
+                guitar_scale_length: Some(648),
+                open_string_cost: 5,
+                string_costs: StringCosts {
+                    crossing: 3,
+                    skip: 7,
+                },
+                pick_style: Some(PickStyle::Economy),
+                input_mode: InputMode::Relative,
+                show_chord_names: true,
+                show_picking_fingers: true,
+                show_hand_positions: true,
+                pinned_fingerings: vec![],
+                selected_arrangement: None,
+            }
+        );
+    }
+    #[test]
+    fn newer_version() {
+        let error = load_project(r#"{"version": 99}"#).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            format!("The project version (99) is newer than the latest supported version ({PROJECT_VERSION}).")
+        );
+    }
+    #[test]
+    fn invalid_version() {
+        let error = load_project(r#"{"version": "one"}"#).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "The project version (\"one\") is not a whole number that fits in 32 bits."
+        );
+
+        let error = load_project(r#"{"version": 4294967297}"#).unwrap_err();
+        assert_eq!(
+            error,
+            Error::InvalidProjectVersion {
+                version: "4294967297".to_owned()
+            }
+        );
+    }
+    #[test]
+    fn invalid_json() {
+        assert!(load_project("{").is_err());
+    }
+    #[test]
+    fn invalid_pinned_fingering() {
+        let mut project = Project::new(&test_composition_input());
+        project.pinned_fingerings = vec![PinnedFingering {
+            line_number: 1,
+            fingering: PitchFingering {
+                pitch: Pitch::E4,
+                string_number: StringNumber::new(1).unwrap(),
+                fret: 0,
+                harmonic: None,
+            },
+        }];
+        let project_document = save_project(&project).unwrap();
+
+        assert_eq!(
+            load_project(&project_document),
+            Err(Error::InvalidPinnedFingering {
+                line_number: 1,
+                fingering: project.pinned_fingerings[0].fingering,
+            })
+        );
+    }
+    #[test]
+    fn missing_fields() {
+        let error = load_project(r#"{"version": 1, "pitches": "E2"}"#).unwrap_err();
+        assert!(format!("{error}").starts_with("The project document is invalid:"));
+    }
+}
+
+/// Migrates a project document from the given version to the next version.
+fn migrate_project_document(version: u32, document: Value) -> Result<Value> {
+    match version {
+        0 => {
+            let composition_input: CompositionInput =
+                serde_json::from_value(document).map_err(|e| Error::InvalidProject {
+                    message: e.to_string(),
+                })?;
+            let project = Project {
+                version: 1,
+                ..Project::new(&composition_input)
+            };
+            serde_json::to_value(project).map_err(|e| Error::InvalidProject {
+                message: e.to_string(),
+            })
+        }
+        _ => unreachable!("Project documents of the latest version do not need a migration."),
+    }
+}
+#[cfg(test)]
+mod test_migrate_project_document {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn version_0_missing_field() {
+        let error = migrate_project_document(0, json!({"pitches": "E2"})).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "The project document is invalid: missing field `tuning_name`"
+        );
+    }
+}
+
+/// Renders the selected arrangement of the project without recalculating it. The best
+/// arrangement is calculated instead if there is no selected arrangement or if it no longer
+/// matches the input pitches or the guitar.
+pub fn render_project(
+    project: &Project,
+    width: u16,
+    padding: u8,
+    playback_index: Option<u16>,
+) -> Result<Composition> {
+    let guitar = project.guitar()?;
+    let parsed_input = parse_input_in_mode(project.pitches.clone(), project.input_mode, &guitar)?
+        .with_default_pick_style(project.pick_style);
+    let input_lines = &parsed_input.lines;
+    let pinned_fingerings =
+        pinned_line_fingerings(&project.pinned_fingerings, &parsed_input, &guitar)?;
+
+    let arrangement = match &project.selected_arrangement {
+        Some(arrangement)
+            if arrangement_matches(arrangement, input_lines, &pinned_fingerings, &guitar) =>
+        {
+            arrangement.clone()
+        }
+        _ => create_arrangements_with_articulation(
+            guitar.clone(),
+            input_lines.clone(),
+            Articulation {
+                pinned_fingerings,
+                ..parsed_input.articulation()
+            },
+            1,
+            project.open_string_cost,
+            project.string_costs,
+        )
+        .map_err(|error| parsed_input.restore_input_line_numbers(error))?
+        .remove(0),
+    };
+
+    Ok(create_composition(
+        arrangement,
+        &guitar,
+        &parsed_input,
+        width,
+        padding,
+        playback_index,
+        ShownAnnotations {
+            chord_names: project.show_chord_names,
+            picking_fingers: project.show_picking_fingers,
+            hand_positions: project.show_hand_positions,
+        },
+    ))
+}
+#[cfg(test)]
+mod test_render_project {
+    use super::*;
+    use crate::{
+        arrangement::create_arrangements,
+        parser::{parse_input, parse_lines},
+        string_number::StringNumber,
+    };
+
+    #[test]
+    fn selected_arrangement() {
+        let mut project = Project::new(&test_composition_input());
+        let arrangements = create_arrangements(
+            project.guitar().unwrap(),
+            parse_lines(project.pitches.clone()).unwrap(),
+            2,
+            0,
+        )
+        .unwrap();
+        project.selected_arrangement = Some(arrangements[1].clone());
+
+        let composition = render_project(&project, 30, 2, None).unwrap();
+
+        assert_eq!(composition.arrangement, arrangements[1]);
+    }
+    #[test]
+    fn selected_arrangement_with_harmonics() {
+        let mut project = Project::new(&CompositionInput {
+            pitches: "@harmonics natural\nB5\nE5\nB5".to_owned(),
+            ..test_composition_input()
+        });
+        let parsed_input = parse_input(project.pitches.clone()).unwrap();
+        let harmonic_arrangement =
+            create_arrangements(project.guitar().unwrap(), parsed_input.lines, 5, 0)
+                .unwrap()
+                .into_iter()
+                .skip(1)
+                .find(|arrangement| {
+                    arrangement.lines.iter().any(|line| match line {
+                        Line::Playable(fingerings) => fingerings
+                            .iter()
+                            .any(|fingering| fingering.harmonic.is_some()),
+                        _ => false,
+                    })
+                })
+                .unwrap();
+        project.selected_arrangement = Some(harmonic_arrangement.clone());
+
+        let project = load_project(&save_project(&project).unwrap()).unwrap();
+        let composition = render_project(&project, 30, 2, None).unwrap();
+
+        assert_eq!(composition.arrangement, harmonic_arrangement);
+    }
+    #[test]
+    fn pinned_fingering() {
+        let mut project = Project::new(&test_composition_input());
+        let pinned_line = Line::Playable(vec![PitchFingering {
+            pitch: Pitch::E4,
+            string_number: StringNumber::new(2).unwrap(),
+            fret: 5,
+            harmonic: None,
+        }]);
+        let best_composition = render_project(&project, 30, 2, None).unwrap();
+        assert!(!best_composition.arrangement.lines.contains(&pinned_line));
+
+        project.selected_arrangement = Some(best_composition.arrangement);
+        project.pinned_fingerings = vec![PinnedFingering {
+            line_number: 8,
+            fingering: PitchFingering {
+                pitch: Pitch::E4,
+                string_number: StringNumber::new(2).unwrap(),
+                fret: 5,
+                harmonic: None,
+            },
+        }];
+        let pinned_composition = render_project(&project, 30, 2, None).unwrap();
+
+        assert!(pinned_composition.arrangement.lines.contains(&pinned_line));
+    }
+    #[test]
+    fn outdated_selected_arrangement() {
+        let mut project = Project::new(&test_composition_input());
+        project.selected_arrangement = Some(
+            create_arrangements(
+                project.guitar().unwrap(),
+                parse_lines("C4".to_owned()).unwrap(),
+                1,
+                0,
+            )
+            .unwrap()[0]
+                .clone(),
+        );
+
+        let composition = render_project(&project, 30, 2, None).unwrap();
+        let best_arrangement = create_arrangements(
+            project.guitar().unwrap(),
+            parse_lines(project.pitches.clone()).unwrap(),
+            1,
+            0,
+        )
+        .unwrap()[0]
+            .clone();
+
+        assert_eq!(composition.arrangement, best_arrangement);
+    }
+}
+
+/// Checks that the arrangement plays the pitches of the input lines with fingerings that exist
+/// on the guitar, using the fingerings pinned on every input line.
+fn arrangement_matches(
+    arrangement: &Arrangement,
+    input_lines: &[Line<BeatVec<Pitch>>],
+    pinned_fingerings: &[Vec<PitchFingering>],
+    guitar: &Guitar,
+) -> bool {
+    let fingerings_exist = arrangement
+        .lines
+        .iter()
+        .filter_map(|line| match line {
+            Line::Playable(fingerings) => Some(fingerings),
+            _ => None,
+        })
+        .flatten()
+        .all(|fingering| pitch_fingering_candidates(guitar, &fingering.pitch).contains(fingering));
+
+    let arranged_pitches = arrangement.lines.iter().filter_map(|line| match line {
+        Line::Playable(fingerings) => Some(
+            fingerings
+                .iter()
+                .map(|fingering| fingering.pitch)
+                .sorted()
+                .collect_vec(),
+        ),
+        _ => None,
+    });
+    let input_pitches = input_lines.iter().filter_map(|line| match line {
+        Line::Playable(pitches) => Some(pitches.iter().copied().sorted().collect_vec()),
+        _ => None,
+    });
+
+    let arranged_fingerings = arrangement.lines.iter().filter_map(|line| match line {
+        Line::Playable(fingerings) => Some(fingerings),
+        _ => None,
+    });
+    let input_pinned_fingerings = input_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| matches!(line, Line::Playable(_)))
+        .map(|(line_index, _)| {
+            pinned_fingerings
+                .get(line_index)
+                .map_or(&[][..], Vec::as_slice)
+        });
+    let pins_used =
+        arranged_fingerings
+            .zip(input_pinned_fingerings)
+            .all(|(fingerings, pinned_fingerings)| {
+                pinned_fingerings
+                    .iter()
+                    .all(|pinned_fingering| fingerings.contains(pinned_fingering))
+            });
+
+    fingerings_exist && arranged_pitches.eq(input_pitches) && pins_used
+}
+#[cfg(test)]
+mod test_arrangement_matches {
+    use super::*;
+    use crate::{
+        arrangement::create_arrangements,
+        create_guitar,
+        guitar::{Harmonic, HarmonicCandidates},
+        parser::parse_lines,
+        string_number::StringNumber,
+    };
+
+    fn arrangement(guitar: &Guitar, input_lines: &[Line<BeatVec<Pitch>>]) -> Arrangement {
+        create_arrangements(guitar.clone(), input_lines.to_vec(), 1, 0).unwrap()[0].clone()
+    }
+
+    #[test]
+    fn matching() {
+        let guitar = Guitar::default();
+        let input_lines = parse_lines("E2A3\n\nC4".to_owned()).unwrap();
+        assert!(arrangement_matches(
+            &arrangement(&guitar, &input_lines),
+            &input_lines,
+            &[],
+            &guitar
+        ));
+    }
+    #[test]
+    fn different_pitches() {
+        let guitar = Guitar::default();
+        let input_lines = parse_lines("E2A3\n\nC4".to_owned()).unwrap();
+        let other_lines = parse_lines("E2A3\n\nD4".to_owned()).unwrap();
+        assert!(!arrangement_matches(
+            &arrangement(&guitar, &input_lines),
+            &other_lines,
+            &[],
+            &guitar
+        ));
+    }
+    #[test]
+    fn different_guitar() {
+        let guitar = Guitar::default();
+        let capo_guitar = create_guitar("standard", 18, 2, None).unwrap();
+        let input_lines = parse_lines("G4".to_owned()).unwrap();
+        assert!(!arrangement_matches(
+            &arrangement(&guitar, &input_lines),
+            &input_lines,
+            &[],
+            &capo_guitar
+        ));
+    }
+    #[test]
+    fn harmonics() {
+        let harmonics_guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        let input_lines = parse_lines("E6".to_owned()).unwrap();
+        let harmonic_arrangement = arrangement(&harmonics_guitar, &input_lines);
+        assert!(arrangement_matches(
+            &harmonic_arrangement,
+            &input_lines,
+            &[],
+            &harmonics_guitar
+        ));
+        assert!(!arrangement_matches(
+            &harmonic_arrangement,
+            &input_lines,
+            &[],
+            &Guitar::default()
+        ));
+
+        let input_lines = parse_lines("E5".to_owned()).unwrap();
+        let mut twelfth_fret_harmonic = arrangement(&harmonics_guitar, &input_lines);
+        twelfth_fret_harmonic.lines = vec![Line::Playable(vec![PitchFingering {
+            pitch: Pitch::E5,
+            string_number: StringNumber::new(1).unwrap(),
+            fret: 12,
+            harmonic: Some(Harmonic::Natural),
+        }])];
+        assert!(arrangement_matches(
+            &twelfth_fret_harmonic,
+            &input_lines,
+            &[],
+            &harmonics_guitar
+        ));
+        assert!(!arrangement_matches(
+            &twelfth_fret_harmonic,
+            &input_lines,
+            &[],
+            &Guitar::default()
+        ));
+    }
+    #[test]
+    fn unused_pinned_fingering() {
+        let guitar = Guitar::default();
+        let input_lines = parse_lines("E2A3\n\nC4".to_owned()).unwrap();
+        let pinned_fingering = PitchFingering {
+            pitch: Pitch::C4,
+            string_number: StringNumber::new(4).unwrap(),
+            fret: 10,
+            harmonic: None,
+        };
+        assert!(!arrangement_matches(
+            &arrangement(&guitar, &input_lines),
+            &input_lines,
+            &[vec![], vec![], vec![pinned_fingering]],
+            &guitar
+        ));
+    }
+}
+
+/// Finds the fingerings pinned on every parsed line of the input, checking that every pinned
+/// fingering plays a pitch of its line on the guitar and that the fingerings pinned on a line can
+/// be played together.
+fn pinned_line_fingerings(
+    pinned_fingerings: &[PinnedFingering],
+    parsed_input: &ParsedInput,
+    guitar: &Guitar,
+) -> Result<Vec<Vec<PitchFingering>>> {
+    let mut line_fingerings: Vec<Vec<PitchFingering>> = vec![vec![]; parsed_input.lines.len()];
+
+    for PinnedFingering {
+        line_number,
+        fingering,
+    } in pinned_fingerings
+    {
+        let invalid_pin = || Error::InvalidPinnedFingering {
+            line_number: *line_number,
+            fingering: *fingering,
+        };
+        let line_index = parsed_input
+            .spans
+            .iter()
+            .position(|line_spans| line_spans.line.line_number == *line_number)
+            .ok_or_else(invalid_pin)?;
+        let Line::Playable(pitches) = &parsed_input.lines[line_index] else {
+            return Err(invalid_pin());
+        };
+        if !pitches.contains(&fingering.pitch)
+            || !pitch_fingering_candidates(guitar, &fingering.pitch).contains(fingering)
+        {
+            return Err(invalid_pin());
+        }
+
+        let pinned_on_line = &mut line_fingerings[line_index];
+        let num_pinned_pitches = pinned_on_line
+            .iter()
+            .filter(|pinned| pinned.pitch == fingering.pitch)
+            .count();
+        let num_line_pitches = pitches
+            .iter()
+            .filter(|pitch| **pitch == fingering.pitch)
+            .count();
+        if num_pinned_pitches >= num_line_pitches
+            || pinned_on_line
+                .iter()
+                .any(|pinned| pinned.string_number == fingering.string_number)
+        {
+            return Err(Error::ConflictingPinnedFingerings {
+                line_number: *line_number,
+            });
+        }
+        pinned_on_line.push(*fingering);
+    }
+
+    Ok(line_fingerings)
+}
+#[cfg(test)]
+mod test_pinned_line_fingerings {
+    use super::*;
+    use crate::string_number::StringNumber;
+
+    fn pin(line_number: usize, pitch: Pitch, string_number: u8, fret: u8) -> PinnedFingering {
+        PinnedFingering {
+            line_number,
+            fingering: PitchFingering {
+                pitch,
+                string_number: StringNumber::new(string_number).unwrap(),
+                fret,
+                harmonic: None,
+            },
+        }
+    }
+
+    fn pinned_line_fingerings_of(
+        input: &str,
+        pinned_fingerings: &[PinnedFingering],
+    ) -> Result<Vec<Vec<PitchFingering>>> {
+        let guitar = Guitar::default();
+        let parsed_input =
+            parse_input_in_mode(input.to_owned(), InputMode::Absolute, &guitar).unwrap();
+        pinned_line_fingerings(pinned_fingerings, &parsed_input, &guitar)
+    }
+
+    #[test]
+    fn input_line_numbers() {
+        let pinned_fingerings = [pin(3, Pitch::C4, 3, 5)];
+        assert_eq!(
+            pinned_line_fingerings_of("@tempo 90\nE2\nC4", &pinned_fingerings).unwrap(),
+            vec![vec![], vec![pinned_fingerings[0].fingering]]
+        );
+    }
+    #[test]
+    fn pitch_not_on_line() {
+        let error = pinned_line_fingerings_of("E2\nC4", &[pin(1, Pitch::C4, 3, 5)]).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "The fingering pinned on line 1 (C4 | 3_G => 5) does not play a pitch of that line on the configured guitar."
+        );
+    }
+    #[test]
+    fn fingering_not_on_guitar() {
+        assert!(pinned_line_fingerings_of("E2\nC4", &[pin(2, Pitch::C4, 3, 7)]).is_err());
+        assert!(pinned_line_fingerings_of("E2\nC4", &[pin(3, Pitch::C4, 3, 5)]).is_err());
+    }
+    #[test]
+    fn conflicting_pins() {
+        assert_eq!(
+            pinned_line_fingerings_of("C4 E4", &[pin(1, Pitch::C4, 3, 5), pin(1, Pitch::E4, 3, 9)]),
+            Err(Error::ConflictingPinnedFingerings { line_number: 1 })
+        );
+        assert_eq!(
+            pinned_line_fingerings_of("C4 E4", &[pin(1, Pitch::C4, 3, 5), pin(1, Pitch::C4, 2, 1)]),
+            Err(Error::ConflictingPinnedFingerings { line_number: 1 })
+        );
+    }
+}
+
+#[cfg(test)]
+fn test_composition_input() -> CompositionInput {
+    CompositionInput {
+        padding: 2,
+        playback_index: Some(3),
+        ..crate::composition_input("E2\nA2\nD3\n\nG3\nB3\n---\nE4")
+    }
+}
@@ -1,10 +1,56 @@
 use crate::{
     arrangement::{BeatVec, Line},
-    guitar::{Guitar, PitchFingering},
+    guitar::{Guitar, Harmonic, PitchFingering},
+    picking::{PickDirection, PickingFinger},
+    technique::Technique,
 };
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
+/// The location of a sonorous beat (a playable line or a rest) in a rendered tab.
+///
+/// Rows are the zero-based line indices of the tab output and columns are zero-based character
+/// indices within those rows. The end row and end column are exclusive. The rows span the strings
+/// of the beat's row group, excluding the playback indicator rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeatLayout {
+    pub beat_index: u16,
+    pub line_index: usize,
+    pub row_group_index: usize,
+    pub start_row: usize,
+    pub end_row: usize,
+    pub start_column: usize,
+    pub end_column: usize,
+}
+
+/// The annotations rendered alongside the frets of a tab, each aligned with the arrangement lines.
+/// Annotations that are not given are not rendered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TabAnnotations<'a> {
+    /// The chord name of every line, rendered in a row naming the chords of the row group.
+    pub chord_names: Option<&'a [Option<String>]>,
+    /// The techniques of the pitches of every line, whose tab symbols are written after the frets
+    /// (e.g. `5h7` or `7b9`).
+    pub techniques: Option<&'a [BeatVec<Option<Technique>>]>,
+    /// The picking fingers of the pitches of every line, rendered in a row below the chord names
+    /// (e.g. `p` or `ima`).
+    pub picking_fingers: Option<&'a [BeatVec<PickingFinger>]>,
+    /// The plectrum pick direction of every line, rendered in a row of `⊓` and `V` markers below
+    /// the picking fingers.
+    pub pick_directions: Option<&'a [Option<PickDirection>]>,
+    /// The position of the fretting hand in every line, rendered in a row of markers where the
+    /// position changes (e.g. `Pos. V`).
+    pub hand_positions: Option<&'a [Option<u8>]>,
+}
+
+/// A rendered tab alongside the layout of every sonorous beat in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabRender {
+    pub tab: String,
+    pub beats: Vec<BeatLayout>,
+}
+
 pub fn render_tab(
     arrangement_lines: &[Line<BeatVec<PitchFingering>>],
     guitar: &Guitar,
@@ -12,8 +58,42 @@ pub fn render_tab(
     padding: u8,
     playback: Option<u16>,
 ) -> String {
+    render_tab_layout(
+        arrangement_lines,
+        guitar,
+        width,
+        padding,
+        playback,
+        TabAnnotations::default(),
+    )
+    .tab
+}
+
+/// Renders the tab like `render_tab`, additionally returning where every sonorous beat was
+/// rendered so that beats can be highlighted or looked up from a position in the tab.
+///
+/// If the arrangement has sections or numbered endings, every row group starts with a row labelling
+/// them, followed by the rows of the given annotations (see `TabAnnotations`).
+pub fn render_tab_layout(
+    arrangement_lines: &[Line<BeatVec<PitchFingering>>],
+    guitar: &Guitar,
+    width: u16,
+    padding: u8,
+    playback: Option<u16>,
+    annotations: TabAnnotations,
+) -> TabRender {
+    let TabAnnotations {
+        chord_names,
+        techniques,
+        picking_fingers,
+        pick_directions,
+        hand_positions,
+    } = annotations;
     if arrangement_lines.is_empty() {
-        return "".to_owned();
+        return TabRender {
+            tab: "".to_owned(),
+            beats: vec![],
+        };
     }
     let num_strings = guitar.string_ranges.len();
 
@@ -26,15 +106,58 @@ pub fn render_tab(
 
     let columns = arrangement_lines
         .iter()
-        .map(|line| render_line(line, num_strings))
+        .enumerate()
+        .map(|(line_index, line)| {
+            let line_techniques = techniques
+                .and_then(|techniques| techniques.get(line_index))
+                .map_or(&[][..], |line_techniques| line_techniques.as_slice());
+            render_line(line, line_techniques, num_strings)
+        })
         .collect_vec();
 
     let beat_column_renders = transpose(columns);
 
-    let (strings_rows, playback_indicator_position) =
-        render_string_groups(beat_column_renders, width, padding, line_index_of_playback);
+    let (strings_rows, column_positions) =
+        render_string_groups(beat_column_renders, width, padding);
+
+    let playback_indicator_position = line_index_of_playback
+        .and_then(|line_index| column_positions.get(line_index))
+        .map(playback_indicator_position);
 
-    render_string_output(&strings_rows, playback_indicator_position)
+    let num_row_groups = strings_rows[0].len();
+    let section_rows = render_section_rows(arrangement_lines, &column_positions, num_row_groups);
+    let chord_rows = chord_names.map(|chord_names| {
+        render_changed_label_rows(chord_names, &column_positions, num_row_groups)
+    });
+    let picking_rows = picking_fingers.map(|picking_fingers| {
+        render_picking_rows(picking_fingers, &column_positions, num_row_groups)
+    });
+    let pick_direction_rows = pick_directions.map(|pick_directions| {
+        render_pick_direction_rows(pick_directions, &column_positions, num_row_groups)
+    });
+    let hand_position_rows = hand_positions.map(|hand_positions| {
+        render_hand_position_rows(hand_positions, &column_positions, num_row_groups)
+    });
+    let label_rows = [
+        section_rows,
+        hand_position_rows,
+        chord_rows,
+        picking_rows,
+        pick_direction_rows,
+    ]
+    .into_iter()
+    .flatten()
+    .collect_vec();
+
+    TabRender {
+        tab: render_string_output(&strings_rows, &label_rows, playback_indicator_position),
+        beats: beat_layouts(
+            arrangement_lines,
+            &column_positions,
+            num_strings,
+            label_rows.len(),
+        ),
+    }
 }
 #[cfg(test)]
 mod test_render_tab {
@@ -48,16 +171,19 @@ mod test_render_tab {
                 pitch: Pitch::E4,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 0,
+                harmonic: None,
             }]),
             Line::Playable(vec![PitchFingering {
                 pitch: Pitch::DSharpEFlat4,
                 string_number: StringNumber::new(2).unwrap(),
                 fret: 4,
+                harmonic: None,
             }]),
             Line::Playable(vec![PitchFingering {
                 pitch: Pitch::E4,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 0,
+                harmonic: None,
             }]),
             Line::Rest,
             Line::MeasureBreak,
@@ -65,11 +191,13 @@ mod test_render_tab {
                 pitch: Pitch::DSharpEFlat4,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 4,
+                harmonic: None,
             }]),
             Line::Playable(vec![PitchFingering {
                 pitch: Pitch::A5,
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 12,
+                harmonic: None,
             }]),
         ]
     }
@@ -144,6 +272,227 @@ mod test_render_tab {
 
         assert_eq!(output, expected_output);
     }
+    #[test]
+    fn beat_layouts() {
+        let arrangement_lines = get_arrangement_lines();
+
+        let tab_render = render_tab_layout(
+            &arrangement_lines,
+            &Guitar::default(),
+            14,
+            1,
+            None,
+            TabAnnotations::default(),
+        );
+
+        assert_eq!(
+            tab_render.tab,
+            render_tab(&arrangement_lines, &Guitar::default(), 14, 1, None)
+        );
+        let beat_positions = tab_render
+            .beats
+            .iter()
+            .map(|beat| {
+                (
+                    beat.beat_index,
+                    beat.line_index,
+                    beat.start_row,
+                    beat.start_column,
+                    beat.end_column,
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            beat_positions,
+            vec![
+                (0, 0, 1, 1, 2),
+                (1, 1, 1, 3, 4),
+                (2, 2, 1, 5, 6),
+                (3, 3, 1, 7, 8),
+                (4, 5, 10, 1, 2),
+                (5, 6, 10, 3, 5),
+            ]
+        );
+        assert!(tab_render
+            .beats
+            .iter()
+            .all(|beat| beat.end_row - beat.start_row == 6));
+
+        let tab_rows = tab_render.tab.lines().collect_vec();
+        assert_eq!(&tab_rows[10][3..5], "12");
+        assert_eq!(&tab_rows[2][3..4], "4");
+    }
+    #[test]
+    fn repeats_and_sections() {
+        let fingering = |string_number, fret, pitch| PitchFingering {
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret,
+            pitch,
+            harmonic: None,
+        };
+        let arrangement_lines = vec![
+            Line::Section("Verse".to_owned()),
+            Line::RepeatStart,
+            Line::Playable(vec![fingering(2, 8, Pitch::G4)]),
+            Line::Ending(1),
+            Line::Playable(vec![fingering(1, 8, Pitch::C5)]),
+            Line::RepeatEnd,
+            Line::Ending(2),
+            Line::Playable(vec![fingering(1, 0, Pitch::E4)]),
+        ];
+
+        let tab_render = render_tab_layout(
+            &arrangement_lines,
+            &Guitar::default(),
+            30,
+            2,
+            Some(1),
+            TabAnnotations::default(),
+        );
+
+        let expected_tab = concat!(
+            "  Verse  1.     2.\n",
+            "         ▼\n",
+            "--|:-----8--:|--0-------------\n",
+            "--|:--8-----:|----------------\n",
+            "--|:--------:|----------------\n",
+            "--|:--------:|----------------\n",
+            "--|:--------:|----------------\n",
+            "--|:--------:|----------------\n",
+            "         ▲\n",
+        );
+        assert_eq!(tab_render.tab, expected_tab);
+        assert_eq!(tab_render.beats[1].start_row, 2);
+        assert_eq!(tab_render.beats[1].start_column, 9);
+    }
+    #[test]
+    fn empty() {
+        assert_eq!(
+            render_tab_layout(
+                &[],
+                &Guitar::default(),
+                14,
+                1,
+                Some(0),
+                TabAnnotations {
+                    chord_names: Some(&[]),
+                    ..Default::default()
+                }
+            ),
+            TabRender {
+                tab: "".to_owned(),
+                beats: vec![]
+            }
+        );
+    }
+    #[test]
+    fn chord_names() {
+        let pitch_fingering = |pitch, string_number, fret| PitchFingering {
+            pitch,
+            string_number: StringNumber::new(string_number).unwrap(),
+            fret,
+            harmonic: None,
+        };
+        let e_major = Line::Playable(vec![
+            pitch_fingering(Pitch::E2, 6, 0),
+            pitch_fingering(Pitch::B2, 5, 2),
+            pitch_fingering(Pitch::E3, 4, 2),
+            pitch_fingering(Pitch::GSharpAFlat3, 3, 1),
+        ]);
+        let arrangement_lines = vec![
+            e_major.clone(),
+            e_major,
+            Line::Playable(vec![pitch_fingering(Pitch::E4, 1, 0)]),
+            Line::Playable(vec![
+                pitch_fingering(Pitch::A2, 5, 0),
+                pitch_fingering(Pitch::E3, 4, 2),
+                pitch_fingering(Pitch::A3, 3, 2),
+                pitch_fingering(Pitch::CSharpDFlat4, 2, 2),
+            ]),
+        ];
+
+        let chord_names = vec![
+            Some("E".to_owned()),
+            Some("E".to_owned()),
+            None,
+            Some("A".to_owned()),
+        ];
+
+        let tab_render = render_tab_layout(
+            &arrangement_lines,
+            &Guitar::default(),
+            20,
+            1,
+            Some(3),
+            TabAnnotations {
+                chord_names: Some(&chord_names),
+                ..Default::default()
+            },
+        );
+
+        let expected_output = concat!(
+            " E     A\n",
+            "       ▼\n",
+            "-----0--------------\n",
+            "-------2------------\n",
+            "-1-1---2------------\n",
+            "-2-2---2------------\n",
+            "-2-2---0------------\n",
+            "-0-0----------------\n",
+            "       ▲\n"
+        );
+        assert_eq!(tab_render.tab, expected_output);
+        assert_eq!(tab_render.beats[0].start_row, 2);
+        assert_eq!(tab_render.beats[0].end_row, 8);
+    }
+}
+
+/// Finds the sonorous beat rendered at the row and column of the tab output.
+pub fn beat_at_position(beats: &[BeatLayout], row: usize, column: usize) -> Option<&BeatLayout> {
+    beats.iter().find(|beat| {
+        (beat.start_row..beat.end_row).contains(&row)
+            && (beat.start_column..beat.end_column).contains(&column)
+    })
+}
+#[cfg(test)]
+mod test_beat_at_position {
+    use super::*;
+
+    fn beats() -> Vec<BeatLayout> {
+        vec![
+            BeatLayout {
+                beat_index: 0,
+                line_index: 0,
+                row_group_index: 0,
+                start_row: 1,
+                end_row: 7,
+                start_column: 1,
+                end_column: 2,
+            },
+            BeatLayout {
+                beat_index: 1,
+                line_index: 2,
+                row_group_index: 1,
+                start_row: 10,
+                end_row: 16,
+                start_column: 1,
+                end_column: 3,
+            },
+        ]
+    }
+
+    #[test]
+    fn inside_beat() {
+        assert_eq!(beat_at_position(&beats(), 1, 1), Some(&beats()[0]));
+        assert_eq!(beat_at_position(&beats(), 15, 2), Some(&beats()[1]));
+    }
+    #[test]
+    fn outside_beats() {
+        assert_eq!(beat_at_position(&beats(), 0, 1), None);
+        assert_eq!(beat_at_position(&beats(), 7, 1), None);
+        assert_eq!(beat_at_position(&beats(), 1, 2), None);
+        assert_eq!(beat_at_position(&beats(), 10, 3), None);
+    }
 }
 
 fn line_index_of_sonorous_index(
@@ -180,33 +529,39 @@ mod test_line_index_of_sonorous_index {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 6,
                 pitch: Pitch::E4,
+                harmonic: None,
             }]),
             Line::Playable(vec![PitchFingering {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 6,
                 pitch: Pitch::E4,
+                harmonic: None,
             }]),
             Line::Rest,
             Line::Playable(vec![PitchFingering {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 6,
                 pitch: Pitch::E4,
+                harmonic: None,
             }]),
             Line::MeasureBreak,
             Line::Playable(vec![PitchFingering {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 6,
                 pitch: Pitch::E4,
+                harmonic: None,
             }]),
             Line::Playable(vec![PitchFingering {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 6,
                 pitch: Pitch::E4,
+                harmonic: None,
             }]),
             Line::Playable(vec![PitchFingering {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 6,
                 pitch: Pitch::E4,
+                harmonic: None,
             }]),
         ]
     }
@@ -228,21 +583,45 @@ mod test_line_index_of_sonorous_index {
 }
 
 /// Renders Line as a vector of strings representing the fret positions on a guitar.
-fn render_line(line: &Line<BeatVec<PitchFingering>>, num_strings: usize) -> Vec<String> {
+///
+/// Sections and numbered endings are rendered with no width, as they are labelled above the
+/// strings instead.
+fn render_line(
+    line: &Line<BeatVec<PitchFingering>>,
+    techniques: &[Option<Technique>],
+    num_strings: usize,
+) -> Vec<String> {
     let pitch_fingerings = match line {
         Line::MeasureBreak => return vec!["|".to_owned(); num_strings],
         Line::Rest => return vec!["-".to_owned(); num_strings],
-        Line::Playable(pitch_fingerings) => pitch_fingerings.iter().sorted().collect_vec(),
+        Line::RepeatStart => return vec!["|:".to_owned(); num_strings],
+        Line::RepeatEnd => return vec![":|".to_owned(); num_strings],
+        Line::Ending(_) | Line::Section(_) => return vec!["".to_owned(); num_strings],
+        Line::Playable(pitch_fingerings) => pitch_fingerings.iter().collect_vec(),
     };
     let fret_width_max = calc_fret_width_max(&pitch_fingerings);
+    let technique_symbols = pitch_fingerings
+        .iter()
+        .zip(techniques.iter().chain(std::iter::repeat(&None)))
+        .map(|(fingering, technique)| {
+            technique.map_or("".to_owned(), |technique| technique.render(fingering.fret))
+        })
+        .collect_vec();
+    let symbol_width_max = technique_symbols
+        .iter()
+        .map(|symbol| symbol.chars().count())
+        .max()
+        .unwrap_or(0);
 
-    // Instantiate vec with rest dashes for all strings with the max fret width
-    let mut playable_render = vec!["-".repeat(fret_width_max); num_strings];
+    // Instantiate vec with rest dashes for all strings with the max fret and symbol widths
+    let mut playable_render = vec!["-".repeat(fret_width_max + symbol_width_max); num_strings];
 
-    // Add the rendered frets for the strings that are played
-    for fingering in pitch_fingerings {
-        playable_render[fingering.string_number.get() as usize - 1] =
-            render_fret(fingering.fret, fret_width_max)
+    // Add the rendered frets and technique symbols for the strings that are played
+    for (fingering, symbol) in pitch_fingerings.into_iter().zip(technique_symbols) {
+        playable_render[fingering.string_number.get() as usize - 1] = format!(
+            "{}{symbol:-<symbol_width_max$}",
+            render_fret(&fingering_fret_text(fingering), fret_width_max)
+        )
     }
 
     playable_render
@@ -257,14 +636,14 @@ mod test_render_line {
     #[test]
     fn measure_break() {
         assert_eq!(
-            render_line(&Line::MeasureBreak, NUM_STRINGS),
+            render_line(&Line::MeasureBreak, &[], NUM_STRINGS),
             vec!["|".to_owned(); NUM_STRINGS]
         );
     }
     #[test]
     fn rest() {
         assert_eq!(
-            render_line(&Line::Rest, NUM_STRINGS),
+            render_line(&Line::Rest, &[], NUM_STRINGS),
             vec!["-".to_owned(); NUM_STRINGS]
         );
     }
@@ -275,17 +654,19 @@ mod test_render_line {
                 string_number: StringNumber::new(2).unwrap(),
                 fret: 2,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(5).unwrap(),
                 fret: 13,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
         ];
         let expected_line_render = vec!["--", "-2", "--", "--", "13", "--"];
 
         assert_eq!(
-            render_line(&Line::Playable(pitch_fingerings), 6),
+            render_line(&Line::Playable(pitch_fingerings), &[], 6),
             expected_line_render
         );
     }
@@ -296,42 +677,79 @@ mod test_render_line {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 9,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(2).unwrap(),
                 fret: 0,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(4).unwrap(),
                 fret: 8,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(5).unwrap(),
                 fret: 10,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(6).unwrap(),
                 fret: 0,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(7).unwrap(),
                 fret: 11,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(8).unwrap(),
                 fret: 12,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
         ];
         let expected_line_render = vec!["-9", "-0", "--", "-8", "10", "-0", "11", "12"];
 
         assert_eq!(
-            render_line(&Line::Playable(pitch_fingerings), 8),
+            render_line(&Line::Playable(pitch_fingerings), &[], 8),
+            expected_line_render
+        );
+    }
+    #[test]
+    fn playable_techniques() {
+        let pitch_fingerings = vec![
+            PitchFingering {
+                string_number: StringNumber::new(1).unwrap(),
+                fret: 7,
+                pitch: Pitch::B4,
+                harmonic: None,
+            },
+            PitchFingering {
+                string_number: StringNumber::new(2).unwrap(),
+                fret: 10,
+                pitch: Pitch::A4,
+                harmonic: None,
+            },
+            PitchFingering {
+                string_number: StringNumber::new(3).unwrap(),
+                fret: 5,
+                pitch: Pitch::C4,
+                harmonic: None,
+            },
+        ];
+        let techniques = [Some(Technique::Bend(2)), None, Some(Technique::HammerOn)];
+        let expected_line_render = vec!["-7b9", "10--", "-5h-", "----"];
+
+        assert_eq!(
+            render_line(&Line::Playable(pitch_fingerings), &techniques, 4),
             expected_line_render
         );
     }
@@ -343,24 +761,69 @@ mod test_render_line {
                 string_number: StringNumber::new(1).unwrap(),
                 fret: 9,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
             PitchFingering {
                 string_number: StringNumber::new(2).unwrap(),
                 fret: 0,
                 pitch: Pitch::G4,
+                harmonic: None,
             },
         ];
-        render_line(&Line::Playable(pitch_fingerings), 1);
+        render_line(&Line::Playable(pitch_fingerings), &[], 1);
+    }
+}
+
+/// Writes the fret of a fingering as it appears in the tab: the fret number for fretted notes,
+/// `<12>` for a natural harmonic touched above the 12th fret and `AH17` for an artificial harmonic
+/// of the 5th fret, which is touched above the 17th fret.
+fn fingering_fret_text(fingering: &PitchFingering) -> String {
+    match fingering.harmonic {
+        None => fingering.fret.to_string(),
+        Some(Harmonic::Natural) => format!("<{}>", fingering.fret),
+        Some(Harmonic::Artificial) => format!("AH{}", fingering.fret + 12),
+    }
+}
+#[cfg(test)]
+mod test_fingering_fret_text {
+    use super::*;
+    use crate::{pitch::Pitch, string_number::StringNumber};
+
+    fn fingering(fret: u8, harmonic: Option<Harmonic>) -> PitchFingering {
+        PitchFingering {
+            pitch: Pitch::E5,
+            string_number: StringNumber::new(1).unwrap(),
+            fret,
+            harmonic,
+        }
+    }
+
+    #[test]
+    fn fretted() {
+        assert_eq!(fingering_fret_text(&fingering(12, None)), "12");
+    }
+    #[test]
+    fn natural_harmonic() {
+        assert_eq!(
+            fingering_fret_text(&fingering(12, Some(Harmonic::Natural))),
+            "<12>"
+        );
+    }
+    #[test]
+    fn artificial_harmonic() {
+        assert_eq!(
+            fingering_fret_text(&fingering(5, Some(Harmonic::Artificial))),
+            "AH17"
+        );
     }
 }
 
-/// Creates a string with the fret number padded with dashes to match the maximum width.
+/// Creates a string with the fret text padded with dashes to match the maximum width.
 ///
 /// # Panics
 ///
-/// Panics if the width of the fret string representation is greater than `fret_width_max`.
-fn render_fret(fret: u8, fret_width_max: usize) -> String {
-    let fret_repr = fret.to_string();
+/// Panics if the width of the fret text is greater than `fret_width_max`.
+fn render_fret(fret_repr: &str, fret_width_max: usize) -> String {
     let fret_width = fret_repr.len();
     assert!(
         fret_width_max >= fret_width,
@@ -376,20 +839,20 @@ mod test_render_fret {
 
     #[test]
     fn one_digit_in_one_digit_max() {
-        assert_eq!(render_fret(4, 1), "4");
+        assert_eq!(render_fret("4", 1), "4");
     }
     #[test]
     fn one_digit_in_two_digit_max() {
-        assert_eq!(render_fret(3, 2), "-3");
+        assert_eq!(render_fret("3", 2), "-3");
     }
     #[test]
     fn two_digit_in_two_digit_max() {
-        assert_eq!(render_fret(12, 2), "12");
+        assert_eq!(render_fret("12", 2), "12");
     }
     #[test]
     #[should_panic]
     fn input_wider_than_max_width() {
-        render_fret(123, 2);
+        render_fret("123", 2);
     }
 }
 
@@ -397,7 +860,7 @@ mod test_render_fret {
 fn calc_fret_width_max(pitch_fingerings: &[&PitchFingering]) -> usize {
     pitch_fingerings
         .iter()
-        .map(|fingering| fingering.fret.to_string().len())
+        .map(|fingering| fingering_fret_text(fingering).len())
         .max()
         .expect("Playable line pitch fingerings should not be empty.")
 }
@@ -413,6 +876,7 @@ mod test_calc_fret_width_max {
             string_number: StringNumber::new(1).unwrap(),
             fret: 2,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         assert_eq!(calc_fret_width_max(&[&fingering]), 1);
     }
@@ -423,16 +887,19 @@ mod test_calc_fret_width_max {
             string_number: StringNumber::new(1).unwrap(),
             fret: 0,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         let fingering2 = PitchFingering {
             string_number: StringNumber::new(2).unwrap(),
             fret: 2,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         let fingering3 = PitchFingering {
             string_number: StringNumber::new(5).unwrap(),
             fret: 8,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         let fingerings = vec![&fingering1, &fingering2, &fingering3];
         assert_eq!(calc_fret_width_max(&fingerings), 1);
@@ -443,16 +910,19 @@ mod test_calc_fret_width_max {
             string_number: StringNumber::new(1).unwrap(),
             fret: 2,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         let fingering2 = PitchFingering {
             string_number: StringNumber::new(2).unwrap(),
             fret: 11,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         let fingering3 = PitchFingering {
             string_number: StringNumber::new(4).unwrap(),
             fret: 3,
             pitch: Pitch::G4,
+            harmonic: None,
         };
         let fingerings = vec![&fingering1, &fingering2, &fingering3];
         assert_eq!(calc_fret_width_max(&fingerings), 2);
@@ -515,21 +985,29 @@ struct PlaybackIndicatorPosition {
     column_index: usize,
 }
 
+/// The location of a rendered line within the row groups of the tab.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ColumnPosition {
+    row_group_index: usize,
+    column_index: usize,
+    width: usize,
+}
+
 fn render_string_groups(
     beat_column_renders: Vec<Vec<String>>,
     width: u16,
     padding: u8,
-    playback_column_index: Option<usize>,
-) -> (Vec<Vec<String>>, Option<PlaybackIndicatorPosition>) {
+) -> (Vec<Vec<String>>, Vec<ColumnPosition>) {
     let padding_render = "-".repeat(padding as usize);
 
     const MAX_FRET_RENDER_WIDTH: usize = 2;
     let mut strings_rows: Vec<Vec<String>> = vec![];
 
-    let mut playback_indicator_position: Option<PlaybackIndicatorPosition> = None;
+    // Every string is split into rows at the same columns, so the positions are only recorded
+    // for the first string.
+    let mut column_positions: Vec<ColumnPosition> = vec![];
 
-    for string_beat_columns in beat_column_renders {
-        let num_render_columns = string_beat_columns.len();
+    for (string_index, string_beat_columns) in beat_column_renders.into_iter().enumerate() {
         let mut remaining_string_beat_columns = VecDeque::from(string_beat_columns);
         let mut string_rows: Vec<String> = vec![];
 
@@ -543,25 +1021,15 @@ fn render_string_groups(
                         break;
                     }
                     Some(string_item) => {
-                        match playback_column_index {
-                            None => {}
-                            Some(idx) => {
-                                if num_render_columns - remaining_string_beat_columns.len() - 1
-                                    == idx
-                                {
-                                    // Offset the playback indicator by one
-                                    // character if the frets are two characters wide
-                                    let wide_fret_playback_offset = match string_item.len() {
-                                        2 => 1,
-                                        _ => 0,
-                                    };
-
-                                    playback_indicator_position = Some(PlaybackIndicatorPosition {
-                                        row_group_index: string_rows.len(),
-                                        column_index: string_row.len() + wide_fret_playback_offset,
-                                    });
-                                }
-                            }
+                        if string_index == 0 {
+                            column_positions.push(ColumnPosition {
+                                row_group_index: string_rows.len(),
+                                column_index: string_row.len(),
+                                width: string_item.len(),
+                            });
+                        }
+                        if string_item.is_empty() {
+                            continue;
                         }
                         string_row.push_str(&string_item)
                     }
@@ -578,7 +1046,7 @@ fn render_string_groups(
         strings_rows.push(string_rows);
     }
 
-    (strings_rows, playback_indicator_position)
+    (strings_rows, column_positions)
 }
 #[cfg(test)]
 mod test_render_string_groups {
@@ -642,7 +1110,6 @@ mod test_render_string_groups {
         let beat_column_renders = get_beat_column_renders();
         let width = 25;
         let padding = 1;
-        let playback_column_index = Some(1);
 
         let expected_string_groups = vec![
             vec!["-0--------|-0--------|---".to_owned()],
@@ -650,37 +1117,26 @@ mod test_render_string_groups {
             vec!["-----2----|-----2----|---".to_owned()],
             vec!["-------30-|-------30-|---".to_owned()],
         ];
-        let expected_playback_indicator_position = Some(PlaybackIndicatorPosition {
-            row_group_index: 0,
-            column_index: 3,
-        });
 
+        let (string_groups, column_positions) =
+            render_string_groups(beat_column_renders, width, padding);
+        assert_eq!(string_groups, expected_string_groups);
+        assert_eq!(column_positions.len(), 10);
         assert_eq!(
-            render_string_groups(beat_column_renders, width, padding, playback_column_index),
-            (expected_string_groups, expected_playback_indicator_position)
+            column_positions[1],
+            ColumnPosition {
+                row_group_index: 0,
+                column_index: 3,
+                width: 1
+            }
         );
-    }
-    #[test]
-    fn single_row_group_playback_second_char_of_wide_fret() {
-        let beat_column_renders = get_beat_column_renders();
-        let width = 25;
-        let padding = 1;
-        let playback_column_index = Some(3);
-
-        let expected_string_groups = vec![
-            vec!["-0--------|-0--------|---".to_owned()],
-            vec!["---1------|---1------|---".to_owned()],
-            vec!["-----2----|-----2----|---".to_owned()],
-            vec!["-------30-|-------30-|---".to_owned()],
-        ];
-        let expected_playback_indicator_position = Some(PlaybackIndicatorPosition {
-            row_group_index: 0,
-            column_index: 8,
-        });
-
         assert_eq!(
-            render_string_groups(beat_column_renders, width, padding, playback_column_index),
-            (expected_string_groups, expected_playback_indicator_position)
+            column_positions[3],
+            ColumnPosition {
+                row_group_index: 0,
+                column_index: 7,
+                width: 2
+            }
         );
     }
     #[test]
@@ -688,7 +1144,6 @@ mod test_render_string_groups {
         let beat_column_renders = get_beat_column_renders();
         let width = 14;
         let padding = 1;
-        let playback_column_index = Some(7);
 
         let expected_string_groups = vec![
             vec!["-0--------|---".to_owned(), "-0--------|---".to_owned()],
@@ -696,34 +1151,470 @@ mod test_render_string_groups {
             vec!["-----2----|---".to_owned(), "-----2----|---".to_owned()],
             vec!["-------30-|---".to_owned(), "-------30-|---".to_owned()],
         ];
-        let expected_playback_indicator_position = Some(PlaybackIndicatorPosition {
+
+        let (string_groups, column_positions) =
+            render_string_groups(beat_column_renders, width, padding);
+        assert_eq!(string_groups, expected_string_groups);
+        assert_eq!(
+            column_positions[7],
+            ColumnPosition {
+                row_group_index: 1,
+                column_index: 5,
+                width: 1
+            }
+        );
+    }
+}
+
+/// Positions the playback indicator over the rendered line, offset by one character if the frets
+/// are two characters wide.
+fn playback_indicator_position(column_position: &ColumnPosition) -> PlaybackIndicatorPosition {
+    let wide_fret_playback_offset = match column_position.width {
+        2 => 1,
+        _ => 0,
+    };
+
+    PlaybackIndicatorPosition {
+        row_group_index: column_position.row_group_index,
+        column_index: column_position.column_index + wide_fret_playback_offset,
+    }
+}
+#[cfg(test)]
+mod test_playback_indicator_position {
+    use super::*;
+
+    #[test]
+    fn narrow_fret() {
+        let column_position = ColumnPosition {
             row_group_index: 1,
             column_index: 5,
-        });
+            width: 1,
+        };
+        assert_eq!(
+            playback_indicator_position(&column_position),
+            PlaybackIndicatorPosition {
+                row_group_index: 1,
+                column_index: 5
+            }
+        );
+    }
+    #[test]
+    fn second_char_of_wide_fret() {
+        let column_position = ColumnPosition {
+            row_group_index: 0,
+            column_index: 7,
+            width: 2,
+        };
+        assert_eq!(
+            playback_indicator_position(&column_position),
+            PlaybackIndicatorPosition {
+                row_group_index: 0,
+                column_index: 8
+            }
+        );
+    }
+}
+
+/// Lays out the sonorous beats from the positions of the rendered lines. Every row group starts
+/// with its label rows, then has a playback indicator row above and below its string rows,
+/// followed by an empty row.
+fn beat_layouts(
+    arrangement_lines: &[Line<BeatVec<PitchFingering>>],
+    column_positions: &[ColumnPosition],
+    num_strings: usize,
+    num_label_rows: usize,
+) -> Vec<BeatLayout> {
+    let row_group_height = num_label_rows + num_strings + 3;
+    let first_string_row = num_label_rows + 1;
+
+    arrangement_lines
+        .iter()
+        .zip(column_positions)
+        .enumerate()
+        .filter(|(_, (line, _))| matches!(line, Line::Playable(_) | Line::Rest))
+        .enumerate()
+        .map(
+            |(beat_index, (line_index, (_, column_position)))| BeatLayout {
+                beat_index: beat_index as u16,
+                line_index,
+                row_group_index: column_position.row_group_index,
+                start_row: column_position.row_group_index * row_group_height + first_string_row,
+                end_row: column_position.row_group_index * row_group_height
+                    + first_string_row
+                    + num_strings,
+                start_column: column_position.column_index,
+                end_column: column_position.column_index + column_position.width,
+            },
+        )
+        .collect_vec()
+}
+
+/// Writes the section names and numbered endings of the lines in a row for every row group, aligned
+/// with the columns of the lines. Labels that would touch the previous label in the row are moved
+/// one space after it. Returns `None` if the lines have no sections or numbered endings.
+fn render_section_rows(
+    arrangement_lines: &[Line<BeatVec<PitchFingering>>],
+    column_positions: &[ColumnPosition],
+    num_row_groups: usize,
+) -> Option<Vec<String>> {
+    let labels = arrangement_lines
+        .iter()
+        .zip(column_positions)
+        .filter_map(|(line, column_position)| match line {
+            Line::Section(name) => Some((name.clone(), column_position)),
+            Line::Ending(ending_number) => Some((format!("{ending_number}."), column_position)),
+            _ => None,
+        })
+        .collect_vec();
+    if labels.is_empty() {
+        return None;
+    }
+
+    let mut section_rows = vec![String::new(); num_row_groups];
+    for (label, column_position) in labels {
+        let section_row = &mut section_rows[column_position.row_group_index];
+        let indent = match section_row.is_empty() {
+            true => column_position.column_index,
+            false => column_position
+                .column_index
+                .saturating_sub(section_row.len())
+                .max(1),
+        };
+        section_row.push_str(&" ".repeat(indent));
+        section_row.push_str(&label);
+    }
 
+    Some(section_rows)
+}
+#[cfg(test)]
+mod test_render_section_rows {
+    use super::*;
+
+    fn column_position(row_group_index: usize, column_index: usize) -> ColumnPosition {
+        ColumnPosition {
+            row_group_index,
+            column_index,
+            width: 0,
+        }
+    }
+
+    #[test]
+    fn sections_and_endings() {
+        let lines = vec![
+            Line::Section("Verse".to_owned()),
+            Line::Rest,
+            Line::Ending(1),
+            Line::Ending(2),
+            Line::Section("Chorus".to_owned()),
+        ];
+        let column_positions = vec![
+            column_position(0, 1),
+            column_position(0, 1),
+            column_position(0, 10),
+            column_position(0, 11),
+            column_position(1, 1),
+        ];
         assert_eq!(
-            render_string_groups(beat_column_renders, width, padding, playback_column_index),
-            (expected_string_groups, expected_playback_indicator_position)
+            render_section_rows(&lines, &column_positions, 2),
+            Some(vec![" Verse    1. 2.".to_owned(), " Chorus".to_owned()])
         );
     }
     #[test]
-    fn no_playback_column_index() {
-        let (_, playback_indicator_position) =
-            render_string_groups(get_beat_column_renders(), 20, 1, None);
+    fn no_sections() {
+        assert_eq!(
+            render_section_rows(&[Line::Rest], &[column_position(0, 1)], 1),
+            None
+        );
+    }
+}
 
-        assert_eq!(playback_indicator_position, None);
+/// Writes the labels of the lines (e.g. chord names) in a row for every row group, aligned with the
+/// columns of the lines. A label is only written when it differs from the previously written label,
+/// and labels that would touch the previous label in the row are moved one space after it.
+fn render_changed_label_rows(
+    labels: &[Option<String>],
+    column_positions: &[ColumnPosition],
+    num_row_groups: usize,
+) -> Vec<String> {
+    let mut label_rows = vec![String::new(); num_row_groups];
+    let mut previous_label: Option<&String> = None;
+
+    for (label, column_position) in labels.iter().zip(column_positions) {
+        let Some(label) = label else {
+            continue;
+        };
+        if previous_label == Some(label) {
+            continue;
+        }
+
+        let label_row = &mut label_rows[column_position.row_group_index];
+        let indent = match label_row.is_empty() {
+            true => column_position.column_index,
+            false => column_position
+                .column_index
+                .saturating_sub(label_row.len())
+                .max(1),
+        };
+        label_row.push_str(&" ".repeat(indent));
+        label_row.push_str(label);
+        previous_label = Some(label);
     }
+
+    label_rows
+}
+#[cfg(test)]
+mod test_render_changed_label_rows {
+    use super::*;
+
+    fn column_position(row_group_index: usize, column_index: usize) -> ColumnPosition {
+        ColumnPosition {
+            row_group_index,
+            column_index,
+            width: 1,
+        }
+    }
+
     #[test]
-    fn too_large_playback_column_index() {
-        let (_, playback_indicator_position) =
-            render_string_groups(get_beat_column_renders(), 20, 1, Some(100_000));
+    fn changed_chords_only() {
+        let chord_names = vec![
+            Some("C".to_owned()),
+            Some("C".to_owned()),
+            None,
+            Some("G/B".to_owned()),
+            Some("C".to_owned()),
+        ];
+        let column_positions = vec![
+            column_position(0, 1),
+            column_position(0, 3),
+            column_position(0, 5),
+            column_position(0, 7),
+            column_position(1, 1),
+        ];
+        assert_eq!(
+            render_changed_label_rows(&chord_names, &column_positions, 2),
+            vec![" C     G/B".to_owned(), " C".to_owned()]
+        );
+    }
+    #[test]
+    fn overlapping_names() {
+        let chord_names = vec![
+            Some("Cmaj7".to_owned()),
+            Some("Am".to_owned()),
+            Some("F".to_owned()),
+        ];
+        let column_positions = vec![
+            column_position(0, 1),
+            column_position(0, 3),
+            column_position(0, 7),
+        ];
+        assert_eq!(
+            render_changed_label_rows(&chord_names, &column_positions, 1),
+            vec![" Cmaj7 Am F".to_owned()]
+        );
+    }
+}
+
+/// Writes a marker in Roman numerals (e.g. `Pos. V`) above the lines where the fretting hand moves
+/// to a new position, in a row for every row group.
+fn render_hand_position_rows(
+    hand_positions: &[Option<u8>],
+    column_positions: &[ColumnPosition],
+    num_row_groups: usize,
+) -> Vec<String> {
+    let position_markers = hand_positions
+        .iter()
+        .map(|hand_position| {
+            hand_position.map(|hand_position| format!("Pos. {}", roman_numeral(hand_position)))
+        })
+        .collect_vec();
+    render_changed_label_rows(&position_markers, column_positions, num_row_groups)
+}
+#[cfg(test)]
+mod test_render_hand_position_rows {
+    use super::*;
+
+    #[test]
+    fn shifts() {
+        let hand_positions = vec![None, Some(5), Some(5), None, Some(7), Some(7)];
+        let column_positions = (0..6)
+            .map(|line_index| ColumnPosition {
+                row_group_index: 0,
+                column_index: 1 + line_index * 4,
+                width: 1,
+            })
+            .collect_vec();
+        assert_eq!(
+            render_hand_position_rows(&hand_positions, &column_positions, 1),
+            vec!["     Pos. V      Pos. VII".to_owned()]
+        );
+    }
+}
 
-        assert_eq!(playback_indicator_position, None);
+/// Writes a number from 1 to 39 in Roman numerals.
+fn roman_numeral(number: u8) -> String {
+    const NUMERALS: [(u8, &str); 5] = [(10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+
+    let mut remainder = number;
+    let mut numeral = String::new();
+    for (value, symbol) in NUMERALS {
+        while remainder >= value {
+            numeral.push_str(symbol);
+            remainder -= value;
+        }
     }
+    numeral
 }
+#[cfg(test)]
+mod test_roman_numeral {
+    use super::*;
 
+    #[test]
+    fn simple() {
+        assert_eq!(roman_numeral(1), "I");
+        assert_eq!(roman_numeral(4), "IV");
+        assert_eq!(roman_numeral(5), "V");
+        assert_eq!(roman_numeral(9), "IX");
+        assert_eq!(roman_numeral(12), "XII");
+        assert_eq!(roman_numeral(19), "XIX");
+        assert_eq!(roman_numeral(24), "XXIV");
+    }
+}
+
+/// Renders a row for every row group with the picking fingers of the pitches of every line written
+/// above its column, from the thumb to the ring finger (e.g. `pim`). Fingers that would overlap the
+/// fingers of the previous line are left out.
+fn render_picking_rows(
+    picking_fingers: &[BeatVec<PickingFinger>],
+    column_positions: &[ColumnPosition],
+    num_row_groups: usize,
+) -> Vec<String> {
+    let mut picking_rows = vec![String::new(); num_row_groups];
+
+    for (fingers, column_position) in picking_fingers.iter().zip(column_positions) {
+        let picking_row = &mut picking_rows[column_position.row_group_index];
+        if fingers.is_empty()
+            || (!picking_row.is_empty() && picking_row.len() >= column_position.column_index)
+        {
+            continue;
+        }
+
+        let indent = column_position.column_index - picking_row.len();
+        picking_row.push_str(&" ".repeat(indent));
+        picking_row.push_str(&fingers.iter().sorted().join(""));
+    }
+
+    picking_rows
+}
+#[cfg(test)]
+mod test_render_picking_rows {
+    use super::*;
+    use PickingFinger::{Index, Middle, Ring, Thumb};
+
+    fn column_position(row_group_index: usize, column_index: usize) -> ColumnPosition {
+        ColumnPosition {
+            row_group_index,
+            column_index,
+            width: 1,
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let picking_fingers = vec![vec![Thumb], vec![], vec![Middle, Thumb, Index], vec![Ring]];
+        let column_positions = vec![
+            column_position(0, 1),
+            column_position(0, 3),
+            column_position(0, 5),
+            column_position(1, 1),
+        ];
+        assert_eq!(
+            render_picking_rows(&picking_fingers, &column_positions, 2),
+            vec![" p   pim".to_owned(), " a".to_owned()]
+        );
+    }
+    #[test]
+    fn overlapping_fingers() {
+        let picking_fingers = vec![vec![Thumb, Index, Middle], vec![Ring], vec![Index]];
+        let column_positions = vec![
+            column_position(0, 1),
+            column_position(0, 3),
+            column_position(0, 5),
+        ];
+        assert_eq!(
+            render_picking_rows(&picking_fingers, &column_positions, 1),
+            vec![" pim i".to_owned()]
+        );
+    }
+}
+
+/// Writes the pick direction markers of the lines in a row for every row group, above the columns
+/// of the lines.
+fn render_pick_direction_rows(
+    pick_directions: &[Option<PickDirection>],
+    column_positions: &[ColumnPosition],
+    num_row_groups: usize,
+) -> Vec<String> {
+    let mut pick_direction_rows = vec![String::new(); num_row_groups];
+
+    for (pick_direction, column_position) in pick_directions.iter().zip(column_positions) {
+        let Some(pick_direction) = pick_direction else {
+            continue;
+        };
+        let pick_direction_row = &mut pick_direction_rows[column_position.row_group_index];
+        // The downstroke marker is wider than a byte, so the row is measured in characters
+        let indent = column_position.column_index - pick_direction_row.chars().count();
+        pick_direction_row.push_str(&" ".repeat(indent));
+        pick_direction_row.push_str(&pick_direction.to_string());
+    }
+
+    pick_direction_rows
+}
+#[cfg(test)]
+mod test_render_pick_direction_rows {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let pick_directions = vec![
+            Some(PickDirection::Down),
+            Some(PickDirection::Up),
+            None,
+            Some(PickDirection::Down),
+        ];
+        let column_positions = vec![
+            ColumnPosition {
+                row_group_index: 0,
+                column_index: 1,
+                width: 1,
+            },
+            ColumnPosition {
+                row_group_index: 0,
+                column_index: 3,
+                width: 1,
+            },
+            ColumnPosition {
+                row_group_index: 0,
+                column_index: 5,
+                width: 1,
+            },
+            ColumnPosition {
+                row_group_index: 1,
+                column_index: 1,
+                width: 1,
+            },
+        ];
+        assert_eq!(
+            render_pick_direction_rows(&pick_directions, &column_positions, 2),
+            vec![" ⊓ V".to_owned(), " ⊓".to_owned()]
+        );
+    }
+}
+
+/// Writes the row groups of the tab, starting every row group with its row of every set of label
+/// rows (e.g. the chord rows).
 fn render_string_output(
     strings_rows: &[Vec<String>],
+    label_rows: &[Vec<String>],
     playback_indicator_position: Option<PlaybackIndicatorPosition>,
 ) -> String {
     let mut output_lines: Vec<String> = vec![];
@@ -731,6 +1622,9 @@ fn render_string_output(
     let num_row_groups = strings_rows[0].len();
 
     for row_group_index in 0..num_row_groups {
+        for rows in label_rows {
+            output_lines.push(rows[row_group_index].clone());
+        }
         let upper_playback_row_render = match playback_indicator_position {
             None => "".to_owned(),
             Some(ref pos) => match row_group_index == pos.row_group_index {
@@ -790,7 +1684,7 @@ mod test_render_string_output {
         .to_owned();
 
         assert_eq!(
-            render_string_output(&string_rows, playback_indicator_position),
+            render_string_output(&string_rows, &[], playback_indicator_position),
             expected_output
         );
     }
@@ -804,7 +1698,7 @@ mod test_render_string_output {
         ];
         let playback_indicator_position = None;
 
-        let output = render_string_output(&string_rows, playback_indicator_position);
+        let output = render_string_output(&string_rows, &[], playback_indicator_position);
 
         let expected_output = concat!(
             "\n",
@@ -834,7 +1728,7 @@ mod test_render_string_output {
             column_index: 8,
         });
 
-        let output = render_string_output(&string_rows, playback_indicator_position);
+        let output = render_string_output(&string_rows, &[], playback_indicator_position);
 
         let expected_output = concat!(
             "\n",
@@ -0,0 +1,53 @@
+use crate::{
+    arrangement::{Arrangement, BeatVec, Line},
+    guitar::Guitar,
+    pitch::Pitch,
+    project::Project,
+};
+use schemars::{gen::SchemaSettings, schema::RootSchema, JsonSchema};
+
+fn root_schema<T: JsonSchema>() -> RootSchema {
+    SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// Generates the JSON schemas of the serializable library types, paired with the file names
+/// under which they are published in the `schema` directory.
+pub fn json_schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("guitar.schema.json", root_schema::<Guitar>()),
+        ("arrangement.schema.json", root_schema::<Arrangement>()),
+        (
+            "input_lines.schema.json",
+            root_schema::<Vec<Line<BeatVec<Pitch>>>>(),
+        ),
+        ("project.schema.json", root_schema::<Project>()),
+    ]
+}
+#[cfg(test)]
+mod test_json_schemas {
+    use super::*;
+
+    fn published_schema(file_name: &str) -> &'static str {
+        match file_name {
+            "guitar.schema.json" => include_str!("../schema/guitar.schema.json"),
+            "arrangement.schema.json" => include_str!("../schema/arrangement.schema.json"),
+            "input_lines.schema.json" => include_str!("../schema/input_lines.schema.json"),
+            "project.schema.json" => include_str!("../schema/project.schema.json"),
+            _ => unreachable!("Unknown schema file name."),
+        }
+    }
+
+    #[test]
+    fn published_schemas_are_current() {
+        for (file_name, schema) in json_schemas() {
+            let generated_schema = serde_json::to_string_pretty(&schema).unwrap() + "\n";
+            assert_eq!(
+                generated_schema,
+                published_schema(file_name),
+                "The published {file_name} is outdated. Regenerate it with `cargo run --example generate_schemas`."
+            );
+        }
+    }
+}
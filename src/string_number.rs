@@ -1,22 +1,86 @@
-use anyhow::{anyhow, Result};
+use crate::error::{Error, Result};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, NumberValidation, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+const MAX_NUM_STRINGS: u8 = 12;
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
 pub struct StringNumber(u8);
 impl StringNumber {
     pub fn new(string_number: u8) -> Result<Self> {
-        const MAX_NUM_STRINGS: u8 = 12;
         match string_number {
-            0 => Err(anyhow!("A guitar cannot have a string number of zero (0). Guitar string numbering commences at one (1).")),
+            0 => Err(Error::StringNumberZero),
             1..=MAX_NUM_STRINGS => Ok(StringNumber(string_number)),
-            _ => Err(anyhow!("The string number ({string_number}) is too high. The maximum is {MAX_NUM_STRINGS}."))
-
+            _ => Err(Error::StringNumberTooHigh {
+                string_number,
+                max: MAX_NUM_STRINGS,
+            }),
         }
     }
     pub fn get(&self) -> u8 {
         self.0
     }
 }
+
+impl TryFrom<u8> for StringNumber {
+    type Error = Error;
+
+    fn try_from(string_number: u8) -> Result<Self> {
+        StringNumber::new(string_number)
+    }
+}
+impl From<StringNumber> for u8 {
+    fn from(string_number: StringNumber) -> u8 {
+        string_number.get()
+    }
+}
+#[cfg(test)]
+mod test_string_number_serde {
+    use super::*;
+
+    #[test]
+    fn serialize() {
+        let string_number = StringNumber::new(3).unwrap();
+        assert_eq!(serde_json::to_string(&string_number).unwrap(), "3");
+    }
+    #[test]
+    fn deserialize() {
+        let string_number: StringNumber = serde_json::from_str("6").unwrap();
+        assert_eq!(string_number, StringNumber::new(6).unwrap());
+    }
+    #[test]
+    fn deserialize_invalid() {
+        let error = serde_json::from_str::<StringNumber>("0").unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "A guitar cannot have a string number of zero (0). Guitar string numbering commences at one (1)."
+        );
+    }
+}
+impl JsonSchema for StringNumber {
+    fn schema_name() -> String {
+        "StringNumber".to_owned()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::Integer.into()),
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(1.0),
+                maximum: Some(MAX_NUM_STRINGS as f64),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
 #[cfg(test)]
 mod test_create_string_number {
     use super::*;
@@ -0,0 +1,119 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Largest bend in semitones.
+const MAX_BEND_SEMITONES: u8 = 4;
+
+/// A left-hand technique played on a note, written after the pitch in the input (e.g. `E4h`).
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub enum Technique {
+    /// Hammer-on to a higher note on the same string on the next beat (`h`).
+    HammerOn,
+    /// Pull-off to a lower note on the same string on the next beat (`p`).
+    PullOff,
+    /// Slide to another note on the same string on the next beat (`/`).
+    Slide,
+    /// Bend up by a number of semitones (e.g. `^2`).
+    Bend(u8),
+    /// Vibrato (`~`).
+    Vibrato,
+}
+impl Technique {
+    /// Parses a technique marker (`h`, `p`, `/`, `~` or `^` followed by the number of semitones of
+    /// the bend, from 1 to 4).
+    pub fn parse(marker: &str) -> Option<Technique> {
+        match marker {
+            "h" => Some(Technique::HammerOn),
+            "p" => Some(Technique::PullOff),
+            "/" => Some(Technique::Slide),
+            "~" => Some(Technique::Vibrato),
+            _ => marker
+                .strip_prefix('^')?
+                .parse::<u8>()
+                .ok()
+                .filter(|semitones| (1..=MAX_BEND_SEMITONES).contains(semitones))
+                .map(Technique::Bend),
+        }
+    }
+
+    /// Whether the technique connects the note to a note on the same string on the next beat.
+    pub fn is_legato(&self) -> bool {
+        matches!(
+            self,
+            Technique::HammerOn | Technique::PullOff | Technique::Slide
+        )
+    }
+
+    /// Whether a legato technique can connect a note at the fret to a note at the next fret on the
+    /// same string. Always false for techniques that are not legato.
+    pub fn connects(&self, fret: u8, next_fret: u8) -> bool {
+        match self {
+            Technique::HammerOn => next_fret > fret,
+            Technique::PullOff => next_fret < fret,
+            Technique::Slide => next_fret != fret,
+            Technique::Bend(_) | Technique::Vibrato => false,
+        }
+    }
+
+    /// Renders the tab symbol of the technique written after the fret of its note, which is the
+    /// fret reached for bends (e.g. `h` or `b9`).
+    pub fn render(&self, fret: u8) -> String {
+        match self {
+            Technique::HammerOn => "h".to_owned(),
+            Technique::PullOff => "p".to_owned(),
+            Technique::Slide => "/".to_owned(),
+            Technique::Bend(semitones) => format!("b{}", fret + semitones),
+            Technique::Vibrato => "~".to_owned(),
+        }
+    }
+}
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Technique::HammerOn => write!(f, "hammer-on"),
+            Technique::PullOff => write!(f, "pull-off"),
+            Technique::Slide => write!(f, "slide"),
+            Technique::Bend(semitones) => write!(f, "bend of {semitones} semitones"),
+            Technique::Vibrato => write!(f, "vibrato"),
+        }
+    }
+}
+#[cfg(test)]
+mod test_technique {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Technique::parse("h"), Some(Technique::HammerOn));
+        assert_eq!(Technique::parse("p"), Some(Technique::PullOff));
+        assert_eq!(Technique::parse("/"), Some(Technique::Slide));
+        assert_eq!(Technique::parse("^2"), Some(Technique::Bend(2)));
+        assert_eq!(Technique::parse("~"), Some(Technique::Vibrato));
+    }
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(Technique::parse("H"), None);
+        assert_eq!(Technique::parse("^"), None);
+        assert_eq!(Technique::parse("^0"), None);
+        assert_eq!(Technique::parse("^5"), None);
+        assert_eq!(Technique::parse("b2"), None);
+    }
+    #[test]
+    fn connects() {
+        assert!(Technique::HammerOn.connects(5, 7));
+        assert!(!Technique::HammerOn.connects(7, 5));
+        assert!(Technique::PullOff.connects(7, 0));
+        assert!(Technique::Slide.connects(7, 5));
+        assert!(!Technique::Slide.connects(5, 5));
+        assert!(!Technique::Vibrato.connects(5, 7));
+    }
+    #[test]
+    fn render() {
+        assert_eq!(Technique::HammerOn.render(5), "h");
+        assert_eq!(Technique::Bend(2).render(7), "b9");
+        assert_eq!(Technique::Vibrato.render(7), "~");
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    arrangement::{BeatVec, Line},
+    chord::pitch_class,
+    error::{Error, Result},
+    guitar::Guitar,
+    pitch::Pitch,
+};
+use itertools::Itertools;
+
+/// Parses a transposition into a number of semitones.
+///
+/// A transposition is a signed number of semitones (e.g. `+2` or `-3`), an optionally signed
+/// interval (e.g. `M2`, `-P5` or `+m3`) or a change of key (e.g. `G to A` or `from Em to F#m`).
+/// A change of key moves by the smallest interval, so `G to A` is up two semitones and `A to G`
+/// is down two semitones.
+pub fn parse_transposition(transposition: &str) -> Option<i16> {
+    let transposition = transposition.trim();
+
+    if let Ok(semitones) = transposition.parse::<i16>() {
+        return Some(semitones);
+    }
+    if let Some(semitones) = parse_interval(transposition) {
+        return Some(semitones);
+    }
+
+    let transposition = transposition.strip_prefix("from ").unwrap_or(transposition);
+    let (from_key, to_key) = transposition.split_once(" to ")?;
+    let from_pitch_class = key_tonic(from_key.trim())? as i16;
+    let to_pitch_class = key_tonic(to_key.trim())? as i16;
+    match (to_pitch_class - from_pitch_class).rem_euclid(12) {
+        semitones @ 0..=6 => Some(semitones),
+        semitones => Some(semitones - 12),
+    }
+}
+#[cfg(test)]
+mod test_parse_transposition {
+    use super::*;
+
+    #[test]
+    fn semitones() {
+        assert_eq!(parse_transposition("+2"), Some(2));
+        assert_eq!(parse_transposition(" -3 "), Some(-3));
+        assert_eq!(parse_transposition("0"), Some(0));
+    }
+    #[test]
+    fn intervals() {
+        assert_eq!(parse_transposition("M2"), Some(2));
+        assert_eq!(parse_transposition("-P5"), Some(-7));
+        assert_eq!(parse_transposition("+m3"), Some(3));
+    }
+    #[test]
+    fn keys() {
+        assert_eq!(parse_transposition("G to A"), Some(2));
+        assert_eq!(parse_transposition("from A to G"), Some(-2));
+        assert_eq!(parse_transposition("from Em to F#m"), Some(2));
+        assert_eq!(parse_transposition("C to F#"), Some(6));
+        assert_eq!(parse_transposition("Bb to E"), Some(6));
+    }
+    #[test]
+    fn invalid() {
+        assert_eq!(parse_transposition(""), None);
+        assert_eq!(parse_transposition("up"), None);
+        assert_eq!(parse_transposition("X2"), None);
+        assert_eq!(parse_transposition("G to H"), None);
+    }
+}
+
+/// Converts an optionally signed interval name (e.g. `m3`, `+P4` or `-M6`) into semitones.
+fn parse_interval(interval: &str) -> Option<i16> {
+    let (sign, interval) = match interval.chars().next()? {
+        '+' => (1, &interval[1..]),
+        '-' => (-1, &interval[1..]),
+        _ => (1, interval),
+    };
+    let semitones = match interval {
+        "P1" => 0,
+        "m2" => 1,
+        "M2" => 2,
+        "m3" => 3,
+        "M3" => 4,
+        "P4" => 5,
+        "A4" | "d5" => 6,
+        "P5" => 7,
+        "m6" => 8,
+        "M6" => 9,
+        "m7" => 10,
+        "M7" => 11,
+        "P8" => 12,
+        _ => return None,
+    };
+    Some(sign * semitones)
+}
+
+/// Returns the pitch class of the tonic of a key name, which is a note name optionally followed
+/// by `m` for minor keys (e.g. `F#` or `Em`).
+fn key_tonic(key_name: &str) -> Option<u8> {
+    pitch_class(key_name).or_else(|| pitch_class(key_name.strip_suffix('m')?))
+}
+
+/// Transposes the pitches of every playable line by a number of semitones.
+///
+/// If a guitar is given, the transposed pitches must also be within the range of the guitar.
+/// The error lists every pitch that could not be transposed, numbering the lines from one.
+pub fn transpose_lines(
+    lines: &[Line<BeatVec<Pitch>>],
+    semitones: i16,
+    guitar: Option<&Guitar>,
+) -> Result<Vec<Line<BeatVec<Pitch>>>> {
+    let transposed_lines = lines
+        .iter()
+        .enumerate()
+        .map(|(line_index, line)| match line {
+            Line::Playable(pitches) => {
+                transpose_pitches(pitches, semitones, line_index + 1, guitar).map(Line::Playable)
+            }
+            line => Ok(line.clone()),
+        })
+        .collect_vec();
+
+    let errors = transposed_lines
+        .iter()
+        .filter_map(|line| line.as_ref().err())
+        .flat_map(|error| match error {
+            Error::Multiple { errors } => errors.clone(),
+            error => vec![error.clone()],
+        })
+        .collect_vec();
+    match Error::from_errors(errors) {
+        Some(error) => Err(error),
+        None => Ok(transposed_lines.into_iter().flatten().collect_vec()),
+    }
+}
+#[cfg(test)]
+mod test_transpose_lines {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let lines = vec![
+            Line::Playable(vec![Pitch::G2, Pitch::B3]),
+            Line::Rest,
+            Line::MeasureBreak,
+            Line::Playable(vec![Pitch::D3]),
+        ];
+        assert_eq!(
+            transpose_lines(&lines, 2, None).unwrap(),
+            vec![
+                Line::Playable(vec![Pitch::A2, Pitch::CSharpDFlat4]),
+                Line::Rest,
+                Line::MeasureBreak,
+                Line::Playable(vec![Pitch::E3]),
+            ]
+        );
+    }
+    #[test]
+    fn out_of_range() {
+        let lines = vec![
+            Line::Playable(vec![Pitch::E2]),
+            Line::Playable(vec![Pitch::ASharpBFlat9, Pitch::B9]),
+        ];
+        assert_eq!(
+            transpose_lines(&lines, 2, None).unwrap_err(),
+            Error::Multiple {
+                errors: vec![
+                    Error::TransposedPitchOutOfRange {
+                        pitch: Pitch::ASharpBFlat9,
+                        semitones: 2,
+                        line_number: 2
+                    },
+                    Error::TransposedPitchOutOfRange {
+                        pitch: Pitch::B9,
+                        semitones: 2,
+                        line_number: 2
+                    },
+                ]
+            }
+        );
+    }
+    #[test]
+    fn out_of_guitar_range() {
+        let lines = vec![
+            Line::Playable(vec![Pitch::E2]),
+            Line::Playable(vec![Pitch::G2]),
+        ];
+        assert_eq!(
+            transpose_lines(&lines, -2, Some(&Guitar::default())).unwrap_err(),
+            Error::TransposedPitchOutOfGuitarRange {
+                pitch: Pitch::E2,
+                transposed_pitch: Pitch::D2,
+                line_number: 1
+            }
+        );
+    }
+}
+
+/// Transposes the pitches of an input line by a number of semitones, checking that they stay
+/// within the range of `Pitch` and, if a guitar is given, that the guitar can play them as
+/// fretted notes or as the harmonics it considers.
+pub(crate) fn transpose_pitches(
+    pitches: &[Pitch],
+    semitones: i16,
+    line_number: usize,
+    guitar: Option<&Guitar>,
+) -> Result<Vec<Pitch>> {
+    let playable_pitches = guitar.map(Guitar::playable_pitches);
+    let transposed_pitches = pitches
+        .iter()
+        .map(|&pitch| {
+            let transposed_pitch =
+                pitch
+                    .plus_offset(semitones)
+                    .map_err(|_| Error::TransposedPitchOutOfRange {
+                        pitch,
+                        semitones,
+                        line_number,
+                    })?;
+            match &playable_pitches {
+                Some(playable_pitches) if !playable_pitches.contains(&transposed_pitch) => {
+                    Err(Error::TransposedPitchOutOfGuitarRange {
+                        pitch,
+                        transposed_pitch,
+                        line_number,
+                    })
+                }
+                _ => Ok(transposed_pitch),
+            }
+        })
+        .collect_vec();
+
+    let errors = transposed_pitches
+        .iter()
+        .filter_map(|pitch| pitch.as_ref().err().cloned())
+        .collect_vec();
+    match Error::from_errors(errors) {
+        Some(error) => Err(error),
+        None => Ok(transposed_pitches.into_iter().flatten().collect_vec()),
+    }
+}
+#[cfg(test)]
+mod test_transpose_pitches {
+    use super::*;
+    use crate::guitar::HarmonicCandidates;
+
+    #[test]
+    fn simple() {
+        assert_eq!(
+            transpose_pitches(&[Pitch::E2, Pitch::A2], -1, 1, None).unwrap(),
+            vec![Pitch::DSharpEFlat2, Pitch::GSharpAFlat2]
+        );
+    }
+    #[test]
+    fn harmonics() {
+        let guitar = Guitar::default().with_harmonics(HarmonicCandidates::Natural);
+        assert_eq!(
+            transpose_pitches(&[Pitch::A5], 2, 1, Some(&guitar)).unwrap(),
+            vec![Pitch::B5]
+        );
+        assert_eq!(
+            transpose_pitches(&[Pitch::A5], 2, 1, Some(&Guitar::default())).unwrap_err(),
+            Error::TransposedPitchOutOfGuitarRange {
+                pitch: Pitch::A5,
+                transposed_pitch: Pitch::B5,
+                line_number: 1
+            }
+        );
+    }
+    #[test]
+    fn out_of_range() {
+        assert_eq!(
+            transpose_pitches(&[Pitch::C0, Pitch::E2], -1, 4, None).unwrap_err(),
+            Error::TransposedPitchOutOfRange {
+                pitch: Pitch::C0,
+                semitones: -1,
+                line_number: 4
+            }
+        );
+    }
+}
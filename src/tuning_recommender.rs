@@ -0,0 +1,543 @@
+use crate::{
+    arrangement::{create_arrangements, validate_num_arrangements, Arrangement, BeatVec, Line},
+    create_input_guitar,
+    error::{Error, Result},
+    guitar::{Guitar, STD_6_STRING_TUNING_OPEN_PITCHES},
+    parser::{
+        create_string_tuning_offset, parse_input_in_mode, parse_tuning, InputMode, TuningName,
+    },
+    pitch::Pitch,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use strum::VariantNames;
+use wasm_bindgen::prelude::*;
+
+/// Difficulty bonus for every beat whose bass pitch can be played on an open string.
+const BASS_OPEN_STRING_BONUS: i32 = 10;
+
+/// Number of the most frequent bass pitch classes used as roots of generated open tunings.
+const NUM_GENERATED_OPEN_ROOTS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TuningCandidate {
+    pub name: String,
+    pub offsets: [i8; 6],
+    pub generated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TuningRecommendation {
+    pub tuning: TuningCandidate,
+    pub guitar: Guitar,
+    pub arrangements: Vec<Arrangement>,
+    pub difficulty: i32,
+    pub open_bass_beats: u16,
+    pub score: i32,
+}
+
+/// The settings of a tuning recommendation request from the web app. A `@tuning` directive at the
+/// start of the input is ignored because every candidate tuning is tried.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuningRecommendationInput {
+    pub pitches: String,
+    pub guitar_num_frets: u8,
+    pub guitar_capo: u8,
+    pub num_recommendations: u8,
+    pub num_arrangements: u8,
+    pub open_string_cost: u16,
+    #[serde(default)]
+    pub include_generated: bool,
+    #[serde(default)]
+    pub input_mode: InputMode,
+}
+
+#[wasm_bindgen]
+#[cfg(not(tarpaulin_include))]
+pub fn wasm_recommend_tunings(input: JsValue) -> Result<JsValue, JsValue> {
+    let recommendation_input: TuningRecommendationInput = serde_wasm_bindgen::from_value(input)?;
+
+    let recommendations = wrapper_recommend_tunings(recommendation_input)?;
+
+    Ok(serde_wasm_bindgen::to_value(&recommendations)?)
+}
+
+/// Parses the input text and recommends tunings for it (see `recommend_tunings`).
+///
+/// The input is parsed on a guitar in standard tuning with the capo and number of frets of the
+/// request, or of the directives at the start of the input.
+pub fn wrapper_recommend_tunings(
+    recommendation_input: TuningRecommendationInput,
+) -> Result<Vec<TuningRecommendation>> {
+    let TuningRecommendationInput {
+        pitches: input_pitches,
+        guitar_num_frets,
+        guitar_capo,
+        num_recommendations,
+        num_arrangements,
+        open_string_cost,
+        include_generated,
+        input_mode,
+    } = recommendation_input;
+
+    let guitar = create_input_guitar(
+        &input_pitches,
+        "standard",
+        guitar_num_frets,
+        guitar_capo,
+        None,
+    )?;
+    let parsed_input = parse_input_in_mode(input_pitches, input_mode, &guitar)?;
+
+    recommend_tunings(
+        parsed_input.lines.clone(),
+        guitar.num_frets + guitar.capo,
+        guitar.capo,
+        num_recommendations,
+        num_arrangements,
+        open_string_cost,
+        include_generated,
+    )
+    .map_err(|error| parsed_input.restore_input_line_numbers(error))
+}
+#[cfg(test)]
+mod test_wrapper_recommend_tunings {
+    use super::*;
+
+    fn recommendation_input(pitches: &str) -> TuningRecommendationInput {
+        TuningRecommendationInput {
+            pitches: pitches.to_owned(),
+            guitar_num_frets: 18,
+            guitar_capo: 0,
+            num_recommendations: 3,
+            num_arrangements: 1,
+            open_string_cost: 0,
+            include_generated: false,
+            input_mode: InputMode::Absolute,
+        }
+    }
+
+    #[test]
+    fn valid_input() {
+        let recommendations =
+            wrapper_recommend_tunings(recommendation_input("D2F#3\nA2A3\nD2D4")).unwrap();
+
+        assert_eq!(recommendations.len(), 3);
+        assert_eq!(recommendations[0].tuning.offsets[5], -2);
+    }
+    #[test]
+    fn header_capo() {
+        let recommendations =
+            wrapper_recommend_tunings(recommendation_input("@capo 2\n@tuning openg\nE2")).unwrap();
+
+        assert!(recommendations
+            .iter()
+            .all(|recommendation| recommendation.guitar.capo == 2));
+    }
+    #[test]
+    fn invalid_input() {
+        assert!(wrapper_recommend_tunings(recommendation_input("E2\nE2xyz")).is_err());
+    }
+}
+
+/// Searches the known tunings (and optionally generated drop and open variants) for those that
+/// make the input lines the least difficult to play.
+///
+/// Each tuning is scored by the difficulty of its best arrangement, reduced by a bonus for every
+/// beat whose bass pitch rings on an open string. Tunings that cannot play the input are skipped.
+/// The recommendations are returned from the lowest (best) score to the highest.
+pub fn recommend_tunings(
+    input_lines: Vec<Line<BeatVec<Pitch>>>,
+    guitar_num_frets: u8,
+    guitar_capo: u8,
+    num_recommendations: u8,
+    num_arrangements: u8,
+    open_string_cost: u16,
+    include_generated: bool,
+) -> Result<Vec<TuningRecommendation>> {
+    if num_recommendations == 0 {
+        return Err(Error::NoTuningRecommendationsRequested);
+    }
+    validate_num_arrangements(num_arrangements)?;
+
+    let bass_pitches = bass_pitches(&input_lines);
+    let candidates = candidate_tunings(&bass_pitches, include_generated);
+
+    let mut recommendations: Vec<TuningRecommendation> = vec![];
+    for tuning in candidates {
+        let guitar = Guitar::new(
+            create_string_tuning_offset(tuning.offsets),
+            guitar_num_frets,
+            guitar_capo,
+        )?;
+        let arrangements = match create_arrangements(
+            guitar.clone(),
+            input_lines.clone(),
+            num_arrangements,
+            open_string_cost,
+        ) {
+            Ok(arrangements) => arrangements,
+            Err(error) if is_unplayable_error(&error) => continue,
+            Err(error) => return Err(error),
+        };
+
+        let difficulty = arrangements
+            .first()
+            .map(|arrangement| arrangement.difficulty())
+            .unwrap_or(0);
+        let open_bass_beats = count_open_bass_beats(&guitar, &bass_pitches);
+
+        recommendations.push(TuningRecommendation {
+            tuning,
+            guitar,
+            arrangements,
+            difficulty,
+            open_bass_beats,
+            score: difficulty - BASS_OPEN_STRING_BONUS * open_bass_beats as i32,
+        });
+    }
+
+    if recommendations.is_empty() {
+        return Err(Error::NoPlayableTuning);
+    }
+
+    Ok(recommendations
+        .into_iter()
+        .sorted_by_key(|recommendation| recommendation.score)
+        .take(num_recommendations as usize)
+        .collect_vec())
+}
+#[cfg(test)]
+mod test_recommend_tunings {
+    use super::*;
+    use crate::parser::parse_lines;
+
+    #[test]
+    fn open_d_bass_favors_d_tunings() {
+        let input_lines = parse_lines("D2F#3\nA2A3\nD2D4\nA2F#4\nD2A3".to_owned()).unwrap();
+
+        let recommendations = recommend_tunings(input_lines, 18, 0, 3, 1, 0, false).unwrap();
+
+        assert_eq!(recommendations.len(), 3);
+        assert!(recommendations[0].tuning.offsets[5] == -2);
+        assert_eq!(recommendations[0].open_bass_beats, 5);
+        assert!(recommendations
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.score <= b.score));
+    }
+    #[test]
+    fn skips_unplayable_tunings() {
+        // D2 is only playable with a dropped 6th string.
+        let input_lines = parse_lines("D2".to_owned()).unwrap();
+
+        let recommendations = recommend_tunings(input_lines, 18, 0, 20, 1, 0, false).unwrap();
+
+        assert!(recommendations
+            .iter()
+            .all(|recommendation| recommendation.tuning.offsets[5] <= -2));
+        assert!(recommendations
+            .iter()
+            .all(|recommendation| recommendation.tuning.name != "standard"));
+    }
+    #[test]
+    fn unplayable_in_all_tunings() {
+        let input_lines = parse_lines("C1".to_owned()).unwrap();
+
+        let error = recommend_tunings(input_lines, 18, 0, 3, 1, 0, true).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "The input cannot be played in any of the candidate tunings."
+        );
+    }
+    #[test]
+    fn zero_recommendations_requested() {
+        let input_lines = parse_lines("E2".to_owned()).unwrap();
+
+        let error = recommend_tunings(input_lines, 18, 0, 0, 1, 0, false).unwrap_err();
+        assert_eq!(
+            format!("{error}"),
+            "No tuning recommendations were requested."
+        );
+    }
+    #[test]
+    fn zero_arrangements_requested() {
+        let input_lines = parse_lines("E2".to_owned()).unwrap();
+
+        let error = recommend_tunings(input_lines, 18, 0, 3, 0, 0, false).unwrap_err();
+        assert_eq!(error, Error::NoArrangementsRequested);
+    }
+    #[test]
+    fn too_many_arrangements_requested() {
+        // The limit is checked even when no tuning can play the input.
+        let input_lines = parse_lines("C1".to_owned()).unwrap();
+
+        let error = recommend_tunings(input_lines, 18, 0, 3, 21, 0, false).unwrap_err();
+        assert_eq!(error, Error::TooManyArrangementsRequested { max: 20 });
+    }
+}
+
+/// Returns whether an arranger error only reports pitches the tuning cannot play.
+fn is_unplayable_error(error: &Error) -> bool {
+    match error {
+        Error::ImpossiblePitch { .. } => true,
+        Error::Multiple { errors } => errors.iter().all(is_unplayable_error),
+        _ => false,
+    }
+}
+#[cfg(test)]
+mod test_is_unplayable_error {
+    use super::*;
+
+    #[test]
+    fn impossible_pitches() {
+        let error = Error::ImpossiblePitch {
+            pitch: Pitch::C1,
+            line_number: 1,
+            span: None,
+        };
+        assert!(is_unplayable_error(&error));
+        assert!(is_unplayable_error(&Error::Multiple {
+            errors: vec![error.clone(), error]
+        }));
+    }
+    #[test]
+    fn other_errors() {
+        assert!(!is_unplayable_error(&Error::NoArrangementsRequested));
+        assert!(!is_unplayable_error(&Error::Multiple {
+            errors: vec![
+                Error::ImpossiblePitch {
+                    pitch: Pitch::C1,
+                    line_number: 1,
+                    span: None,
+                },
+                Error::NoArrangementsRequested,
+            ]
+        }));
+    }
+}
+
+/// Returns the lowest pitch of every playable line.
+fn bass_pitches(input_lines: &[Line<BeatVec<Pitch>>]) -> Vec<Pitch> {
+    input_lines
+        .iter()
+        .filter_map(|line| match line {
+            Line::Playable(pitches) => pitches.iter().min().copied(),
+            _ => None,
+        })
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_bass_pitches {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let input_lines = vec![
+            Line::Playable(vec![Pitch::E4, Pitch::A2]),
+            Line::Rest,
+            Line::MeasureBreak,
+            Line::Playable(vec![Pitch::G3]),
+        ];
+        assert_eq!(bass_pitches(&input_lines), vec![Pitch::A2, Pitch::G3]);
+    }
+}
+
+fn count_open_bass_beats(guitar: &Guitar, bass_pitches: &[Pitch]) -> u16 {
+    bass_pitches
+        .iter()
+        .filter(|bass_pitch| guitar.tuning.values().contains(bass_pitch))
+        .count() as u16
+}
+
+/// Lists the standard tuning and the named tunings, followed by the generated variants if
+/// requested. Generated variants with the same offsets as an earlier candidate are omitted.
+///
+/// The generated variants are the drop variant (6th string lowered by a whole step) of every
+/// named tuning whose 6th string is not already dropped below a fourth under the 5th string, and
+/// the open major tunings rooted on the most frequent bass pitch classes. Drop variants are named
+/// by their open strings from the 6th string to the 1st (e.g. `CGDGBD`).
+pub fn candidate_tunings(bass_pitches: &[Pitch], include_generated: bool) -> Vec<TuningCandidate> {
+    let mut candidates = vec![TuningCandidate {
+        name: "standard".to_owned(),
+        offsets: [0, 0, 0, 0, 0, 0],
+        generated: false,
+    }];
+    candidates.extend(TuningName::VARIANTS.iter().map(|&name| TuningCandidate {
+        name: name.to_owned(),
+        offsets: parse_tuning(name),
+        generated: false,
+    }));
+
+    if !include_generated {
+        return candidates;
+    }
+
+    let drop_variants = candidates
+        .iter()
+        .filter(|candidate| candidate.offsets[5] >= candidate.offsets[4])
+        .map(|candidate| {
+            let mut offsets = candidate.offsets;
+            offsets[5] -= 2;
+            TuningCandidate {
+                name: open_string_names(offsets),
+                offsets,
+                generated: true,
+            }
+        })
+        .collect_vec();
+
+    let open_variants = most_frequent_pitch_classes(bass_pitches)
+        .into_iter()
+        .take(NUM_GENERATED_OPEN_ROOTS)
+        .map(|root_pitch_class| TuningCandidate {
+            name: format!("Open{}", pitch_class_name(root_pitch_class)),
+            offsets: open_tuning_offsets(root_pitch_class),
+            generated: true,
+        })
+        .collect_vec();
+
+    for variant in drop_variants.into_iter().chain(open_variants) {
+        if !candidates
+            .iter()
+            .any(|candidate| candidate.offsets == variant.offsets)
+        {
+            candidates.push(variant);
+        }
+    }
+
+    candidates
+}
+#[cfg(test)]
+mod test_candidate_tunings {
+    use super::*;
+
+    #[test]
+    fn named_only() {
+        let candidates = candidate_tunings(&[], false);
+        assert_eq!(candidates.len(), TuningName::VARIANTS.len() + 1);
+        assert!(candidates.iter().all(|candidate| !candidate.generated));
+    }
+    #[test]
+    fn generated_variants_are_unique() {
+        let candidates = candidate_tunings(&[Pitch::D2, Pitch::D3, Pitch::A2], true);
+
+        assert!(candidates.iter().any(|candidate| candidate.generated));
+        assert!(candidates
+            .iter()
+            .map(|candidate| candidate.offsets)
+            .all_unique());
+        // Standard tuning dropped is already known as DropD
+        assert!(!candidates
+            .iter()
+            .any(|candidate| candidate.name == "DADGBE"));
+    }
+    #[test]
+    fn drop_variants() {
+        let drop_variants = candidate_tunings(&[], true)
+            .into_iter()
+            .filter(|candidate| candidate.generated)
+            .collect_vec();
+
+        assert_eq!(
+            drop_variants,
+            vec![TuningCandidate {
+                name: "CGDGBD".to_owned(),
+                offsets: [-2, 0, 0, 0, -2, -4],
+                generated: true,
+            }]
+        );
+    }
+}
+
+/// Names a tuning by the pitch classes of its open strings from the 6th string to the 1st.
+fn open_string_names(offsets: [i8; 6]) -> String {
+    create_string_tuning_offset(offsets)
+        .values()
+        .rev()
+        .map(|pitch| pitch_class_name(pitch.index() % 12))
+        .join("")
+}
+#[cfg(test)]
+mod test_open_string_names {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        assert_eq!(open_string_names([0, 0, 0, 0, 0, 0]), "EADGBE");
+        assert_eq!(open_string_names(parse_tuning("dadgad")), "DADGAD");
+    }
+}
+
+/// Returns the pitch classes (0 = C, 11 = B) of the pitches, ordered from the most to the least
+/// frequent.
+fn most_frequent_pitch_classes(pitches: &[Pitch]) -> Vec<u8> {
+    let mut counts: BTreeMap<u8, usize> = BTreeMap::new();
+    for pitch in pitches {
+        *counts.entry(pitch.index() % 12).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .sorted_by(|(a_class, a_count), (b_class, b_count)| {
+            b_count.cmp(a_count).then(a_class.cmp(b_class))
+        })
+        .map(|(pitch_class, _)| pitch_class)
+        .collect_vec()
+}
+#[cfg(test)]
+mod test_most_frequent_pitch_classes {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let pitches = [
+            Pitch::A2,
+            Pitch::D2,
+            Pitch::D3,
+            Pitch::E2,
+            Pitch::A3,
+            Pitch::D4,
+        ];
+        assert_eq!(most_frequent_pitch_classes(&pitches), vec![2, 9, 4]);
+    }
+}
+
+fn pitch_class_name(pitch_class: u8) -> String {
+    Pitch::from_repr(pitch_class as usize)
+        .expect("Pitch class should be a valid pitch index.")
+        .plain_text()
+        .trim_end_matches(char::is_numeric)
+        .to_owned()
+}
+
+/// Calculates the offsets of the open major tuning (root, fifth, third, root, fifth, root from
+/// the 1st string to the 6th string) rooted on the pitch class, moving each string by at most
+/// half an octave from standard tuning.
+fn open_tuning_offsets(root_pitch_class: u8) -> [i8; 6] {
+    const STRING_INTERVALS: [u8; 6] = [0, 7, 4, 0, 7, 0];
+
+    let mut offsets = [0; 6];
+    for (string_index, std_pitch) in STD_6_STRING_TUNING_OPEN_PITCHES.iter().enumerate() {
+        let target_pitch_class = (root_pitch_class + STRING_INTERVALS[string_index]) % 12;
+        let up_offset = (target_pitch_class + 12 - std_pitch.index() % 12) % 12;
+        offsets[string_index] = match up_offset {
+            0..=5 => up_offset as i8,
+            _ => up_offset as i8 - 12,
+        };
+    }
+    offsets
+}
+#[cfg(test)]
+mod test_open_tuning_offsets {
+    use super::*;
+
+    #[test]
+    fn open_d() {
+        assert_eq!(open_tuning_offsets(2), parse_tuning("opend"));
+    }
+    #[test]
+    fn open_e() {
+        assert_eq!(open_tuning_offsets(4), parse_tuning("opene"));
+    }
+}